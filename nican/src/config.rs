@@ -0,0 +1,87 @@
+//! The typed configuration of [`NiCan`], it's converted to the options of [`DeviceBuilder`].
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use rs_can::{AnyCanDevice, CanError, CanFilter, ChannelConfig, DeviceBuilder, DeviceConfig, registry::{self, DeviceUri}};
+use crate::{NiCan, FILTERS, LIBPATH, LOG_ERROR};
+
+/// The name of [`NiCan`] backend in [`registry`].
+pub const NICAN: &str = "nican";
+
+/// The typed configuration of a NI-CAN channel.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NiCanChannelConfig {
+    pub bitrate: u32,
+    pub filters: Option<Vec<CanFilter>>,
+    /// Receive the error frames of bus.
+    pub log_error: Option<bool>,
+}
+
+impl From<&NiCanChannelConfig> for ChannelConfig {
+    fn from(cfg: &NiCanChannelConfig) -> Self {
+        let mut config = ChannelConfig::new(cfg.bitrate);
+        if let Some(v) = &cfg.filters {
+            config.add_other(FILTERS, Box::new(v.clone()));
+        }
+        if let Some(v) = cfg.log_error {
+            config.add_other(LOG_ERROR, Box::new(v));
+        }
+
+        config
+    }
+}
+
+/// The typed configuration of [`NiCan`], the channels are keyed by interface name, like `CAN0`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NiCanConfig {
+    /// The path of NI-CAN library, `Nican.dll` is loaded from the search path if `None`.
+    pub libpath: Option<String>,
+    pub channels: HashMap<String, NiCanChannelConfig>,
+}
+
+impl DeviceConfig for NiCanConfig {
+    type Device = NiCan;
+
+    fn builder(&self) -> DeviceBuilder {
+        let mut builder = DeviceBuilder::new();
+        if let Some(libpath) = &self.libpath {
+            builder.add_other(LIBPATH, Box::new(libpath.clone()));
+        }
+        for (chl, cfg) in &self.channels {
+            builder.add_config(chl.as_str(), cfg.into());
+        }
+
+        builder
+    }
+}
+
+impl NiCanConfig {
+    /// The configuration of device URI, like `nican://CAN0?bitrate=500000&log_error`.
+    ///
+    /// The channels share the parameters `bitrate` and `log_error`, and the library is at `libpath`.
+    pub fn from_uri(uri: &DeviceUri) -> Result<Self, CanError> {
+        let channel = NiCanChannelConfig {
+            bitrate: uri.get("bitrate")?.unwrap_or_default(),
+            filters: None,
+            log_error: uri.get("log_error")?,
+        };
+
+        Ok(Self {
+            libpath: uri.param("libpath").map(|v| v.to_owned()),
+            channels: uri.channels().iter()
+                .map(|c| (c.clone(), channel.clone()))
+                .collect(),
+        })
+    }
+}
+
+/// Register [`NiCan`] to [`registry`] as [`NICAN`].
+pub fn register() {
+    registry::register(NICAN, open);
+}
+
+fn open(uri: &DeviceUri) -> Result<Box<dyn AnyCanDevice>, CanError> {
+    Ok(Box::new(NiCanConfig::from_uri(uri)?.build()?))
+}
@@ -394,7 +394,7 @@ mod tests {
     use crate::CanMessage;
     use std::time::Duration;
     use rs_can::{CanDevice, CanFrame, CanId};
-    // use rs_can::isotp::IsoTpAdapter;
+    use rs_can::isotp::{IsoTp, IsoTpConfig};
 
     #[ignore]   // device required
     #[test]
@@ -428,34 +428,32 @@ mod tests {
         Ok(())
     }
 
-    // #[ignore]   // device required
-    // #[test]
-    // fn isotp() -> anyhow::Result<()> {
-    //     let channel = "CAN0";
-    //     let mut driver = NiCan::new(None)?;
-    //     driver.open(channel, vec![], 500_000, true)?;
-    //
-    //     let mut adapter = IsoTpAdapter::new(driver.clone());
-    //     adapter.start(100);
-    //
-    //     let data = vec![0x02, 0x10, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00];
-    //     let mut count = 0;
-    //     loop {
-    //         let mut msg = CanMessage::new(CanId::from(0x7DF), data.as_slice()).unwrap();
-    //         msg.set_channel(channel.into());
-    //         adapter.sender().send(msg)?;
-    //
-    //         std::thread::sleep(Duration::from_millis(100));
-    //
-    //         count += 1;
-    //         if count > 10 {
-    //             break;
-    //         }
-    //     }
-    //
-    //     adapter.stop();
-    //     driver.shutdown();
-    //
-    //     Ok(())
-    // }
+    #[ignore]   // device required
+    #[test]
+    fn isotp() -> anyhow::Result<()> {
+        let channel = "CAN0";
+        let mut driver = NiCan::new(None)?;
+        driver.open(channel, vec![], 500_000, true)?;
+
+        let mut config = IsoTpConfig::new(0x7E0, 0x7E8);
+        config.set_functional_id(0x7DF);
+        let mut isotp = IsoTp::new(driver.clone(), channel.into(), config);
+
+        let mut count = 0;
+        loop {
+            isotp.send_functional(&[0x10, 0x01])?;
+            if let Ok(data) = isotp.receive(Some(100)) {
+                println!("{:02X?}", data);
+            }
+
+            count += 1;
+            if count > 10 {
+                break;
+            }
+        }
+
+        driver.shutdown();
+
+        Ok(())
+    }
 }
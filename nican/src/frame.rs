@@ -1,4 +1,4 @@
-use rs_can::{{CanDirect, CanFrame, CanId}, can_utils, CanType};
+use rs_can::{{CanDirect, CanError, CanFrame, CanId}, can_utils, CanType};
 use std::fmt::{Display, Formatter};
 
 #[repr(C)]
@@ -179,3 +179,35 @@ impl Display for CanMessage {
         <dyn CanFrame<Channel = String> as Display>::fmt(self, f)
     }
 }
+
+impl From<CanMessage> for rs_can::CanMessage {
+    #[inline]
+    fn from(msg: CanMessage) -> Self {
+        Self::from_frame(&msg)
+    }
+}
+
+/// Only the classic CAN frame is supported by NI-CAN.
+impl TryFrom<rs_can::CanMessage> for CanMessage {
+    type Error = CanError;
+
+    fn try_from(msg: rs_can::CanMessage) -> Result<Self, Self::Error> {
+        if msg.can_type() != CanType::Can || msg.length() > 8 {
+            return Err(CanError::OperationError(format!("{:?} frame not supported", msg.can_type())));
+        }
+
+        Ok(Self {
+            timestamp: msg.timestamp(),
+            arbitration_id: msg.id().into_bits(),
+            is_extended_id: msg.is_extended(),
+            is_remote_frame: msg.is_remote(),
+            is_error_frame: msg.is_error_frame(),
+            channel: msg.channel(),
+            length: msg.length(),
+            data: msg.data().to_vec(),
+            direct: msg.direct(),
+            bitrate_switch: msg.is_bitrate_switch(),
+            error_state_indicator: msg.is_esi(),
+        })
+    }
+}
@@ -1,6 +1,8 @@
 mod api;
 mod constant;
 
+mod config;
+pub use config::*;
 mod constants;
 pub use constants::*;
 mod driver;
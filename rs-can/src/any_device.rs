@@ -0,0 +1,92 @@
+//! Object-safe device for the backends chosen at runtime.
+//!
+//! Every [`Device`] whose channel could be parsed from string and frame converted to [`Message`]
+//! is an [`AnyDevice`], the frames received are converted by the backend without losing precision
+//! of timestamp, and the channels are named by string.
+
+use std::{any::Any, str::FromStr};
+use crate::device::Device;
+use crate::error::Error;
+use crate::frame::{Frame, Message};
+
+pub trait AnyDevice: Send + Sync {
+    /// The concrete device, it's downcast to use the API of backend.
+    fn as_any(&self) -> &dyn Any;
+    fn is_closed(&self) -> bool;
+    /// get all channels that has opened
+    fn opened_channels(&self) -> Vec<String>;
+    /// Transmit a CAN, CAN-FD or CAN XL frame, the channel is taken from frame.
+    fn transmit(&self, msg: Message, timeout: Option<u32>) -> Result<(), Error>;
+    /// Receive CAN, CAN-FD and CAN XL frames.
+    fn receive(&self, channel: &str, timeout: Option<u32>) -> Result<Vec<Message>, Error>;
+    /// Close device.
+    fn shutdown(&mut self);
+}
+
+impl<D> AnyDevice for D
+where
+    D: Device + Send + Sync + 'static,
+    D::Channel: FromStr,
+    D::Frame: Into<Message>,
+{
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn is_closed(&self) -> bool {
+        Device::is_closed(self)
+    }
+
+    fn opened_channels(&self) -> Vec<String> {
+        Device::opened_channels(self).iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    fn transmit(&self, msg: Message, timeout: Option<u32>) -> Result<(), Error> {
+        let channel = parse_channel::<D::Channel>(&msg.channel)?;
+        Device::transmit(self, convert(&msg, channel)?, timeout)
+    }
+
+    fn receive(&self, channel: &str, timeout: Option<u32>) -> Result<Vec<Message>, Error> {
+        Ok(Device::receive(self, parse_channel(channel)?, timeout)?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    #[inline]
+    fn shutdown(&mut self) {
+        Device::shutdown(self)
+    }
+}
+
+#[inline]
+fn parse_channel<C: FromStr>(channel: &str) -> Result<C, Error> {
+    channel.parse()
+        .map_err(|_| Error::OperationError(format!("invalid channel: {}", channel)))
+}
+
+/// Convert to the frame of backend with all fields of [`Frame`].
+fn convert<T: Frame>(src: &Message, channel: T::Channel) -> Result<T, Error> {
+    let frame = if src.is_remote() {
+        T::new_remote(src.id(), src.length())
+    }
+    else {
+        T::new(src.id(), src.data())
+    };
+    let mut frame = frame
+        .ok_or_else(|| Error::OperationError(format!("invalid frame: {:08X}", src.id().into_bits())))?;
+    frame.set_timestamp(Some(src.timestamp()))
+        .set_can_type(src.can_type())
+        .set_direct(src.direct())
+        .set_bitrate_switch(src.is_bitrate_switch())
+        .set_error_frame(src.is_error_frame())
+        .set_esi(src.is_esi())
+        .set_xl_control(src.xl_control())
+        .set_channel(channel);
+
+    Ok(frame)
+}
@@ -0,0 +1,586 @@
+//! Vector ASC log file.
+//!
+//! [`AscWriter`] writes CANalyzer/CANoe loadable files, and [`AscReader`] parses
+//! CAN, CAN-FD, CAN XL, remote and error frames of it back into any [`Frame`].
+//!
+//! The timestamp of frame is milliseconds since UNIX epoch,
+//! and the date of ASC header is treated as UTC.
+
+use std::{collections::HashMap, fmt::{self, Display}, fs::File, io::{BufRead, BufReader, BufWriter, Write}, marker::PhantomData, path::Path, str::FromStr};
+use crate::error::Error;
+use crate::frame::{Direct, Frame, Id, Type, XlControl};
+use crate::utils;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// The number base of identifiers and data bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Base {
+    #[default]
+    Hex,
+    Dec,
+}
+
+/// The timestamps of events.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamps {
+    /// The offset to start of measurement.
+    #[default]
+    Absolute,
+    /// The offset to the previous event.
+    Relative,
+}
+
+/// Format date as `Wed Jun 13 10:21:00.123 am 2018`.
+pub(crate) fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400_000) as i64;
+    let ms_of_day = timestamp % 86_400_000;
+    let (year, month, day) = utils::civil_from_days(days);
+    let (hour, minute, second, ms) = (
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000,
+    );
+
+    format!("{} {} {:02} {:02}:{:02}:{:02}.{:03} {} {}",
+            WEEKDAYS[(days + 4).rem_euclid(7) as usize],
+            MONTHS[month as usize - 1],
+            day,
+            match hour % 12 { 0 => 12, v => v },
+            minute,
+            second,
+            ms,
+            if hour < 12 { "am" } else { "pm" },
+            year,
+    )
+}
+
+/// Parse date like `Wed Jun 13 10:21:00.123 am 2018` or `Wed Jun 13 22:21:00 2018`.
+pub(crate) fn parse_date(date: &str) -> Option<u64> {
+    let mut tokens = date.split_whitespace()
+        .skip_while(|t| !MONTHS.iter().any(|m| t.eq_ignore_ascii_case(m)));
+    let month = tokens.next()
+        .and_then(|t| MONTHS.iter().position(|m| t.eq_ignore_ascii_case(m)))? as u32 + 1;
+    let day = tokens.next()?.parse::<u32>().ok()?;
+    let mut time = tokens.next()?.split(':');
+    let mut hour = time.next()?.parse::<u64>().ok()?;
+    let minute = time.next()?.parse::<u64>().ok()?;
+    let second = time.next()?;
+    let (second, ms) = match second.split_once('.') {
+        Some((s, ms)) => (s.parse::<u64>().ok()?, format!("{:0<3}", ms)[..3].parse::<u64>().ok()?),
+        None => (second.parse::<u64>().ok()?, 0),
+    };
+    let mut token = tokens.next()?;
+    if token.eq_ignore_ascii_case("am") || token.eq_ignore_ascii_case("pm") {
+        hour %= 12;
+        if token.eq_ignore_ascii_case("pm") {
+            hour += 12;
+        }
+        token = tokens.next()?;
+    }
+    let year = token.parse::<i64>().ok()?;
+
+    let days = utils::days_from_civil(year, month, day);
+    if days < 0 {
+        return None;
+    }
+
+    Some(days as u64 * 86_400_000 + ((hour * 60 + minute) * 60 + second) * 1000 + ms)
+}
+
+/// Output a frame as `asc` line without line ending.
+pub(crate) fn fmt_frame<T: Display>(
+    f: &mut dyn fmt::Write,
+    frame: &dyn Frame<Channel = T>,
+    timestamp: f64,
+    channel: &dyn Display,
+    base: Base,
+) -> fmt::Result {
+    let number = |v: u32| match base {
+        Base::Hex => format!("{:X}", v),
+        Base::Dec => format!("{}", v),
+    };
+    let id = format!("{}{}", number(frame.id().into_bits()), if frame.is_extended() { "x" } else { "" });
+    let data = frame.data()
+        .iter()
+        .map(|&b| match base {
+            Base::Hex => format!("{:02X}", b),
+            Base::Dec => format!("{}", b),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if frame.is_error_frame() {
+        return write!(f, "{:>11.6} {} ErrorFrame", timestamp, channel);
+    }
+
+    match frame.can_type() {
+        Type::Can => {
+            write!(f, "{:>11.6} {:<2} {:<15} {:<4} ", timestamp, channel, id, frame.direct())?;
+            if frame.is_remote() {
+                write!(f, "r {}", number(frame.length() as u32))
+            }
+            else {
+                write!(f, "d {} {}", number(frame.dlc() as u32), data)
+            }
+        },
+        Type::CanFd => {
+            let mut flags = 1 << 12;
+            if frame.is_bitrate_switch() {
+                flags |= 1 << 13;
+            }
+            if frame.is_esi() {
+                flags |= 1 << 14;
+            }
+            write!(f, "{:>11.6} CANFD {:>3} {:<4} {:>8} {} {} {:X} {:>2} {} {:>8} {:>4} {:>8X} {:>8} {:>8} {:>8} {:>8} {:>8}",
+                   timestamp,
+                   channel,
+                   frame.direct(),
+                   id,
+                   frame.is_bitrate_switch() as u8,
+                   frame.is_esi() as u8,
+                   utils::dlc_code(frame.length()),
+                   frame.length(),
+                   data,
+                   0,       // message_duration
+                   0,       // message_length
+                   flags,
+                   0,       // crc
+                   0,       // bit_timing_conf_arb
+                   0,       // bit_timing_conf_data
+                   0,       // bit_timing_conf_ext_arb
+                   0,       // bit_timing_conf_ext_data
+            )
+        },
+        Type::CanXl => {
+            // <Id> <SDT> <SEC> <VCID> <AF> <DataLength> <Data>
+            let control = frame.xl_control();
+            write!(f, "{:>11.6} CANXL {:>3} {:<4} {:>8} {:02X} {} {:02X} {:08X} {:>4} {}",
+                   timestamp,
+                   channel,
+                   frame.direct(),
+                   id,
+                   control.sdu_type,
+                   control.sec as u8,
+                   control.vcid,
+                   control.acceptance_field,
+                   frame.length(),
+                   data,
+            )
+        },
+    }
+}
+
+/// ASC file writer.
+///
+/// The header is written when the first frame is written,
+/// and the footer is written by [`AscWriter::finish`] or when the writer is dropped.
+pub struct AscWriter<W: Write> {
+    writer: W,
+    base: Base,
+    timestamps: Timestamps,
+    start: Option<u64>,
+    last: u64,
+    channels: HashMap<String, u8>,
+    started: bool,
+    finished: bool,
+}
+
+impl AscWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::create(path)
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> AscWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            base: Default::default(),
+            timestamps: Default::default(),
+            start: Default::default(),
+            last: Default::default(),
+            channels: Default::default(),
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Set the number base, it's ignored after first frame written.
+    pub fn set_base(&mut self, base: Base) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// Set the timestamps type, it's ignored after first frame written.
+    pub fn set_timestamps(&mut self, timestamps: Timestamps) -> &mut Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Set start of measurement, the timestamp of first frame is used by default.
+    pub fn set_start(&mut self, timestamp: u64) -> &mut Self {
+        self.start = Some(timestamp);
+        self
+    }
+
+    /// Map a frame channel to ASC channel number.
+    ///
+    /// The channels that are not mapped are numbered from 1 in order of appearance.
+    pub fn set_channel<C: Display>(&mut self, channel: C, number: u8) -> &mut Self {
+        self.channels.insert(channel.to_string(), number);
+        self
+    }
+
+    pub fn write<T: Display>(&mut self, frame: &dyn Frame<Channel = T>) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::operation_error("asc writer is finished"));
+        }
+
+        let timestamp = frame.timestamp();
+        let start = *self.start.get_or_insert(timestamp);
+        if !self.started {
+            self.write_header(start)?;
+            self.last = start;
+        }
+
+        let offset = match self.timestamps {
+            Timestamps::Absolute => timestamp.saturating_sub(start),
+            Timestamps::Relative => timestamp.saturating_sub(self.last),
+        };
+        self.last = timestamp;
+
+        let channel = frame.channel().to_string();
+        let next = self.channels.values().max().map_or(1, |v| v.saturating_add(1));
+        let number = *self.channels.entry(channel).or_insert(next);
+
+        let mut line = String::new();
+        fmt_frame(&mut line, frame, offset as f64 / 1000., &number, self.base)
+            .map_err(|e| Error::OtherError(e.to_string()))?;
+        writeln!(self.writer, "{}", line)
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+
+    /// Write the footer and flush the writer.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if !self.started {
+            self.write_header(self.start.unwrap_or_else(utils::system_timestamp))?;
+        }
+        writeln!(self.writer, "End TriggerBlock")
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn write_header(&mut self, start: u64) -> Result<(), Error> {
+        self.started = true;
+        let date = format_date(start);
+        write!(self.writer, "date {}\nbase {}  timestamps {}\ninternal events logged\n// version 9.0.0\nBegin Triggerblock {}\n{:>11.6} Start of measurement\n",
+               date,
+               match self.base { Base::Hex => "hex", Base::Dec => "dec" },
+               match self.timestamps { Timestamps::Absolute => "absolute", Timestamps::Relative => "relative" },
+               date,
+               0.,
+        )
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+}
+
+impl<W: Write> Drop for AscWriter<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::warn!("RUST-CAN - {} when asc writer dropped", e);
+        }
+    }
+}
+
+/// ASC file reader, iterating frames that parsed from file.
+///
+/// The ASC channel number is converted to frame channel by `FromStr`
+/// unless it's mapped by [`AscReader::set_channel`].
+/// The lines other than CAN, CAN-FD and error frames are skipped.
+pub struct AscReader<R: BufRead, F: Frame> {
+    reader: R,
+    base: Base,
+    timestamps: Timestamps,
+    start: u64,
+    last: f64,
+    line_no: usize,
+    channels: HashMap<u8, F::Channel>,
+    _frame: PhantomData<F>,
+}
+
+impl<F: Frame> AscReader<BufReader<File>, F> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead, F: Frame> AscReader<R, F> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            base: Default::default(),
+            timestamps: Default::default(),
+            start: Default::default(),
+            last: Default::default(),
+            line_no: Default::default(),
+            channels: Default::default(),
+            _frame: Default::default(),
+        }
+    }
+
+    /// Map an ASC channel number to frame channel.
+    pub fn set_channel(&mut self, number: u8, channel: F::Channel) -> &mut Self {
+        self.channels.insert(number, channel);
+        self
+    }
+
+    /// The start of measurement parsed from header.
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    #[inline]
+    pub fn base(&self) -> Base {
+        self.base
+    }
+
+    #[inline]
+    pub fn timestamps(&self) -> Timestamps {
+        self.timestamps
+    }
+
+    fn parse_header(&mut self, line: &str) -> bool {
+        let lower = line.to_ascii_lowercase();
+        if let Some(date) = lower.strip_prefix("date ") {
+            match parse_date(date) {
+                Some(v) => self.start = v,
+                None => log::warn!("RUST-CAN - asc date: `{}` is not supported", date),
+            }
+        }
+        else if lower.starts_with("base ") {
+            let mut tokens = lower.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token {
+                    "base" => if tokens.next() == Some("dec") { self.base = Base::Dec } else { self.base = Base::Hex },
+                    "timestamps" => if tokens.next() == Some("relative") {
+                        self.timestamps = Timestamps::Relative
+                    } else {
+                        self.timestamps = Timestamps::Absolute
+                    },
+                    _ => {},
+                }
+            }
+        }
+        else if let Some(date) = lower.strip_prefix("begin triggerblock") {
+            if let Some(v) = parse_date(date) {
+                self.start = v;
+            }
+            self.last = 0.;
+        }
+        else if !lower.starts_with("end triggerblock") && !lower.contains("events logged") {
+            return false;
+        }
+
+        true
+    }
+
+    fn parse_number(&self, s: &str) -> Option<u32> {
+        match self.base {
+            Base::Hex => u32::from_str_radix(s, 16).ok(),
+            Base::Dec => s.parse().ok(),
+        }
+    }
+
+    fn parse_id(&self, s: &str) -> Option<Id> {
+        match s.strip_suffix(['x', 'X']) {
+            Some(v) => Some(Id::from_bits(self.parse_number(v)?, Some(true))),
+            None => Some(Id::from_bits(self.parse_number(s)?, Some(false))),
+        }
+    }
+
+    fn parse_data(&self, tokens: &mut dyn Iterator<Item = &str>, len: usize) -> Option<Vec<u8>> {
+        (0..len)
+            .map(|_| self.parse_number(tokens.next()?).and_then(|v| u8::try_from(v).ok()))
+            .collect()
+    }
+
+    fn channel(&self, s: &str) -> Option<F::Channel>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let number = s.parse::<u8>().ok()?;
+        match self.channels.get(&number) {
+            Some(v) => Some(v.clone()),
+            None => number.to_string().parse().ok(),
+        }
+    }
+
+    fn parse_event(&mut self, line: &str) -> Option<Result<F, Error>>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let mut tokens = line.split_whitespace();
+        let offset = tokens.next()?.parse::<f64>().ok()?;
+        let token = tokens.next()?;
+        let fd = token.eq_ignore_ascii_case("CANFD");
+        let xl = token.eq_ignore_ascii_case("CANXL");
+        let channel = if fd || xl { tokens.next()? } else { token };
+        let channel = self.channel(channel)?;
+
+        let timestamp = match self.timestamps {
+            Timestamps::Absolute => offset,
+            Timestamps::Relative => self.last + offset,
+        };
+        self.last = timestamp;
+        let timestamp = self.start + (timestamp * 1000.).round() as u64;
+
+        let error = || Error::OtherError(format!("invalid asc frame at line {}: `{}`", self.line_no, line));
+        let direct = |s: &str| if s.eq_ignore_ascii_case("Tx") { Direct::Transmit } else { Direct::Receive };
+
+        let mut frame = if xl {
+            let direct = direct(tokens.next()?);
+            let hex = |v: Option<&str>| v.and_then(|v| u32::from_str_radix(v, 16).ok());
+            let frame = self.parse_id(tokens.next()?)
+                .and_then(|id| {
+                    let control = XlControl {
+                        sdu_type: hex(tokens.next())? as u8,
+                        sec: tokens.next()? == "1",
+                        vcid: hex(tokens.next())? as u8,
+                        acceptance_field: hex(tokens.next())?,
+                    };
+                    let data = tokens.next()
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .and_then(|len| self.parse_data(&mut tokens, len))?;
+                    let mut frame = F::new(id, &data)?;
+                    frame.set_can_type(Type::CanXl)
+                        .set_xl_control(control)
+                        .set_direct(direct);
+                    Some(frame)
+                });
+            match frame {
+                Some(frame) => frame,
+                None => return Some(Err(error())),
+            }
+        }
+        else if fd {
+            let direct = direct(tokens.next()?);
+            let token = tokens.next()?;
+            if token.eq_ignore_ascii_case("ErrorFrame") {
+                Self::error_frame(direct)
+            }
+            else {
+                let id = match self.parse_id(token) {
+                    Some(v) => v,
+                    None => return Some(Err(error())),
+                };
+                let mut tokens = tokens.skip_while(|t| !t.chars().all(|c| c.is_ascii_digit()));
+                let brs = tokens.next() == Some("1");
+                let esi = tokens.next() == Some("1");
+                let _dlc = tokens.next();
+                let frame = tokens.next()
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .and_then(|len| self.parse_data(&mut tokens, len))
+                    .and_then(|data| F::new(id, &data));
+                match frame {
+                    Some(mut frame) => {
+                        frame.set_can_type(Type::CanFd)
+                            .set_bitrate_switch(brs)
+                            .set_esi(esi)
+                            .set_direct(direct);
+                        frame
+                    },
+                    None => return Some(Err(error())),
+                }
+            }
+        }
+        else {
+            let token = tokens.next()?;
+            if token.eq_ignore_ascii_case("ErrorFrame") {
+                Self::error_frame(Direct::Receive)
+            }
+            else {
+                let id = self.parse_id(token)?;
+                let direct = direct(tokens.next()?);
+                let frame = match tokens.next()? {
+                    "d" | "D" => tokens.next()
+                        .and_then(|v| self.parse_number(v))
+                        .and_then(|len| self.parse_data(&mut tokens, len as usize))
+                        .and_then(|data| F::new(id, &data)),
+                    "r" | "R" => {
+                        let len = tokens.next()
+                            .and_then(|v| self.parse_number(v))
+                            .unwrap_or_default();
+                        F::new_remote(id, len as usize)
+                    },
+                    _ => None,
+                };
+                match frame {
+                    Some(mut frame) => {
+                        frame.set_direct(direct);
+                        frame
+                    },
+                    None => return Some(Err(error())),
+                }
+            }
+        };
+
+        frame.set_timestamp(Some(timestamp))
+            .set_channel(channel);
+
+        Some(Ok(frame))
+    }
+
+    fn error_frame(direct: Direct) -> F {
+        let mut frame = F::new(Id::Standard(0), &[])
+            .expect("RUST-CAN - empty frame is always valid");
+        frame.set_error_frame(true)
+            .set_direct(direct);
+        frame
+    }
+}
+
+impl<R: BufRead, F: Frame> Iterator for AscReader<R, F>
+where
+    F::Channel: FromStr + Clone,
+{
+    type Item = Result<F, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_no += 1,
+                Err(e) => return Some(Err(Error::OperationError(e.to_string()))),
+            }
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") || self.parse_header(line) {
+                continue;
+            }
+
+            if let Some(result) = self.parse_event(line) {
+                return Some(result);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Asynchronous CAN device.
+//!
+//! [`AsyncDevice`] transmits and receives frames by futures, and provides a `Stream` of frames
+//! received on a channel and a `Sink` of frames to transmit. It's independent of async runtime,
+//! and the devices with blocking API only, like ZLG and NI, are adapted by [`BlockingAdapter`].
+
+use std::{collections::{HashMap, VecDeque}, fmt::Display, pin::Pin, sync::{mpsc, Arc, Mutex, MutexGuard, Weak}, thread};
+use futures::{channel::{mpsc as async_mpsc, oneshot}, future::BoxFuture, lock::Mutex as AsyncMutex, stream::{self, BoxStream}, FutureExt, Sink, StreamExt};
+use crate::device::Device;
+use crate::error::Error;
+use crate::frame::Frame;
+
+/// The timeout in milliseconds of each blocking receive by [`BlockingAdapter`],
+/// the receiving threads exit within it after the adapter dropped.
+pub const BLOCKING_POLL_INTERVAL: u32 = 50;
+
+pub trait AsyncDevice: Clone + Send + Sync + 'static {
+    type Channel: Display + Clone + Send + Sync + 'static;
+    type Frame: Frame<Channel = Self::Channel> + Send + 'static;
+
+    /// get all channels that has opened
+    fn opened_channels(&self) -> Vec<Self::Channel>;
+    /// Transmit a CAN, CAN-FD or CAN XL frame, it's resolved when the frame is queued by device.
+    fn transmit(&self, msg: Self::Frame) -> BoxFuture<'_, Result<(), Error>>;
+    /// Receive frames of channel, it's resolved when any frame received.
+    fn receive(&self, channel: Self::Channel) -> BoxFuture<'_, Result<Vec<Self::Frame>, Error>>;
+
+    /// The stream of frames received on channel, it's ended after an error.
+    fn stream(&self, channel: Self::Channel) -> BoxStream<'static, Result<Self::Frame, Error>> {
+        stream::unfold(Some((self.clone(), VecDeque::new())), move |state| {
+            let channel = channel.clone();
+            async move {
+                let (device, mut frames) = state?;
+                loop {
+                    if let Some(frame) = frames.pop_front() {
+                        return Some((Ok(frame), Some((device, frames))));
+                    }
+                    match device.receive(channel.clone()).await {
+                        Ok(v) => frames.extend(v),
+                        Err(e) => return Some((Err(e), None)),
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// The sink of frames to transmit, the frames are transmitted in order.
+    fn sink(&self) -> Pin<Box<dyn Sink<Self::Frame, Error = Error> + Send>> {
+        Box::pin(futures::sink::unfold(self.clone(), |device, frame| async move {
+            device.transmit(frame).await?;
+            Ok(device)
+        }))
+    }
+}
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+type Batch<F> = Result<Vec<F>, Error>;
+type Request<F> = (F, oneshot::Sender<Result<(), Error>>);
+type Receivers<F> = Mutex<HashMap<String, Arc<AsyncMutex<async_mpsc::UnboundedReceiver<Batch<F>>>>>>;
+
+/// Adapt the blocking [`Device`] to [`AsyncDevice`] by threads.
+///
+/// The frames are transmitted in order by a thread, and each channel is received by a thread
+/// since its first receive. The frames received are buffered until they're taken by
+/// [`AsyncDevice::receive`] or the stream, and the thread exits after an error reported.
+pub struct BlockingAdapter<D: Device> {
+    device: D,
+    transmitter: mpsc::Sender<Request<D::Frame>>,
+    receivers: Arc<Receivers<D::Frame>>,
+}
+
+impl<D: Device> Clone for BlockingAdapter<D> {
+    fn clone(&self) -> Self {
+        Self {
+            device: self.device.clone(),
+            transmitter: self.transmitter.clone(),
+            receivers: self.receivers.clone(),
+        }
+    }
+}
+
+impl<D> BlockingAdapter<D>
+where
+    D: Device + Send + Sync + 'static,
+    D::Channel: Clone + Send + Sync + 'static,
+    D::Frame: Send + 'static,
+{
+    /// Adapt the device, the frames are transmitted with `timeout` in milliseconds.
+    pub fn new(device: D, timeout: Option<u32>) -> Self {
+        let (transmitter, requests) = mpsc::channel::<Request<D::Frame>>();
+        let dev = device.clone();
+        thread::spawn(move || {
+            for (frame, result) in requests {
+                let _ = result.send(dev.transmit(frame, timeout));
+            }
+        });
+
+        Self { device, transmitter, receivers: Default::default() }
+    }
+
+    #[inline]
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    fn receiver(&self, channel: &D::Channel) -> Arc<AsyncMutex<async_mpsc::UnboundedReceiver<Batch<D::Frame>>>> {
+        let key = channel.to_string();
+        let mut receivers = lock(&self.receivers);
+        if let Some(receiver) = receivers.get(&key) {
+            return receiver.clone();
+        }
+
+        let (sender, receiver) = async_mpsc::unbounded();
+        let receiver = Arc::new(AsyncMutex::new(receiver));
+        receivers.insert(key.clone(), receiver.clone());
+
+        let device = self.device.clone();
+        let channel = channel.clone();
+        let registry: Weak<Receivers<D::Frame>> = Arc::downgrade(&self.receivers);
+        thread::spawn(move || {
+            while !sender.is_closed() {
+                match device.receive(channel.clone(), Some(BLOCKING_POLL_INTERVAL)) {
+                    Ok(frames) => {
+                        if !frames.is_empty() && sender.unbounded_send(Ok(frames)).is_err() {
+                            break;
+                        }
+                    },
+                    Err(Error::TimeoutError(_)) => {},
+                    Err(e) => {
+                        // the next receive starts a new thread.
+                        if let Some(registry) = registry.upgrade() {
+                            lock(&registry).remove(&key);
+                        }
+                        let _ = sender.unbounded_send(Err(e));
+                        break;
+                    },
+                }
+            }
+        });
+
+        receiver
+    }
+}
+
+impl<D> AsyncDevice for BlockingAdapter<D>
+where
+    D: Device + Send + Sync + 'static,
+    D::Channel: Clone + Send + Sync + 'static,
+    D::Frame: Send + 'static,
+{
+    type Channel = D::Channel;
+    type Frame = D::Frame;
+
+    #[inline]
+    fn opened_channels(&self) -> Vec<Self::Channel> {
+        self.device.opened_channels()
+    }
+
+    fn transmit(&self, msg: Self::Frame) -> BoxFuture<'_, Result<(), Error>> {
+        let (result, receiver) = oneshot::channel();
+        let sent = self.transmitter.send((msg, result));
+        async move {
+            sent.map_err(|_| Error::OtherError("transmitting thread exited".into()))?;
+            receiver.await
+                .map_err(|_| Error::OtherError("transmitting thread exited".into()))?
+        }
+        .boxed()
+    }
+
+    fn receive(&self, channel: Self::Channel) -> BoxFuture<'_, Result<Vec<Self::Frame>, Error>> {
+        let receiver = self.receiver(&channel);
+        async move {
+            match receiver.lock().await.next().await {
+                Some(batch) => batch,
+                None => Err(Error::channel_not_opened(&channel)),
+            }
+        }
+        .boxed()
+    }
+}
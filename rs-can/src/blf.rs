@@ -0,0 +1,656 @@
+//! Vector BLF(Binary Logging Format) file.
+//!
+//! [`BlfWriter`] writes `CAN_MESSAGE2`, `CAN_FD_MESSAGE_64` and `CAN_ERROR_EXT` objects
+//! into zlib compressed log containers, and [`BlfReader`] reads them(and `CAN_MESSAGE`, `CAN_FD_MESSAGE`)
+//! back into any [`Frame`].
+//!
+//! The timestamp of frame is milliseconds since UNIX epoch,
+//! and the start time of file header is treated as UTC.
+
+use std::{collections::{HashMap, VecDeque}, fmt::Display, fs::File, io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write}, marker::PhantomData, path::Path, str::FromStr};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use crate::error::Error;
+use crate::frame::{Direct, Frame, Id, Type};
+use crate::utils;
+
+const FILE_SIGNATURE: &[u8; 4] = b"LOGG";
+const OBJ_SIGNATURE: &[u8; 4] = b"LOBJ";
+const FILE_HEADER_SIZE: usize = 144;
+const OBJ_HEADER_BASE_SIZE: usize = 16;
+const OBJ_HEADER_V1_SIZE: usize = 16;
+const LOG_CONTAINER_HEADER_SIZE: usize = 16;
+const MAX_CONTAINER_SIZE: usize = 128 * 1024;
+/// Vector application ID, 5 is used by `python-can`.
+const APPLICATION_ID: u8 = 5;
+
+const CAN_MESSAGE: u32 = 1;
+const LOG_CONTAINER: u32 = 10;
+const CAN_ERROR_EXT: u32 = 73;
+const CAN_MESSAGE2: u32 = 86;
+const CAN_FD_MESSAGE: u32 = 100;
+const CAN_FD_MESSAGE_64: u32 = 101;
+
+const NO_COMPRESSION: u16 = 0;
+const ZLIB_DEFLATE: u16 = 2;
+
+const CAN_MSG_EXT: u32 = 0x8000_0000;
+const CAN_MSG_DIR: u8 = 0x01;
+const CAN_MSG_RTR: u8 = 0x80;
+const CAN_FD_MSG_EDL: u8 = 0x01;
+const CAN_FD_MSG_BRS: u8 = 0x02;
+const CAN_FD_MSG_ESI: u8 = 0x04;
+const CAN_FD_64_RTR: u32 = 0x0010;
+const CAN_FD_64_EDL: u32 = 0x1000;
+const CAN_FD_64_BRS: u32 = 0x2000;
+const CAN_FD_64_ESI: u32 = 0x4000;
+
+const TIME_TEN_MICS: u32 = 0x0000_0001;
+const TIME_ONE_NANS: u32 = 0x0000_0002;
+
+#[inline(always)]
+fn u16_at(buf: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([buf[pos], buf[pos + 1]])
+}
+
+#[inline(always)]
+fn u32_at(buf: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]])
+}
+
+#[inline(always)]
+fn u64_at(buf: &[u8], pos: usize) -> u64 {
+    (u32_at(buf, pos) as u64) | ((u32_at(buf, pos + 4) as u64) << 32)
+}
+
+#[inline(always)]
+fn io_error(e: std::io::Error) -> Error {
+    Error::OperationError(e.to_string())
+}
+
+/// Convert to `SYSTEMTIME`.
+fn to_system_time(timestamp: u64) -> [u16; 8] {
+    let days = (timestamp / 86_400_000) as i64;
+    let ms_of_day = timestamp % 86_400_000;
+    let (year, month, day) = utils::civil_from_days(days);
+    [
+        year as u16,
+        month as u16,
+        (days + 4).rem_euclid(7) as u16,
+        day as u16,
+        (ms_of_day / 3_600_000) as u16,
+        (ms_of_day / 60_000 % 60) as u16,
+        (ms_of_day / 1000 % 60) as u16,
+        (ms_of_day % 1000) as u16,
+    ]
+}
+
+/// Convert from `SYSTEMTIME`.
+fn from_system_time(buf: &[u8]) -> u64 {
+    let v = |i: usize| u16_at(buf, i * 2) as u64;
+    if v(0) == 0 {
+        return 0;
+    }
+
+    let days = utils::days_from_civil(v(0) as i64, v(1) as u32, v(3) as u32).max(0) as u64;
+    days * 86_400_000 + ((v(4) * 60 + v(5)) * 60 + v(6)) * 1000 + v(7)
+}
+
+/// BLF file writer.
+///
+/// The objects are buffered and written as log container when the container is full,
+/// and the file header is rewritten by [`BlfWriter::finish`] or when the writer is dropped.
+pub struct BlfWriter<W: Write + Seek> {
+    writer: W,
+    compression: Option<u32>,
+    channels: HashMap<String, u16>,
+    buffer: Vec<u8>,
+    start: Option<u64>,
+    stop: u64,
+    object_count: u32,
+    uncompressed_size: u64,
+    started: bool,
+    finished: bool,
+}
+
+impl BlfWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::create(path)
+            .map_err(io_error)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write + Seek> BlfWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            compression: Some(Compression::default().level()),
+            channels: Default::default(),
+            buffer: Default::default(),
+            start: Default::default(),
+            stop: Default::default(),
+            object_count: Default::default(),
+            uncompressed_size: FILE_HEADER_SIZE as u64,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Set zlib compression level(0~9) of log containers, `None` means no compression.
+    pub fn set_compression(&mut self, level: Option<u32>) -> &mut Self {
+        self.compression = level.map(|v| v.min(9));
+        self
+    }
+
+    /// Set start time of measurement, the timestamp of first frame is used by default.
+    pub fn set_start(&mut self, timestamp: u64) -> &mut Self {
+        self.start = Some(timestamp);
+        self
+    }
+
+    /// Map a frame channel to BLF channel number.
+    ///
+    /// The channels that are not mapped are numbered from 1 in order of appearance.
+    pub fn set_channel<C: Display>(&mut self, channel: C, number: u16) -> &mut Self {
+        self.channels.insert(channel.to_string(), number);
+        self
+    }
+
+    /// Write a frame, the CAN XL frames are not supported.
+    pub fn write<T: Display>(&mut self, frame: &dyn Frame<Channel = T>) -> Result<(), Error> {
+        if self.finished {
+            return Err(Error::operation_error("blf writer is finished"));
+        }
+        if frame.can_type() == Type::CanXl {
+            return Err(Error::NotSupportedError);
+        }
+
+        let timestamp = frame.timestamp();
+        let start = *self.start.get_or_insert(timestamp);
+        if !self.started {
+            self.write_header(0)?;
+        }
+        self.stop = self.stop.max(timestamp);
+
+        let channel = frame.channel().to_string();
+        let next = self.channels.values().max().map_or(1, |v| v.saturating_add(1));
+        let channel = *self.channels.entry(channel).or_insert(next);
+
+        let mut arb_id = frame.id().into_bits();
+        if frame.is_extended() {
+            arb_id |= CAN_MSG_EXT;
+        }
+        let tx = frame.direct() == Direct::Transmit;
+        let data = frame.data();
+        let length = data.len().min(frame.length());
+
+        let mut object = Vec::new();
+        let obj_type = if frame.is_error_frame() {
+            object.extend_from_slice(&channel.to_le_bytes());
+            object.extend_from_slice(&0u16.to_le_bytes());         // length
+            object.extend_from_slice(&0u32.to_le_bytes());         // flags
+            object.extend_from_slice(&[0, 0, length.min(8) as u8, 0]);  // ecc, position, dlc, reserved
+            object.extend_from_slice(&0u32.to_le_bytes());         // frame length
+            object.extend_from_slice(&arb_id.to_le_bytes());
+            object.extend_from_slice(&0u32.to_le_bytes());         // flags ext, reserved
+            let mut buf = [0u8; 8];
+            buf[..length.min(8)].copy_from_slice(&data[..length.min(8)]);
+            object.extend_from_slice(&buf);
+            CAN_ERROR_EXT
+        }
+        else if frame.can_type() == Type::CanFd {
+            let mut flags = CAN_FD_64_EDL;
+            if frame.is_bitrate_switch() {
+                flags |= CAN_FD_64_BRS;
+            }
+            if frame.is_esi() {
+                flags |= CAN_FD_64_ESI;
+            }
+            object.extend_from_slice(&[channel as u8, utils::dlc_code(length), length as u8, 0]);
+            object.extend_from_slice(&arb_id.to_le_bytes());
+            object.extend_from_slice(&0u32.to_le_bytes());         // frame length
+            object.extend_from_slice(&flags.to_le_bytes());
+            object.extend_from_slice(&[0u8; 16]);                   // bit timing and time offset
+            object.extend_from_slice(&0u16.to_le_bytes());         // bit count
+            object.extend_from_slice(&[if tx { 1 } else { 0 }, 0]);  // direction, ext data offset
+            object.extend_from_slice(&0u32.to_le_bytes());         // crc
+            object.extend_from_slice(&data[..length]);
+            CAN_FD_MESSAGE_64
+        }
+        else {
+            let mut flags = 0;
+            if tx {
+                flags |= CAN_MSG_DIR;
+            }
+            if frame.is_remote() {
+                flags |= CAN_MSG_RTR;
+            }
+            let length = if frame.is_remote() { 0 } else { length.min(8) };
+            object.extend_from_slice(&channel.to_le_bytes());
+            object.extend_from_slice(&[flags, frame.length().min(8) as u8]);
+            object.extend_from_slice(&arb_id.to_le_bytes());
+            let mut buf = [0u8; 8];
+            buf[..length].copy_from_slice(&data[..length]);
+            object.extend_from_slice(&buf);
+            object.extend_from_slice(&0u32.to_le_bytes());         // frame length
+            object.extend_from_slice(&[0, 0, 0, 0]);                // bit count, reserved
+            CAN_MESSAGE2
+        };
+
+        self.add_object(obj_type, &object, timestamp.saturating_sub(start) * 1_000_000);
+        if self.buffer.len() >= MAX_CONTAINER_SIZE {
+            self.flush_container()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the buffered objects and file header, then flush the writer.
+    pub fn finish(&mut self) -> Result<(), Error> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if !self.started {
+            self.write_header(0)?;
+        }
+        while !self.buffer.is_empty() {
+            self.flush_container()?;
+        }
+
+        let size = self.writer.stream_position()
+            .map_err(io_error)?;
+        self.writer.seek(SeekFrom::Start(0))
+            .map_err(io_error)?;
+        self.write_header(size)?;
+        self.writer.seek(SeekFrom::Start(size))
+            .and_then(|_| self.writer.flush())
+            .map_err(io_error)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn add_object(&mut self, obj_type: u32, data: &[u8], timestamp: u64) {
+        let header_size = OBJ_HEADER_BASE_SIZE + OBJ_HEADER_V1_SIZE;
+        let obj_size = header_size + data.len();
+        self.buffer.extend_from_slice(OBJ_SIGNATURE);
+        self.buffer.extend_from_slice(&(header_size as u16).to_le_bytes());
+        self.buffer.extend_from_slice(&1u16.to_le_bytes());        // header version
+        self.buffer.extend_from_slice(&(obj_size as u32).to_le_bytes());
+        self.buffer.extend_from_slice(&obj_type.to_le_bytes());
+        self.buffer.extend_from_slice(&TIME_ONE_NANS.to_le_bytes());
+        self.buffer.extend_from_slice(&0u16.to_le_bytes());        // client index
+        self.buffer.extend_from_slice(&0u16.to_le_bytes());        // object version
+        self.buffer.extend_from_slice(&timestamp.to_le_bytes());
+        self.buffer.extend_from_slice(data);
+        self.buffer.resize(self.buffer.len() + obj_size % 4, 0);
+        self.object_count += 1;
+    }
+
+    fn flush_container(&mut self) -> Result<(), Error> {
+        let size = self.buffer.len().min(MAX_CONTAINER_SIZE);
+        let tail = self.buffer.split_off(size);
+        let uncompressed = std::mem::replace(&mut self.buffer, tail);
+
+        let (method, data) = match self.compression {
+            Some(level) => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+                encoder.write_all(&uncompressed)
+                    .map_err(io_error)?;
+                (ZLIB_DEFLATE, encoder.finish().map_err(io_error)?)
+            },
+            None => (NO_COMPRESSION, uncompressed.clone()),
+        };
+
+        let obj_size = OBJ_HEADER_BASE_SIZE + LOG_CONTAINER_HEADER_SIZE + data.len();
+        let mut container = Vec::with_capacity(obj_size + 4);
+        container.extend_from_slice(OBJ_SIGNATURE);
+        container.extend_from_slice(&(OBJ_HEADER_BASE_SIZE as u16).to_le_bytes());
+        container.extend_from_slice(&1u16.to_le_bytes());
+        container.extend_from_slice(&(obj_size as u32).to_le_bytes());
+        container.extend_from_slice(&LOG_CONTAINER.to_le_bytes());
+        container.extend_from_slice(&method.to_le_bytes());
+        container.extend_from_slice(&[0u8; 6]);
+        container.extend_from_slice(&(uncompressed.len() as u32).to_le_bytes());
+        container.extend_from_slice(&[0u8; 4]);
+        container.extend_from_slice(&data);
+        container.resize(container.len() + obj_size % 4, 0);
+        self.writer.write_all(&container)
+            .map_err(io_error)?;
+
+        self.uncompressed_size += (OBJ_HEADER_BASE_SIZE + LOG_CONTAINER_HEADER_SIZE + uncompressed.len()) as u64;
+        Ok(())
+    }
+
+    fn write_header(&mut self, file_size: u64) -> Result<(), Error> {
+        self.started = true;
+        let start = self.start.unwrap_or_else(utils::system_timestamp);
+        let stop = self.stop.max(start);
+
+        let mut header = Vec::with_capacity(FILE_HEADER_SIZE);
+        header.extend_from_slice(FILE_SIGNATURE);
+        header.extend_from_slice(&(FILE_HEADER_SIZE as u32).to_le_bytes());
+        // application ID, version and bin log version
+        header.extend_from_slice(&[APPLICATION_ID, 0, 0, 0, 2, 6, 8, 1]);
+        header.extend_from_slice(&file_size.to_le_bytes());
+        header.extend_from_slice(&self.uncompressed_size.to_le_bytes());
+        header.extend_from_slice(&self.object_count.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());             // count of objects read
+        to_system_time(start).iter()
+            .chain(to_system_time(stop).iter())
+            .for_each(|v| header.extend_from_slice(&v.to_le_bytes()));
+        header.resize(FILE_HEADER_SIZE, 0);
+
+        self.writer.write_all(&header)
+            .map_err(io_error)
+    }
+}
+
+impl<W: Write + Seek> Drop for BlfWriter<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::warn!("RUST-CAN - {} when blf writer dropped", e);
+        }
+    }
+}
+
+/// BLF file reader, iterating frames that read from file.
+///
+/// The BLF channel number is converted to frame channel by `FromStr`
+/// unless it's mapped by [`BlfReader::set_channel`].
+/// The objects other than CAN, CAN-FD and error frames are skipped.
+pub struct BlfReader<R: Read, F: Frame> {
+    reader: R,
+    start: u64,
+    stop: u64,
+    object_count: u32,
+    started: bool,
+    buffer: Vec<u8>,
+    frames: VecDeque<F>,
+    channels: HashMap<u16, F::Channel>,
+    _frame: PhantomData<F>,
+}
+
+impl<F: Frame> BlfReader<BufReader<File>, F> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .map_err(io_error)?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: Read, F: Frame> BlfReader<R, F> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            start: Default::default(),
+            stop: Default::default(),
+            object_count: Default::default(),
+            started: false,
+            buffer: Default::default(),
+            frames: Default::default(),
+            channels: Default::default(),
+            _frame: Default::default(),
+        }
+    }
+
+    /// Map a BLF channel number to frame channel.
+    pub fn set_channel(&mut self, number: u16, channel: F::Channel) -> &mut Self {
+        self.channels.insert(number, channel);
+        self
+    }
+
+    /// The start time of measurement, it's available after first frame read.
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// The stop time of measurement, it's available after first frame read.
+    #[inline]
+    pub fn stop(&self) -> u64 {
+        self.stop
+    }
+
+    /// The count of objects in file header, it's available after first frame read.
+    #[inline]
+    pub fn object_count(&self) -> u32 {
+        self.object_count
+    }
+
+    fn read_header(&mut self) -> Result<(), Error> {
+        self.started = true;
+        let mut header = [0u8; FILE_HEADER_SIZE];
+        self.reader.read_exact(&mut header[..8])
+            .map_err(io_error)?;
+        if &header[..4] != FILE_SIGNATURE {
+            return Err(Error::other_error("invalid blf file signature"));
+        }
+
+        let header_size = (u32_at(&header, 4) as usize).max(8);
+        let mut rest = vec![0u8; header_size - 8];
+        self.reader.read_exact(&mut rest)
+            .map_err(io_error)?;
+        let size = rest.len().min(FILE_HEADER_SIZE - 8);
+        header[8..8 + size].copy_from_slice(&rest[..size]);
+
+        self.object_count = u32_at(&header, 32);
+        self.start = from_system_time(&header[40..56]);
+        self.stop = from_system_time(&header[56..72]);
+        Ok(())
+    }
+
+    /// Read next object from file, return `false` when end of file.
+    fn read_object(&mut self) -> Result<bool, Error>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let mut base = [0u8; OBJ_HEADER_BASE_SIZE];
+        match self.reader.read_exact(&mut base) {
+            Ok(_) => {},
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(io_error(e)),
+        }
+        if &base[..4] != OBJ_SIGNATURE {
+            return Err(Error::other_error("invalid blf object signature"));
+        }
+
+        let obj_size = u32_at(&base, 8) as usize;
+        let obj_type = u32_at(&base, 12);
+        let mut object = vec![0u8; obj_size.saturating_sub(OBJ_HEADER_BASE_SIZE)];
+        self.reader.read_exact(&mut object)
+            .map_err(io_error)?;
+        // the padding of last object may be omitted.
+        let mut padding = [0u8; 4];
+        let _ = self.reader.read_exact(&mut padding[..obj_size % 4]);
+
+        if obj_type == LOG_CONTAINER {
+            let method = u16_at(&object, 0);
+            let data = object.get(LOG_CONTAINER_HEADER_SIZE..).unwrap_or_default();
+            match method {
+                NO_COMPRESSION => self.buffer.extend_from_slice(data),
+                ZLIB_DEFLATE => {
+                    ZlibDecoder::new(data).read_to_end(&mut self.buffer)
+                        .map_err(io_error)?;
+                },
+                _ => return Err(Error::OtherError(format!("blf compression method: {} is not supported", method))),
+            }
+            self.parse_container()?;
+        }
+        else {
+            let mut buf = base.to_vec();
+            buf.extend_from_slice(&object);
+            self.parse_object(&buf)?;
+        }
+
+        Ok(true)
+    }
+
+    fn parse_container(&mut self) -> Result<(), Error>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut pos = 0;
+        while pos + OBJ_HEADER_BASE_SIZE <= buffer.len() {
+            if &buffer[pos..pos + 4] != OBJ_SIGNATURE {
+                return Err(Error::other_error("invalid blf object signature in container"));
+            }
+            let obj_size = u32_at(&buffer, pos + 8) as usize;
+            if obj_size < OBJ_HEADER_BASE_SIZE {
+                return Err(Error::other_error("invalid blf object size in container"));
+            }
+            if pos + obj_size > buffer.len() {
+                break;
+            }
+            self.parse_object(&buffer[pos..pos + obj_size])?;
+            pos += obj_size + obj_size % 4;
+        }
+
+        self.buffer = buffer.get(pos..).unwrap_or_default().to_vec();
+        Ok(())
+    }
+
+    fn parse_object(&mut self, object: &[u8]) -> Result<(), Error>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let header_size = u16_at(object, 4) as usize;
+        let obj_type = u32_at(object, 12);
+        if object.len() < OBJ_HEADER_BASE_SIZE + OBJ_HEADER_V1_SIZE || header_size > object.len() {
+            return Ok(());
+        }
+        let flags = u32_at(object, 16);
+        let offset = u64_at(object, 24);
+        let offset = match flags {
+            TIME_TEN_MICS => offset / 100,
+            _ => offset / 1_000_000,
+        };
+        let data = &object[header_size..];
+        let invalid = || Error::OtherError(format!("invalid blf object of type: {}", obj_type));
+
+        let (channel, mut frame) = match obj_type {
+            CAN_MESSAGE | CAN_MESSAGE2 => {
+                if data.len() < 16 {
+                    return Err(invalid());
+                }
+                let (flags, dlc, arb_id) = (data[2], data[3], u32_at(data, 4));
+                let id = Id::from_bits(arb_id & !CAN_MSG_EXT, Some(arb_id & CAN_MSG_EXT != 0));
+                let frame = if flags & CAN_MSG_RTR != 0 {
+                    F::new_remote(id, dlc.min(8) as usize)
+                }
+                else {
+                    F::new(id, &data[8..8 + dlc.min(8) as usize])
+                };
+                let mut frame = frame.ok_or_else(invalid)?;
+                frame.set_direct(if flags & CAN_MSG_DIR != 0 { Direct::Transmit } else { Direct::Receive });
+                (u16_at(data, 0), frame)
+            },
+            CAN_FD_MESSAGE => {
+                if data.len() < 84 {
+                    return Err(invalid());
+                }
+                let (flags, arb_id, fd_flags, length) = (data[2], u32_at(data, 4), data[13], data[14]);
+                let id = Id::from_bits(arb_id & !CAN_MSG_EXT, Some(arb_id & CAN_MSG_EXT != 0));
+                let frame = if flags & CAN_MSG_RTR != 0 {
+                    F::new_remote(id, length.min(8) as usize)
+                }
+                else {
+                    F::new(id, &data[20..20 + length.min(64) as usize])
+                };
+                let mut frame = frame.ok_or_else(invalid)?;
+                if fd_flags & CAN_FD_MSG_EDL != 0 {
+                    frame.set_can_type(Type::CanFd);
+                }
+                frame.set_bitrate_switch(fd_flags & CAN_FD_MSG_BRS != 0)
+                    .set_esi(fd_flags & CAN_FD_MSG_ESI != 0)
+                    .set_direct(if flags & CAN_MSG_DIR != 0 { Direct::Transmit } else { Direct::Receive });
+                (u16_at(data, 0), frame)
+            },
+            CAN_FD_MESSAGE_64 => {
+                if data.len() < 40 {
+                    return Err(invalid());
+                }
+                let (channel, valid, arb_id, fd_flags, dir, ext_offset) =
+                    (data[0], data[2] as usize, u32_at(data, 4), u32_at(data, 12), data[34], data[35] as usize);
+                let id = Id::from_bits(arb_id & !CAN_MSG_EXT, Some(arb_id & CAN_MSG_EXT != 0));
+                // valid data bytes could be more than actual data, padding zero like CANoe.
+                let end = match ext_offset {
+                    0 => data.len(),
+                    v => v.saturating_sub(header_size).min(data.len()),
+                };
+                let mut payload = data[40.min(end)..end.min(40 + valid)].to_vec();
+                payload.resize(valid, 0);
+                let frame = if fd_flags & CAN_FD_64_RTR != 0 {
+                    F::new_remote(id, data[1].min(8) as usize)
+                }
+                else {
+                    F::new(id, &payload)
+                };
+                let mut frame = frame.ok_or_else(invalid)?;
+                if fd_flags & CAN_FD_64_EDL != 0 {
+                    frame.set_can_type(Type::CanFd);
+                }
+                frame.set_bitrate_switch(fd_flags & CAN_FD_64_BRS != 0)
+                    .set_esi(fd_flags & CAN_FD_64_ESI != 0)
+                    .set_direct(if dir != 0 { Direct::Transmit } else { Direct::Receive });
+                (channel as u16, frame)
+            },
+            CAN_ERROR_EXT => {
+                if data.len() < 32 {
+                    return Err(invalid());
+                }
+                let (dlc, arb_id) = (data[10].min(8) as usize, u32_at(data, 16));
+                let id = Id::from_bits(arb_id & !CAN_MSG_EXT, Some(arb_id & CAN_MSG_EXT != 0));
+                let mut frame = F::new(id, &data[24..24 + dlc])
+                    .ok_or_else(invalid)?;
+                frame.set_error_frame(true)
+                    .set_direct(Direct::Receive);
+                (u16_at(data, 0), frame)
+            },
+            _ => return Ok(()),
+        };
+
+        let channel = match self.channels.get(&channel) {
+            Some(v) => v.clone(),
+            None => channel.to_string().parse()
+                .map_err(|_| Error::OtherError(format!("blf channel: {} can't be converted", channel)))?,
+        };
+        frame.set_timestamp(Some(self.start + offset))
+            .set_channel(channel);
+        self.frames.push_back(frame);
+
+        Ok(())
+    }
+}
+
+impl<R: Read, F: Frame> Iterator for BlfReader<R, F>
+where
+    F::Channel: FromStr + Clone,
+{
+    type Item = Result<F, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            if let Err(e) = self.read_header() {
+                return Some(Err(e));
+            }
+        }
+
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Some(Ok(frame));
+            }
+
+            match self.read_object() {
+                Ok(true) => {},
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
@@ -43,6 +43,36 @@ pub fn can_dlc(length: usize, r#type: Type) -> isize {
     }
 }
 
+/// get CAN-FD DLC code(0~15) from length of data.
+#[inline]
+pub fn dlc_code(length: usize) -> u8 {
+    match length {
+        ..=MAX_FRAME_SIZE => length as u8,
+        9..=12 => 9,
+        13..=16 => 10,
+        17..=20 => 11,
+        21..=24 => 12,
+        25..=32 => 13,
+        33..=48 => 14,
+        _ => 15,
+    }
+}
+
+/// get length of data from CAN-FD DLC code(0~15).
+#[inline]
+pub fn dlc_length(code: u8) -> usize {
+    match code {
+        ..=8 => code as usize,
+        9 => 12,
+        10 => 16,
+        11 => 20,
+        12 => 24,
+        13 => 32,
+        14 => 48,
+        _ => MAX_FD_FRAME_SIZE,
+    }
+}
+
 #[inline]
 pub fn system_timestamp() -> u64 {
     match SystemTime::now().duration_since(UNIX_EPOCH) {
@@ -53,3 +83,41 @@ pub fn system_timestamp() -> u64 {
         }
     }
 }
+
+/// The nanoseconds since UNIX epoch.
+#[inline]
+pub fn system_timestamp_ns() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(v) => v.as_nanos() as u64,
+        Err(e) => {
+            log::warn!("RUST-CAN - SystemTimeError: {0} when conversion failed!", e);
+            0
+        }
+    }
+}
+
+/// Days since 1970-01-01 of the proleptic Gregorian date.
+#[inline]
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The proleptic Gregorian date(year, month, day) of days since 1970-01-01.
+#[inline]
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (if month <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, month, day)
+}
@@ -12,3 +12,5 @@ pub const MAX_FD_FRAME_SIZE: usize = 64;
 pub const MAX_XL_FRAME_SIZE: usize = 2048;
 /// Default padding value(0b1010_1010).
 pub const DEFAULT_PADDING: u8 = 0xAA;
+/// The time in milliseconds to wait frames of device at a time when waiting forever.
+pub(crate) const POLL_INTERVAL: u32 = 100;
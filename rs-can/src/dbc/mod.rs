@@ -0,0 +1,191 @@
+//! Vector DBC database.
+//!
+//! [`Dbc`] parses messages, signals, value tables, comments and attributes of a DBC file,
+//! and decodes [`Frame::data`] into physical signal values or encodes them back to payloads.
+//!
+//! Simple multiplexing(`M` and `mN`) is supported,
+//! the extended multiplexing(`SG_MUL_VAL_`) is not.
+
+mod parser;
+mod signal;
+
+pub use signal::*;
+
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+use derive_getters::Getters;
+use crate::error::Error;
+use crate::frame::{Frame, Id};
+
+/// The value of attribute, the index of `ENUM` attribute is stored as `Int`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+/// The object that attribute is defined for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeObject {
+    Network,
+    Node,
+    Message,
+    Signal,
+    EnvironmentVariable,
+}
+
+/// The value type of attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeType {
+    Int(i64, i64),
+    Hex(i64, i64),
+    Float(f64, f64),
+    String,
+    Enum(Vec<String>),
+}
+
+/// `BA_DEF_` and `BA_DEF_DEF_` of attribute.
+#[derive(Debug, Clone, Getters)]
+pub struct AttributeDefinition {
+    name: String,
+    #[getter(copy)]
+    object: AttributeObject,
+    r#type: AttributeType,
+    default: Option<AttributeValue>,
+}
+
+/// `BO_` of DBC, the message that signals are packed in.
+#[derive(Debug, Clone, Getters)]
+pub struct Message {
+    #[getter(copy)]
+    id: Id,
+    name: String,
+    #[getter(copy)]
+    size: usize,
+    transmitter: String,
+    signals: Vec<Signal>,
+    comment: Option<String>,
+    attributes: HashMap<String, AttributeValue>,
+}
+
+impl Message {
+    #[inline]
+    pub fn signal(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|s| s.name() == name)
+    }
+
+    /// The multiplexor signal of message.
+    #[inline]
+    pub fn multiplexor(&self) -> Option<&Signal> {
+        self.signals.iter().find(|s| s.is_multiplexor())
+    }
+
+    /// The signals are present in data, the multiplexed signals are filtered by multiplexor value.
+    pub fn active_signals(&self, data: &[u8]) -> Result<Vec<&Signal>, Error> {
+        let mux = match self.multiplexor() {
+            Some(s) => Some(s.decode_raw(data)?),
+            None => None,
+        };
+        Ok(self.signals.iter()
+            .filter(|s| s.multiplexer_value().is_none() || s.multiplexer_value() == mux)
+            .collect())
+    }
+
+    /// Decode data into physical values of signals.
+    pub fn decode(&self, data: &[u8]) -> Result<HashMap<String, f64>, Error> {
+        self.active_signals(data)?
+            .into_iter()
+            .map(|s| Ok((s.name().clone(), s.decode(data)?)))
+            .collect()
+    }
+
+    /// Encode physical values of signals into payload of message size.
+    ///
+    /// All signals that active for the multiplexor value are required.
+    pub fn encode(&self, values: &HashMap<String, f64>) -> Result<Vec<u8>, Error> {
+        let mux = match self.multiplexor() {
+            Some(s) => Some(s.to_raw(*values.get(s.name())
+                .ok_or_else(|| Error::OtherError(format!("dbc: multiplexor {} is required", s.name())))?)?),
+            None => None,
+        };
+
+        let mut data = vec![0; self.size];
+        for signal in self.signals.iter()
+            .filter(|s| s.multiplexer_value().is_none() || s.multiplexer_value() == mux) {
+            let value = values.get(signal.name())
+                .ok_or_else(|| Error::OtherError(format!("dbc: signal {} of {} is required", signal.name(), self.name)))?;
+            signal.encode(*value, &mut data)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Encode physical values of signals into a frame.
+    pub fn encode_frame<F: Frame>(&self, values: &HashMap<String, f64>) -> Result<F, Error> {
+        let data = self.encode(values)?;
+        F::new(self.id, &data)
+            .ok_or_else(|| Error::OtherError(format!("dbc: invalid frame of message {}", self.name)))
+    }
+}
+
+/// DBC database.
+#[derive(Debug, Default, Clone, Getters)]
+pub struct Dbc {
+    version: String,
+    nodes: Vec<String>,
+    messages: Vec<Message>,
+    value_tables: HashMap<String, HashMap<i64, String>>,
+    comment: Option<String>,
+    node_comments: HashMap<String, String>,
+    attribute_definitions: Vec<AttributeDefinition>,
+    attributes: HashMap<String, AttributeValue>,
+    node_attributes: HashMap<String, HashMap<String, AttributeValue>>,
+}
+
+impl Dbc {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let content = fs::read(path)
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        // DBC files are usually encoded in cp1252, the invalid bytes are replaced.
+        String::from_utf8_lossy(&content).parse()
+    }
+
+    #[inline]
+    pub fn message(&self, name: &str) -> Option<&Message> {
+        self.messages.iter().find(|m| m.name == name)
+    }
+
+    #[inline]
+    pub fn message_by_id(&self, id: Id) -> Option<&Message> {
+        self.messages.iter().find(|m| m.id == id)
+    }
+
+    #[inline]
+    pub fn attribute_definition(&self, name: &str) -> Option<&AttributeDefinition> {
+        self.attribute_definitions.iter().find(|d| d.name == name)
+    }
+
+    /// Decode the frame into physical values of signals by the message of the same ID.
+    pub fn decode<F: Frame>(&self, frame: &F) -> Result<HashMap<String, f64>, Error> {
+        let id = frame.id();
+        self.message_by_id(id)
+            .ok_or_else(|| Error::OtherError(format!("dbc: message 0x{:X} is not defined", id.into_bits())))?
+            .decode(frame.data())
+    }
+
+    /// Encode physical values of signals into frame of the message.
+    pub fn encode<F: Frame>(&self, message: &str, values: &HashMap<String, f64>) -> Result<F, Error> {
+        self.message(message)
+            .ok_or_else(|| Error::OtherError(format!("dbc: message {} is not defined", message)))?
+            .encode_frame(values)
+    }
+}
+
+impl FromStr for Dbc {
+    type Err = Error;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser::Parser::new(s).parse()
+    }
+}
@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+use crate::constants::EFF_MASK;
+use crate::error::Error;
+use crate::frame::Id;
+use super::{AttributeDefinition, AttributeObject, AttributeType, AttributeValue, ByteOrder, Dbc, Message, Signal, ValueType};
+
+/// The flag of extended identifier in `BO_`.
+const EXTENDED_FLAG: u32 = 0x8000_0000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Number(&'a str),
+    String(String),
+    Punct(char),
+}
+
+#[derive(Debug)]
+struct Item<'a> {
+    token: Token<'a>,
+    line: usize,
+    /// The token is the first of line without indent.
+    line_start: bool,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Item<'_>>, Error> {
+    let mut items = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    let (mut line, mut column) = (1, 0);
+    let mut first = true;
+
+    while let Some(&(i, c)) = chars.peek() {
+        if c == '\n' {
+            chars.next();
+            line += 1;
+            column = 0;
+            first = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            chars.next();
+            column += 1;
+            continue;
+        }
+
+        let line_start = first && column == 0;
+        first = false;
+        let (token, start_line) = if c == '"' {
+            chars.next();
+            let start_line = line;
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((_, '\\')) => if let Some((_, c)) = chars.next() {
+                        value.push(c);
+                    },
+                    Some((_, c)) => {
+                        if c == '\n' {
+                            line += 1;
+                        }
+                        value.push(c);
+                    },
+                    None => return Err(Error::OtherError(format!("dbc: unterminated string at line {}", start_line))),
+                }
+            }
+            (Token::String(value), start_line)
+        }
+        else if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = i;
+            while let Some(&(j, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = j + c.len_utf8();
+                chars.next();
+            }
+            (Token::Ident(&s[i..end]), line)
+        }
+        else if c.is_ascii_digit() || c == '.' {
+            let mut end = i;
+            let mut prev = c;
+            while let Some(&(j, c)) = chars.peek() {
+                let exponent_sign = (c == '-' || c == '+') && (prev == 'e' || prev == 'E');
+                if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                    break;
+                }
+                end = j + c.len_utf8();
+                prev = c;
+                chars.next();
+            }
+            (Token::Number(&s[i..end]), line)
+        }
+        else {
+            chars.next();
+            (Token::Punct(c), line)
+        };
+        column += 1;
+        items.push(Item { token, line: start_line, line_start });
+    }
+
+    Ok(items)
+}
+
+pub(super) struct Parser<'a> {
+    content: &'a str,
+    items: Vec<Item<'a>>,
+    index: usize,
+    dbc: Dbc,
+}
+
+impl<'a> Parser<'a> {
+    pub(super) fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            items: Default::default(),
+            index: Default::default(),
+            dbc: Default::default(),
+        }
+    }
+
+    pub(super) fn parse(mut self) -> Result<Dbc, Error> {
+        self.items = tokenize(self.content)?;
+
+        while let Some(item) = self.items.get(self.index) {
+            let keyword = match item.token {
+                Token::Ident(v) => v,
+                _ => return Err(self.error("keyword expected")),
+            };
+            self.index += 1;
+            match keyword {
+                "VERSION" => self.dbc.version = self.string()?,
+                "NS_" | "BS_" => self.skip_indented(),
+                "BU_" => {
+                    self.punct(':')?;
+                    while let Some(Item { token: Token::Ident(v), line_start: false, .. }) = self.items.get(self.index) {
+                        self.dbc.nodes.push(v.to_string());
+                        self.index += 1;
+                    }
+                },
+                "VAL_TABLE_" => {
+                    let name = self.ident()?.to_owned();
+                    let values = self.value_descriptions()?;
+                    self.dbc.value_tables.insert(name, values);
+                },
+                "BO_" => self.parse_message()?,
+                "SG_" => return Err(self.error("signal without message")),
+                "CM_" => self.parse_comment()?,
+                "BA_DEF_" => self.parse_attribute_definition()?,
+                "BA_DEF_DEF_" => {
+                    let name = self.string()?;
+                    let value = self.attribute_value()?;
+                    self.punct(';')?;
+                    if let Some(def) = self.dbc.attribute_definitions.iter_mut().find(|d| d.name == name) {
+                        def.default = Some(value);
+                    }
+                },
+                "BA_" => self.parse_attribute()?,
+                "VAL_" => self.parse_value_descriptions()?,
+                "SIG_VALTYPE_" => {
+                    let id = self.message_id()?;
+                    let name = self.ident()?.to_owned();
+                    self.punct(':')?;
+                    let value_type = match self.number::<u8>()? {
+                        1 => ValueType::Float,
+                        2 => ValueType::Double,
+                        _ => ValueType::Unsigned,
+                    };
+                    self.punct(';')?;
+                    if let Some(signal) = self.signal_mut(id, &name) {
+                        if value_type != ValueType::Unsigned {
+                            signal.value_type = value_type;
+                        }
+                    }
+                },
+                // EV_, BO_TX_BU_, SG_MUL_VAL_ and so on.
+                _ => self.skip_statement(),
+            }
+        }
+
+        Ok(self.dbc)
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        match self.items.get(self.index) {
+            Some(item) => Error::OtherError(format!("dbc: {} at line {}: {:?}", msg, item.line, item.token)),
+            None => Error::OtherError(format!("dbc: {} at end of file", msg)),
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.items.get(self.index).map(|v| &v.token)
+    }
+
+    fn next(&mut self) -> Option<&Token<'a>> {
+        let item = self.items.get(self.index)?;
+        self.index += 1;
+        Some(&item.token)
+    }
+
+    fn punct(&mut self, c: char) -> Result<(), Error> {
+        match self.peek() {
+            Some(Token::Punct(v)) if *v == c => {
+                self.index += 1;
+                Ok(())
+            },
+            _ => Err(self.error(&format!("`{}` expected", c))),
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        matches!(self.peek(), Some(Token::Punct(v)) if *v == c)
+    }
+
+    fn ident(&mut self) -> Result<&'a str, Error> {
+        match self.peek() {
+            Some(&Token::Ident(v)) => {
+                self.index += 1;
+                Ok(v)
+            },
+            _ => Err(self.error("identifier expected")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::String(v)) => {
+                let v = v.clone();
+                self.index += 1;
+                Ok(v)
+            },
+            _ => Err(self.error("string expected")),
+        }
+    }
+
+    /// Parse a number with optional sign.
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, Error> {
+        let sign = match self.peek() {
+            Some(Token::Punct(c)) if *c == '-' || *c == '+' => {
+                let c = *c;
+                self.index += 1;
+                Some(c)
+            },
+            _ => None,
+        };
+        match self.peek() {
+            Some(&Token::Number(v)) => {
+                let text = match sign {
+                    Some('-') => format!("-{}", v),
+                    _ => v.to_owned(),
+                };
+                let result = text.parse::<T>()
+                    .map_err(|_| self.error("invalid number"))?;
+                self.index += 1;
+                Ok(result)
+            },
+            _ => Err(self.error("number expected")),
+        }
+    }
+
+    fn message_id(&mut self) -> Result<Id, Error> {
+        let id = self.number::<u32>()?;
+        Ok(if id & EXTENDED_FLAG != 0 {
+            Id::Extended(id & EFF_MASK)
+        }
+        else {
+            Id::Standard(id as u16)
+        })
+    }
+
+    /// Skip the tokens until next line without indent.
+    fn skip_indented(&mut self) {
+        while self.items.get(self.index).is_some_and(|v| !v.line_start) {
+            self.index += 1;
+        }
+    }
+
+    fn skip_statement(&mut self) {
+        while let Some(token) = self.next() {
+            if *token == Token::Punct(';') {
+                break;
+            }
+        }
+    }
+
+    fn value_descriptions(&mut self) -> Result<HashMap<i64, String>, Error> {
+        let mut values = HashMap::new();
+        while !self.is_punct(';') {
+            let value = self.number::<f64>()? as i64;
+            values.insert(value, self.string()?);
+        }
+        self.punct(';')?;
+        Ok(values)
+    }
+
+    fn signal_mut(&mut self, id: Id, name: &str) -> Option<&mut Signal> {
+        self.dbc.messages.iter_mut()
+            .find(|m| m.id == id)?
+            .signals.iter_mut()
+            .find(|s| s.name == name)
+    }
+
+    fn parse_message(&mut self) -> Result<(), Error> {
+        let id = self.message_id()?;
+        let name = self.ident()?.to_owned();
+        self.punct(':')?;
+        let size = self.number::<usize>()?;
+        let transmitter = self.ident()?.to_owned();
+
+        let mut signals = Vec::new();
+        while self.peek() == Some(&Token::Ident("SG_")) {
+            self.index += 1;
+            signals.push(self.parse_signal()?);
+        }
+
+        self.dbc.messages.push(Message {
+            id,
+            name,
+            size,
+            transmitter,
+            signals,
+            comment: Default::default(),
+            attributes: Default::default(),
+        });
+        Ok(())
+    }
+
+    fn parse_signal(&mut self) -> Result<Signal, Error> {
+        let name = self.ident()?.to_owned();
+        let (mut is_multiplexor, mut multiplexer_value) = (false, None);
+        if let Some(&Token::Ident(mux)) = self.peek() {
+            // `M`, `m<N>` or `m<N>M` of extended multiplexing.
+            if mux == "M" {
+                is_multiplexor = true;
+            }
+            else if let Some(v) = mux.strip_prefix('m') {
+                let v = v.strip_suffix('M').unwrap_or(v);
+                multiplexer_value = Some(v.parse::<u64>()
+                    .map_err(|_| self.error("invalid multiplexer"))?);
+            }
+            else {
+                return Err(self.error("multiplexer expected"));
+            }
+            self.index += 1;
+        }
+        self.punct(':')?;
+
+        let start_bit = self.number::<u32>()?;
+        self.punct('|')?;
+        let size = self.number::<u32>()?;
+        self.punct('@')?;
+        // `@1+` is tokenized as `1` and `+`, `@1-` is tokenized as `1` and `-`.
+        let byte_order = match self.peek() {
+            Some(Token::Number("0")) => ByteOrder::BigEndian,
+            Some(Token::Number("1")) => ByteOrder::LittleEndian,
+            _ => return Err(self.error("byte order expected")),
+        };
+        self.index += 1;
+        let value_type = match self.peek() {
+            Some(Token::Punct('+')) => ValueType::Unsigned,
+            Some(Token::Punct('-')) => ValueType::Signed,
+            _ => return Err(self.error("value type expected")),
+        };
+        self.index += 1;
+        self.punct('(')?;
+        let factor = self.number::<f64>()?;
+        self.punct(',')?;
+        let offset = self.number::<f64>()?;
+        self.punct(')')?;
+        self.punct('[')?;
+        let minimum = self.number::<f64>()?;
+        self.punct('|')?;
+        let maximum = self.number::<f64>()?;
+        self.punct(']')?;
+        let unit = self.string()?;
+
+        let mut receivers = vec![self.ident()?.to_owned()];
+        while self.is_punct(',') {
+            self.index += 1;
+            receivers.push(self.ident()?.to_owned());
+        }
+
+        Ok(Signal {
+            name,
+            start_bit,
+            size,
+            byte_order,
+            value_type,
+            factor,
+            offset,
+            minimum,
+            maximum,
+            unit,
+            receivers,
+            is_multiplexor,
+            multiplexer_value,
+            value_descriptions: Default::default(),
+            comment: Default::default(),
+            attributes: Default::default(),
+        })
+    }
+
+    fn parse_comment(&mut self) -> Result<(), Error> {
+        match self.peek() {
+            Some(Token::String(_)) => self.dbc.comment = Some(self.string()?),
+            Some(Token::Ident("BU_")) => {
+                self.index += 1;
+                let node = self.ident()?.to_owned();
+                let comment = self.string()?;
+                self.dbc.node_comments.insert(node, comment);
+            },
+            Some(Token::Ident("BO_")) => {
+                self.index += 1;
+                let id = self.message_id()?;
+                let comment = self.string()?;
+                if let Some(msg) = self.dbc.messages.iter_mut().find(|m| m.id == id) {
+                    msg.comment = Some(comment);
+                }
+            },
+            Some(Token::Ident("SG_")) => {
+                self.index += 1;
+                let id = self.message_id()?;
+                let name = self.ident()?.to_owned();
+                let comment = self.string()?;
+                if let Some(signal) = self.signal_mut(id, &name) {
+                    signal.comment = Some(comment);
+                }
+            },
+            // comment of environment variable.
+            _ => {
+                self.skip_statement();
+                return Ok(());
+            },
+        }
+        self.punct(';')
+    }
+
+    fn attribute_object(&mut self) -> AttributeObject {
+        let object = match self.peek() {
+            Some(Token::Ident("BU_")) => AttributeObject::Node,
+            Some(Token::Ident("BO_")) => AttributeObject::Message,
+            Some(Token::Ident("SG_")) => AttributeObject::Signal,
+            Some(Token::Ident("EV_")) => AttributeObject::EnvironmentVariable,
+            _ => return AttributeObject::Network,
+        };
+        self.index += 1;
+        object
+    }
+
+    fn parse_attribute_definition(&mut self) -> Result<(), Error> {
+        let object = self.attribute_object();
+        let name = self.string()?;
+        let r#type = match self.ident()? {
+            "INT" => AttributeType::Int(self.number()?, self.number()?),
+            "HEX" => AttributeType::Hex(self.number()?, self.number()?),
+            "FLOAT" => AttributeType::Float(self.number()?, self.number()?),
+            "STRING" => AttributeType::String,
+            "ENUM" => {
+                let mut values = Vec::new();
+                while !self.is_punct(';') {
+                    values.push(self.string()?);
+                    if self.is_punct(',') {
+                        self.index += 1;
+                    }
+                }
+                AttributeType::Enum(values)
+            },
+            _ => {
+                self.index -= 1;
+                return Err(self.error("attribute type expected"));
+            },
+        };
+        self.punct(';')?;
+
+        self.dbc.attribute_definitions.push(AttributeDefinition { name, object, r#type, default: None });
+        Ok(())
+    }
+
+    fn attribute_value(&mut self) -> Result<AttributeValue, Error> {
+        match self.peek() {
+            Some(Token::String(_)) => Ok(AttributeValue::String(self.string()?)),
+            _ => {
+                let value = self.number::<f64>()?;
+                if value.fract() == 0. && value.abs() < i64::MAX as f64 {
+                    Ok(AttributeValue::Int(value as i64))
+                }
+                else {
+                    Ok(AttributeValue::Float(value))
+                }
+            },
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<(), Error> {
+        let name = self.string()?;
+        match self.attribute_object() {
+            AttributeObject::Network => {
+                let value = self.attribute_value()?;
+                self.dbc.attributes.insert(name, value);
+            },
+            AttributeObject::Node => {
+                let node = self.ident()?.to_owned();
+                let value = self.attribute_value()?;
+                self.dbc.node_attributes.entry(node).or_default().insert(name, value);
+            },
+            AttributeObject::Message => {
+                let id = self.message_id()?;
+                let value = self.attribute_value()?;
+                if let Some(msg) = self.dbc.messages.iter_mut().find(|m| m.id == id) {
+                    msg.attributes.insert(name, value);
+                }
+            },
+            AttributeObject::Signal => {
+                let id = self.message_id()?;
+                let signal = self.ident()?.to_owned();
+                let value = self.attribute_value()?;
+                if let Some(signal) = self.signal_mut(id, &signal) {
+                    signal.attributes.insert(name, value);
+                }
+            },
+            AttributeObject::EnvironmentVariable => {
+                self.skip_statement();
+                return Ok(());
+            },
+        }
+        self.punct(';')
+    }
+
+    fn parse_value_descriptions(&mut self) -> Result<(), Error> {
+        // value descriptions of environment variable.
+        if !matches!(self.peek(), Some(Token::Number(_))) {
+            self.skip_statement();
+            return Ok(());
+        }
+
+        let id = self.message_id()?;
+        let name = self.ident()?.to_owned();
+        let values = self.value_descriptions()?;
+        if let Some(signal) = self.signal_mut(id, &name) {
+            signal.value_descriptions = values;
+        }
+        Ok(())
+    }
+}
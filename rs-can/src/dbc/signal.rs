@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use derive_getters::Getters;
+use crate::error::Error;
+use super::AttributeValue;
+
+/// The byte order of signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Intel(`@1`), the start bit is the least significant bit.
+    LittleEndian,
+    /// Motorola(`@0`), the start bit is the most significant bit.
+    BigEndian,
+}
+
+/// The value type of signal, the float types are defined by `SIG_VALTYPE_`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Unsigned,
+    Signed,
+    Float,
+    Double,
+}
+
+/// `SG_` of DBC.
+#[derive(Debug, Clone, Getters)]
+pub struct Signal {
+    pub(super) name: String,
+    #[getter(copy)]
+    pub(super) start_bit: u32,
+    #[getter(copy)]
+    pub(super) size: u32,
+    #[getter(copy)]
+    pub(super) byte_order: ByteOrder,
+    #[getter(copy)]
+    pub(super) value_type: ValueType,
+    #[getter(copy)]
+    pub(super) factor: f64,
+    #[getter(copy)]
+    pub(super) offset: f64,
+    #[getter(copy)]
+    pub(super) minimum: f64,
+    #[getter(copy)]
+    pub(super) maximum: f64,
+    pub(super) unit: String,
+    pub(super) receivers: Vec<String>,
+    #[getter(copy)]
+    pub(super) is_multiplexor: bool,
+    #[getter(copy)]
+    pub(super) multiplexer_value: Option<u64>,
+    pub(super) value_descriptions: HashMap<i64, String>,
+    pub(super) comment: Option<String>,
+    pub(super) attributes: HashMap<String, AttributeValue>,
+}
+
+impl Signal {
+    /// The bit positions(`byte * 8 + bit`) from the least significant bit.
+    fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        let start = self.start_bit as usize;
+        let size = self.size as usize;
+        (0..size).map(move |i| match self.byte_order {
+            ByteOrder::LittleEndian => start + i,
+            ByteOrder::BigEndian => {
+                // the index in big endian bit sequence, 0 is MSB of byte 0.
+                let index = (start / 8) * 8 + (7 - start % 8) + (size - 1 - i);
+                (index / 8) * 8 + (7 - index % 8)
+            },
+        })
+    }
+
+    fn check_length(&self, length: usize) -> Result<(), Error> {
+        match self.positions().max() {
+            Some(v) if v >= length * 8 => Err(Error::OtherError(
+                format!("dbc: signal {} is out of data length {}", self.name, length)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decode the raw bits of signal from data.
+    pub fn decode_raw(&self, data: &[u8]) -> Result<u64, Error> {
+        self.check_length(data.len())?;
+        Ok(self.positions()
+            .enumerate()
+            .fold(0, |raw, (i, pos)| raw | (((data[pos / 8] >> (pos % 8)) & 1) as u64) << i))
+    }
+
+    /// The integer value of raw bits, it's sign extended if signal is signed.
+    pub fn raw_integer(&self, raw: u64) -> i64 {
+        match self.value_type {
+            ValueType::Signed if self.size < 64 => {
+                let shift = 64 - self.size;
+                ((raw << shift) as i64) >> shift
+            },
+            _ => raw as i64,
+        }
+    }
+
+    /// Convert raw bits to physical value.
+    pub fn to_physical(&self, raw: u64) -> f64 {
+        let value = match self.value_type {
+            ValueType::Unsigned => raw as f64,
+            ValueType::Signed => self.raw_integer(raw) as f64,
+            ValueType::Float => f32::from_bits(raw as u32) as f64,
+            ValueType::Double => f64::from_bits(raw),
+        };
+        value * self.factor + self.offset
+    }
+
+    /// Convert physical value to raw bits, an error is returned if it's out of signal size.
+    pub fn to_raw(&self, value: f64) -> Result<u64, Error> {
+        let value = (value - self.offset) / self.factor;
+        let out_of_range = || Error::OtherError(format!("dbc: value {} is out of range of signal {}", value, self.name));
+        let mask = if self.size >= 64 { u64::MAX } else { (1 << self.size) - 1 };
+
+        match self.value_type {
+            ValueType::Unsigned => {
+                let value = value.round();
+                if !(0. ..=mask as f64).contains(&value) {
+                    return Err(out_of_range());
+                }
+                Ok(value as u64)
+            },
+            ValueType::Signed => {
+                let value = value.round();
+                let max = (mask >> 1) as f64;
+                if !(-max - 1. ..=max).contains(&value) {
+                    return Err(out_of_range());
+                }
+                Ok(value as i64 as u64 & mask)
+            },
+            ValueType::Float => Ok((value as f32).to_bits() as u64),
+            ValueType::Double => Ok(value.to_bits()),
+        }
+    }
+
+    /// Decode physical value of signal from data.
+    #[inline]
+    pub fn decode(&self, data: &[u8]) -> Result<f64, Error> {
+        Ok(self.to_physical(self.decode_raw(data)?))
+    }
+
+    /// Encode raw bits of signal into data, the other bits of data are kept.
+    pub fn encode_raw(&self, raw: u64, data: &mut [u8]) -> Result<(), Error> {
+        self.check_length(data.len())?;
+        self.positions()
+            .enumerate()
+            .for_each(|(i, pos)| {
+                let bit = 1 << (pos % 8);
+                if raw >> i & 1 == 1 {
+                    data[pos / 8] |= bit;
+                }
+                else {
+                    data[pos / 8] &= !bit;
+                }
+            });
+        Ok(())
+    }
+
+    /// Encode physical value of signal into data.
+    #[inline]
+    pub fn encode(&self, value: f64, data: &mut [u8]) -> Result<(), Error> {
+        self.encode_raw(self.to_raw(value)?, data)
+    }
+
+    /// The description of raw value from `VAL_`.
+    #[inline]
+    pub fn value_description(&self, raw: u64) -> Option<&String> {
+        self.value_descriptions.get(&self.raw_integer(raw))
+    }
+}
@@ -1,13 +1,11 @@
 use std::{any::{Any, type_name}, collections::HashMap, fmt::Display};
 use derive_getters::Getters;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use crate::error::Error;
 use crate::frame::{Frame, Id};
 
-#[cfg(not(feature = "async"))]
+/// The result of [`Device`], the asynchronous devices are `AsyncCanDevice` of feature `async`.
 pub type CanResult<R, E> = Result<R, E>;
-#[cfg(feature = "async")]
-pub type CanResult<R, E> = impl std::future::Future<Output = Result<R, E>>;
 
 pub trait Listener<C, F: Frame>: Send {
     fn as_any(&self) -> &dyn Any;
@@ -15,6 +13,9 @@ pub trait Listener<C, F: Frame>: Send {
     fn on_frame_transmitting(&self, channel: C, frame: &F);
     /// Callback when frame transmit success.
     fn on_frame_transmitted(&self, channel: C, id: Id);
+    /// Callback when frame transmit failed.
+    #[allow(unused_variables)]
+    fn on_frame_transmit_failed(&self, channel: C, id: Id, error: &Error) {}
     /// Callback when frames received.
     fn on_frame_received(&self, channel: C, frames: &[F]);
 }
@@ -36,6 +37,21 @@ pub trait Device: Clone + TryFrom<DeviceBuilder, Error = Error> {
     fn shutdown(&mut self);
 }
 
+/// The typed configuration of a [`Device`], it's loaded from any format of serde, like YAML, TOML and JSON.
+///
+/// It's converted to [`DeviceBuilder`] with the options of backend, so that the types of
+/// options are checked at compile time instead of by [`DeviceBuilder::get_other`].
+pub trait DeviceConfig: Serialize + DeserializeOwned {
+    type Device: Device;
+    /// Convert to the builder of device.
+    fn builder(&self) -> DeviceBuilder;
+    /// Build the device configured.
+    #[inline]
+    fn build(&self) -> Result<Self::Device, Error> {
+        self.builder().build()
+    }
+}
+
 #[derive(Debug, Default, Deserialize, Serialize, Getters)]
 pub struct ChannelConfig {
     #[getter(copy)]
@@ -88,6 +104,11 @@ impl DeviceBuilder {
         Self::default()
     }
 
+    #[inline]
+    pub fn from_config<C: DeviceConfig>(cfg: &C) -> Self {
+        cfg.builder()
+    }
+
     pub fn add_config<S: Into<String>>(&mut self, channel: S, cfg: ChannelConfig) -> &mut Self {
         self.configs.insert(channel.into(), cfg);
         self
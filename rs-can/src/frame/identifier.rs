@@ -1,4 +1,5 @@
 use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 use crate::constants::{EFF_MASK, SFF_MASK};
 
 bitflags! {
@@ -22,7 +23,7 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub struct Filter {
     pub can_id: u32,
     pub can_mask: u32,
@@ -134,3 +135,125 @@ impl Id {
         }
     }
 }
+
+/// SAE J1939 identifier, it's the 29-bit extended identifier:
+/// priority(3) | EDP(1) | DP(1) | PF(8) | PS(8) | SA(8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct J1939Id {
+    priority: u8,
+    edp: bool,
+    dp: bool,
+    pf: u8,
+    ps: u8,
+    sa: u8,
+}
+
+impl J1939Id {
+    /// Create identifier of PGN, the destination is ignored if PGN is PDU2 format.
+    #[inline]
+    pub fn new(priority: u8, pgn: u32, destination: u8, source: u8) -> Self {
+        let pf = (pgn >> 8) as u8;
+        Self {
+            priority: priority & 0x07,
+            edp: pgn & 0x2_0000 != 0,
+            dp: pgn & 0x1_0000 != 0,
+            pf,
+            ps: if pf < 240 { destination } else { pgn as u8 },
+            sa: source,
+        }
+    }
+
+    #[inline]
+    pub fn from_bits(bits: u32) -> Self {
+        Self {
+            priority: (bits >> 26) as u8 & 0x07,
+            edp: bits & 0x0200_0000 != 0,
+            dp: bits & 0x0100_0000 != 0,
+            pf: (bits >> 16) as u8,
+            ps: (bits >> 8) as u8,
+            sa: bits as u8,
+        }
+    }
+
+    #[inline]
+    pub fn into_bits(self) -> u32 {
+        (self.priority as u32) << 26
+            | (self.edp as u32) << 25
+            | (self.dp as u32) << 24
+            | (self.pf as u32) << 16
+            | (self.ps as u32) << 8
+            | self.sa as u32
+    }
+
+    #[inline]
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Extended data page.
+    #[inline]
+    pub fn edp(&self) -> bool {
+        self.edp
+    }
+
+    /// Data page.
+    #[inline]
+    pub fn dp(&self) -> bool {
+        self.dp
+    }
+
+    /// PDU format.
+    #[inline]
+    pub fn pf(&self) -> u8 {
+        self.pf
+    }
+
+    /// PDU specific, it's destination address of PDU1 or group extension of PDU2.
+    #[inline]
+    pub fn ps(&self) -> u8 {
+        self.ps
+    }
+
+    /// Source address.
+    #[inline]
+    pub fn sa(&self) -> u8 {
+        self.sa
+    }
+
+    /// The PDU1 format(PF < 240) is destination specific.
+    #[inline]
+    pub fn is_pdu1(&self) -> bool {
+        self.pf < 240
+    }
+
+    /// Parameter group number, the PS of PDU1 is not included.
+    #[inline]
+    pub fn pgn(&self) -> u32 {
+        let ps = if self.is_pdu1() { 0 } else { self.ps as u32 };
+        (self.edp as u32) << 17 | (self.dp as u32) << 16 | (self.pf as u32) << 8 | ps
+    }
+
+    /// Destination address of PDU1, `None` if PDU2.
+    #[inline]
+    pub fn destination(&self) -> Option<u8> {
+        if self.is_pdu1() { Some(self.ps) } else { None }
+    }
+}
+
+impl From<J1939Id> for Id {
+    #[inline]
+    fn from(id: J1939Id) -> Self {
+        Self::Extended(id.into_bits())
+    }
+}
+
+impl TryFrom<Id> for J1939Id {
+    type Error = crate::error::Error;
+
+    fn try_from(id: Id) -> Result<Self, Self::Error> {
+        match id {
+            Id::Extended(v) => Ok(Self::from_bits(v)),
+            Id::Standard(v) => Err(Self::Error::OtherError(format!("standard id: 0x{:03X} is not J1939 id", v))),
+        }
+    }
+}
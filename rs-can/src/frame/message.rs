@@ -0,0 +1,370 @@
+use std::{cmp::Ordering, fmt::{Display, Formatter}, hash::{Hash, Hasher}};
+use serde::{Deserialize, Serialize};
+use crate::constants::{MAX_FRAME_SIZE, MAX_FD_FRAME_SIZE, MAX_XL_FRAME_SIZE};
+use crate::error::Error;
+use crate::utils;
+use super::{Direct, Frame, Id, Type, XlControl};
+
+/// The owned frame which is independent of any device, the frames of backends are converted from and to it.
+///
+/// The frames are equal, hashed and ordered by content, the identifier, type, flags, length and data,
+/// and the data of remote frames are ignored. The timestamp, channel and direction are not compared.
+///
+/// The length and data are checked with type when deserialized.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "Fields")]
+pub struct Message {
+    /// The timestamp in nanoseconds.
+    pub(crate) timestamp: u64,
+    pub(crate) arbitration_id: u32,
+    pub(crate) is_extended_id: bool,
+    pub(crate) is_remote_frame: bool,
+    pub(crate) is_error_frame: bool,
+    pub(crate) channel: String,
+    pub(crate) length: usize,
+    pub(crate) data: Vec<u8>,
+    pub(crate) can_type: Type,
+    pub(crate) direct: Direct,
+    pub(crate) bitrate_switch: bool,
+    pub(crate) error_state_indicator: bool,
+    pub(crate) xl_control: XlControl,
+}
+
+impl Frame for Message {
+    type Channel = String;
+
+    fn new(id: impl Into<Id>, data: &[u8]) -> Option<Self> {
+        let length = data.len();
+
+        match utils::can_type(length) {
+            Ok(can_type) => {
+                let id: Id = id.into();
+                Some(Self {
+                    timestamp: 0,
+                    arbitration_id: id.as_raw(),
+                    is_extended_id: id.is_extended(),
+                    is_remote_frame: false,
+                    is_error_frame: false,
+                    channel: Default::default(),
+                    length,
+                    data: data.to_vec(),
+                    can_type,
+                    direct: Default::default(),
+                    bitrate_switch: false,
+                    error_state_indicator: false,
+                    xl_control: Default::default(),
+                })
+            },
+            Err(_) => None,
+        }
+    }
+
+    fn new_remote(id: impl Into<Id>, len: usize) -> Option<Self> {
+        match utils::can_type(len) {
+            Ok(can_type) => {
+                let id = id.into();
+                let mut data = Vec::new();
+                utils::data_resize(&mut data, len);
+                Some(Self {
+                    timestamp: 0,
+                    arbitration_id: id.as_raw(),
+                    is_extended_id: id.is_extended(),
+                    is_remote_frame: true,
+                    is_error_frame: false,
+                    channel: Default::default(),
+                    length: len,
+                    data,
+                    can_type,
+                    direct: Default::default(),
+                    bitrate_switch: false,
+                    error_state_indicator: false,
+                    xl_control: Default::default(),
+                })
+            },
+            Err(_) => None,
+        }
+    }
+
+    #[inline]
+    fn timestamp(&self) -> u64 {
+        self.timestamp / 1_000_000
+    }
+
+    #[inline]
+    fn set_timestamp(&mut self, value: Option<u64>) -> &mut Self {
+        self.timestamp = value.unwrap_or_else(utils::system_timestamp) * 1_000_000;
+        self
+    }
+
+    #[inline]
+    fn id(&self) -> Id {
+        Id::from_bits(self.arbitration_id, Some(self.is_extended_id))
+    }
+
+    #[inline]
+    fn can_type(&self) -> Type {
+        self.can_type
+    }
+
+    fn set_can_type(&mut self, r#type: Type) -> &mut Self {
+        match r#type {
+            Type::Can => if self.length > MAX_FRAME_SIZE {
+                log::warn!("resize a frame to: {}", MAX_FRAME_SIZE);
+                self.length = MAX_FRAME_SIZE;
+            },
+            Type::CanFd => if self.length > MAX_FD_FRAME_SIZE {
+                log::warn!("resize a frame to: {}", MAX_FD_FRAME_SIZE);
+                self.length = MAX_FD_FRAME_SIZE;
+            },
+            Type::CanXl => if self.length > MAX_XL_FRAME_SIZE {
+                log::warn!("resize a frame to: {}", MAX_XL_FRAME_SIZE);
+                self.length = MAX_XL_FRAME_SIZE;
+            },
+        }
+
+        self.data.truncate(self.length);
+        self.can_type = r#type;
+        self
+    }
+
+    #[inline]
+    fn is_remote(&self) -> bool {
+        self.is_remote_frame
+    }
+
+    #[inline]
+    fn is_extended(&self) -> bool {
+        self.is_extended_id
+    }
+
+    #[inline]
+    fn direct(&self) -> Direct {
+        self.direct
+    }
+
+    #[inline]
+    fn set_direct(&mut self, direct: Direct) -> &mut Self {
+        self.direct = direct;
+        self
+    }
+
+    #[inline]
+    fn is_bitrate_switch(&self) -> bool {
+        self.bitrate_switch
+    }
+
+    #[inline]
+    fn set_bitrate_switch(&mut self, value: bool) -> &mut Self {
+        self.bitrate_switch = value;
+        self
+    }
+
+    #[inline]
+    fn is_error_frame(&self) -> bool {
+        self.is_error_frame
+    }
+
+    #[inline]
+    fn set_error_frame(&mut self, value: bool) -> &mut Self {
+        self.is_error_frame = value;
+        self
+    }
+
+    #[inline]
+    fn is_esi(&self) -> bool {
+        self.error_state_indicator
+    }
+
+    #[inline]
+    fn set_esi(&mut self, value: bool) -> &mut Self {
+        self.error_state_indicator = value;
+        self
+    }
+
+    #[inline]
+    fn xl_control(&self) -> XlControl {
+        self.xl_control
+    }
+
+    #[inline]
+    fn set_xl_control(&mut self, value: XlControl) -> &mut Self {
+        self.xl_control = value;
+        self
+    }
+
+    #[inline]
+    fn channel(&self) -> Self::Channel {
+        self.channel.clone()
+    }
+
+    #[inline]
+    fn set_channel(&mut self, value: Self::Channel) -> &mut Self {
+        self.channel = value;
+        self
+    }
+
+    #[inline]
+    fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    #[inline]
+    fn length(&self) -> usize {
+        self.length
+    }
+}
+
+impl Message {
+    /// Convert from the frame of any backend, the channel is formatted by `Display`.
+    pub fn from_frame<F: Frame>(frame: &F) -> Self {
+        Self {
+            timestamp: frame.timestamp().saturating_mul(1_000_000),
+            arbitration_id: frame.id().as_raw(),
+            is_extended_id: frame.is_extended(),
+            is_remote_frame: frame.is_remote(),
+            is_error_frame: frame.is_error_frame(),
+            channel: frame.channel().to_string(),
+            length: frame.length(),
+            data: frame.data().to_vec(),
+            can_type: frame.can_type(),
+            direct: frame.direct(),
+            bitrate_switch: frame.is_bitrate_switch(),
+            error_state_indicator: frame.is_esi(),
+            xl_control: frame.xl_control(),
+        }
+    }
+
+    #[inline]
+    pub fn timestamp_ns(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Set timestamp in nanoseconds, the system time is used if `None`.
+    #[inline]
+    pub fn set_timestamp_ns(&mut self, value: Option<u64>) -> &mut Self {
+        self.timestamp = value.unwrap_or_else(utils::system_timestamp_ns);
+        self
+    }
+
+    /// The content compared, the data, BRS, ESI and XL control of remote frames are ignored.
+    #[allow(clippy::type_complexity)]
+    fn key(&self) -> (u32, Type, bool, bool, bool, bool, bool, XlControl, usize, &[u8]) {
+        if self.is_remote_frame {
+            (
+                self.arbitration_id,
+                self.can_type,
+                true,
+                self.is_extended_id,
+                self.is_error_frame,
+                false,
+                false,
+                Default::default(),
+                self.length,
+                &[],
+            )
+        }
+        else {
+            (
+                self.arbitration_id,
+                self.can_type,
+                false,
+                self.is_extended_id,
+                self.is_error_frame,
+                self.bitrate_switch,
+                self.error_state_indicator,
+                self.xl_control,
+                self.length,
+                self.data.as_slice(),
+            )
+        }
+    }
+}
+
+/// The fields of [`Message`] deserialized, they're checked before converted.
+#[derive(Deserialize)]
+struct Fields {
+    timestamp: u64,
+    arbitration_id: u32,
+    is_extended_id: bool,
+    is_remote_frame: bool,
+    is_error_frame: bool,
+    channel: String,
+    length: usize,
+    data: Vec<u8>,
+    can_type: Type,
+    direct: Direct,
+    bitrate_switch: bool,
+    error_state_indicator: bool,
+    xl_control: XlControl,
+}
+
+impl TryFrom<Fields> for Message {
+    type Error = Error;
+
+    fn try_from(v: Fields) -> Result<Self, Self::Error> {
+        let max = match v.can_type {
+            Type::Can => MAX_FRAME_SIZE,
+            Type::CanFd => MAX_FD_FRAME_SIZE,
+            Type::CanXl => MAX_XL_FRAME_SIZE,
+        };
+        if v.length > max {
+            return Err(Error::OtherError(format!("length {} is out of range of {:?}", v.length, v.can_type)));
+        }
+        // the data of remote frame is empty or padded.
+        if v.data.len() != v.length && !(v.is_remote_frame && v.data.is_empty()) {
+            return Err(Error::OtherError(format!("data length {} mismatch length {}", v.data.len(), v.length)));
+        }
+
+        Ok(Self {
+            timestamp: v.timestamp,
+            arbitration_id: v.arbitration_id,
+            is_extended_id: v.is_extended_id,
+            is_remote_frame: v.is_remote_frame,
+            is_error_frame: v.is_error_frame,
+            channel: v.channel,
+            length: v.length,
+            data: v.data,
+            can_type: v.can_type,
+            direct: v.direct,
+            bitrate_switch: v.bitrate_switch,
+            error_state_indicator: v.error_state_indicator,
+            xl_control: v.xl_control,
+        })
+    }
+}
+
+impl PartialEq for Message {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Message {}
+
+impl Hash for Message {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl PartialOrd for Message {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Message {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        <dyn Frame<Channel=String> as Display>::fmt(self, f)
+    }
+}
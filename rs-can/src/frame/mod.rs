@@ -1,11 +1,14 @@
 mod identifier;
 pub use identifier::*;
+mod message;
+pub use message::*;
 
-use std::fmt::{Display, Formatter, Write};
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
 use crate::utils::can_dlc;
 
 #[repr(C)]
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum Type {
     #[default]
     Can,
@@ -14,7 +17,7 @@ pub enum Type {
 }
 
 #[repr(C)]
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum Direct {
     #[default]
     Transmit,
@@ -24,12 +27,25 @@ pub enum Direct {
 impl Display for Direct {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Transmit => f.write_str("Tx"),
-            Self::Receive => f.write_str("Rx"),
+            Self::Transmit => f.pad("Tx"),
+            Self::Receive => f.pad("Rx"),
         }
     }
 }
 
+/// The control fields of CAN XL frame besides priority identifier.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct XlControl {
+    /// SDU type, the service data unit type of payload.
+    pub sdu_type: u8,
+    /// Virtual CAN network ID.
+    pub vcid: u8,
+    /// Acceptance field.
+    pub acceptance_field: u32,
+    /// Simple extended content.
+    pub sec: bool,
+}
+
 /// CAN 2.0 | CAN 1.0
 pub trait Frame: Send + Sync {
     type Channel: Display;
@@ -48,7 +64,7 @@ pub trait Frame: Send + Sync {
     where
         Self: Sized;
 
-    /// Prioritizes returning J1939Id if j1939 is true.
+    /// The identifier, J1939 identifier could be decomposed by `J1939Id::try_from`.
     fn id(&self) -> Id;
 
     fn can_type(&self) -> Type;
@@ -87,6 +103,19 @@ pub trait Frame: Send + Sync {
     where
         Self: Sized;
 
+    /// The CAN XL control fields, it's meaningful only when type is `CanXl`.
+    fn xl_control(&self) -> XlControl {
+        Default::default()
+    }
+
+    /// Set the CAN XL control fields, it's ignored by the frames of device without CAN XL.
+    fn set_xl_control(&mut self, _value: XlControl) -> &mut Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
     fn channel(&self) -> Self::Channel;
 
     fn set_channel(&mut self, value: Self::Channel) -> &mut Self
@@ -106,63 +135,6 @@ pub trait Frame: Send + Sync {
 impl<T: Display> Display for dyn Frame<Channel = T> {
     /// Output Frame as `asc` String.
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let data_str = if self.is_remote() {
-            " ".to_owned()
-        } else {
-            self.data()
-                .iter()
-                .fold(String::new(), |mut out, &b| {
-                    let _ = write!(out, "{b:02x} ");
-                    out
-                })
-        };
-
-        match self.can_type() {
-            Type::Can => {
-                write!(f, "{:.3} {} {}{: <4} {} {} {} {}",
-                       self.timestamp() as f64 / 1000.,
-                       self.channel(),
-                       format!("{: >8x}", self.id().into_bits()),
-                       if self.is_extended() { "x" } else { "" },
-                       self.direct(),
-                       // if self.is_rx() { "Rx" } else { "Tx" },
-                       if self.is_remote() { "r" } else { "d" },
-                       format!("{: >2}", self.length()),
-                       data_str,
-                )
-            },
-            Type::CanFd => {
-                let mut flags = 1 << 12;
-                write!(f, "{:.3} CANFD {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
-                       self.timestamp() as f64 / 1000.,
-                       self.channel(),
-                       self.direct(),
-                       // if self.is_rx() { "Rx" } else { "Tx" },
-                       format!("{: >8x}", self.id().into_bits()),
-                       if self.is_bitrate_switch() {
-                           flags |= 1 << 13;
-                           1
-                       } else { 0 },
-                       if self.is_esi() {
-                           flags |= 1 << 14;
-                           1
-                       } else { 0 },
-                       format!("{: >2}", self.dlc()),
-                       format!("{: >2}", self.length()),
-                       data_str,
-                       format!("{: >8}", 0),       // message_duration
-                       format!("{: <4}", 0),       // message_length
-                       format!("{: >8x}", flags),
-                       format!("{: >8}", 0),       // crc
-                       format!("{: >8}", 0),       // bit_timing_conf_arb
-                       format!("{: >8}", 0),       // bit_timing_conf_data
-                       format!("{: >8}", 0),       // bit_timing_conf_ext_arb
-                       format!("{: >8}", 0),       // bit_timing_conf_ext_data
-                )
-            },
-            Type::CanXl => {    // TODO
-                write!(f, "CANXL Frame")
-            }
-        }
+        crate::asc::fmt_frame(f, self, self.timestamp() as f64 / 1000., &self.channel(), Default::default())
     }
 }
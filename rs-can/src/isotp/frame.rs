@@ -0,0 +1,153 @@
+use std::time::Duration;
+use crate::constants::DEFAULT_PADDING;
+use crate::error::Error;
+use crate::utils;
+
+/// The max length of single frame data in classic CAN.
+pub const SINGLE_FRAME_SIZE: usize = 7;
+/// The max length of first frame without escape sequence.
+pub const FIRST_FRAME_SIZE: usize = 0xFFF;
+
+/// The flow status of flow control frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowStatus {
+    ContinueToSend = 0x00,
+    Wait = 0x01,
+    Overflow = 0x02,
+}
+
+/// The separation time minimum(STmin) of flow control frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StMin(pub u8);
+
+impl StMin {
+    /// The separation time, the reserved values are treated as 127ms.
+    pub fn duration(&self) -> Duration {
+        match self.0 {
+            0x00..=0x7F => Duration::from_millis(self.0 as u64),
+            0xF1..=0xF9 => Duration::from_micros((self.0 - 0xF0) as u64 * 100),
+            _ => Duration::from_millis(0x7F),
+        }
+    }
+}
+
+impl From<Duration> for StMin {
+    /// Convert the duration to the nearest STmin that not less than it.
+    fn from(value: Duration) -> Self {
+        match value.as_nanos() {
+            0 => Self(0),
+            1..=900_000 => Self(0xF0 + ((value + Duration::from_nanos(99_999)).as_micros() / 100) as u8),
+            _ => Self((value + Duration::from_nanos(999_999)).as_millis().min(0x7F) as u8),
+        }
+    }
+}
+
+/// The protocol data unit of ISO-TP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsoTpFrame {
+    SingleFrame { data: Vec<u8> },
+    FirstFrame { length: u32, data: Vec<u8> },
+    ConsecutiveFrame { sequence: u8, data: Vec<u8> },
+    FlowControl { status: FlowStatus, block_size: u8, st_min: StMin },
+}
+
+impl IsoTpFrame {
+    /// Decode the payload of CAN or CAN-FD frame.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let error = || Error::OperationError(format!("isotp: invalid frame {:02X?}", data));
+        let pci = *data.first().ok_or_else(error)?;
+
+        match pci >> 4 {
+            0x0 => {
+                let (length, offset) = match pci & 0x0F {
+                    0 => (*data.get(1).ok_or_else(error)? as usize, 2),
+                    v => (v as usize, 1),
+                };
+                if length == 0 {
+                    return Err(error());
+                }
+                let data = data.get(offset..offset + length).ok_or_else(error)?;
+                Ok(Self::SingleFrame { data: data.to_vec() })
+            },
+            0x1 => {
+                let length = ((pci & 0x0F) as u32) << 8 | *data.get(1).ok_or_else(error)? as u32;
+                let (length, offset) = match length {
+                    0 => {
+                        let bytes = data.get(2..6).ok_or_else(error)?;
+                        (u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]), 6)
+                    },
+                    v => (v, 2),
+                };
+                let data = data.get(offset..).unwrap_or_default();
+                if (length as usize) < data.len() {
+                    return Err(error());
+                }
+                Ok(Self::FirstFrame { length, data: data.to_vec() })
+            },
+            0x2 => Ok(Self::ConsecutiveFrame { sequence: pci & 0x0F, data: data[1..].to_vec() }),
+            0x3 => {
+                let status = match pci & 0x0F {
+                    0x00 => FlowStatus::ContinueToSend,
+                    0x01 => FlowStatus::Wait,
+                    0x02 => FlowStatus::Overflow,
+                    _ => return Err(error()),
+                };
+                let params = data.get(1..3).ok_or_else(error)?;
+                Ok(Self::FlowControl { status, block_size: params[0], st_min: StMin(params[1]) })
+            },
+            _ => Err(error()),
+        }
+    }
+
+    /// Encode to payload of CAN(`max_length` is 8) or CAN-FD frame.
+    ///
+    /// The payload is padded to a valid CAN-FD length, or to `max_length` of classic CAN
+    /// if padding is set; the data that out of `max_length` is truncated.
+    pub fn encode(&self, max_length: usize, padding: Option<u8>) -> Vec<u8> {
+        let mut result = match self {
+            Self::SingleFrame { data } => {
+                if data.len() <= SINGLE_FRAME_SIZE {
+                    let mut result = vec![data.len() as u8];
+                    result.extend_from_slice(data);
+                    result
+                }
+                else {
+                    let mut result = vec![0x00, data.len() as u8];
+                    result.extend_from_slice(data);
+                    result
+                }
+            },
+            Self::FirstFrame { length, data } => {
+                let mut result = if *length as usize > FIRST_FRAME_SIZE {
+                    let mut result = vec![0x10, 0x00];
+                    result.extend_from_slice(&length.to_be_bytes());
+                    result
+                }
+                else {
+                    vec![0x10 | (length >> 8) as u8, *length as u8]
+                };
+                result.extend_from_slice(data);
+                result
+            },
+            Self::ConsecutiveFrame { sequence, data } => {
+                let mut result = vec![0x20 | (sequence & 0x0F)];
+                result.extend_from_slice(data);
+                result
+            },
+            Self::FlowControl { status, block_size, st_min } => vec![0x30 | *status as u8, *block_size, st_min.0],
+        };
+        result.truncate(max_length);
+
+        let length = if result.len() > 8 {
+            utils::dlc_length(utils::dlc_code(result.len()))
+        }
+        else {
+            match padding {
+                Some(_) => max_length.min(8),
+                None => result.len(),
+            }
+        };
+        result.resize(length, padding.unwrap_or(DEFAULT_PADDING));
+        result
+    }
+}
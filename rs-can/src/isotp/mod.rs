@@ -0,0 +1,328 @@
+//! ISO-TP(ISO 15765-2) transport layer over any [`Device`].
+//!
+//! [`IsoTp`] segments and reassembles messages of up to 4GiB with single, first, consecutive
+//! and flow control frames. It's blocking and driven by the calling thread, so that it works on
+//! the adapters without kernel ISO-TP, like ZLG and NI.
+
+mod frame;
+
+pub use frame::*;
+
+use std::{collections::VecDeque, thread, time::{Duration, Instant}};
+use derive_getters::Getters;
+use crate::constants::{DEFAULT_PADDING, MAX_FD_FRAME_SIZE, MAX_FRAME_SIZE, POLL_INTERVAL};
+use crate::device::Device;
+use crate::error::Error;
+use crate::frame::{Direct, Frame, Id, Type};
+
+/// The default N_As, N_Bs and N_Cr timeout in milliseconds.
+pub const DEFAULT_TIMEOUT: u32 = 1000;
+/// The default max count of `Wait` flow control frames(N_WFTmax).
+pub const DEFAULT_MAX_WAIT: usize = 10;
+
+#[derive(Debug, Clone, Getters)]
+pub struct IsoTpConfig {
+    #[getter(copy)]
+    pub(crate) tx_id: Id,
+    #[getter(copy)]
+    pub(crate) rx_id: Id,
+    #[getter(copy)]
+    pub(crate) functional_id: Option<Id>,
+    #[getter(copy)]
+    pub(crate) fd: bool,
+    #[getter(copy)]
+    pub(crate) bitrate_switch: bool,
+    #[getter(copy)]
+    pub(crate) padding: Option<u8>,
+    #[getter(copy)]
+    pub(crate) block_size: u8,
+    #[getter(copy)]
+    pub(crate) st_min: StMin,
+    #[getter(copy)]
+    pub(crate) n_as: u32,
+    #[getter(copy)]
+    pub(crate) n_bs: u32,
+    #[getter(copy)]
+    pub(crate) n_cr: u32,
+    #[getter(copy)]
+    pub(crate) max_wait: usize,
+}
+
+impl IsoTpConfig {
+    /// Create config with physical addressing.
+    pub fn new(tx_id: impl Into<Id>, rx_id: impl Into<Id>) -> Self {
+        Self {
+            tx_id: tx_id.into(),
+            rx_id: rx_id.into(),
+            functional_id: Default::default(),
+            fd: false,
+            bitrate_switch: false,
+            padding: Some(DEFAULT_PADDING),
+            block_size: Default::default(),
+            st_min: Default::default(),
+            n_as: DEFAULT_TIMEOUT,
+            n_bs: DEFAULT_TIMEOUT,
+            n_cr: DEFAULT_TIMEOUT,
+            max_wait: DEFAULT_MAX_WAIT,
+        }
+    }
+
+    /// Set the functional address.
+    ///
+    /// Single frames are transmitted to it by [`IsoTp::send_functional`],
+    /// and the single frames from it are received as well as physical addressing.
+    pub fn set_functional_id(&mut self, id: impl Into<Id>) -> &mut Self {
+        self.functional_id = Some(id.into());
+        self
+    }
+
+    /// Transmit CAN-FD frames with payload up to 64 bytes.
+    pub fn set_fd(&mut self, fd: bool, bitrate_switch: bool) -> &mut Self {
+        self.fd = fd;
+        self.bitrate_switch = bitrate_switch;
+        self
+    }
+
+    /// Set padding byte of frames, the frames are not padded if `None`.
+    pub fn set_padding(&mut self, padding: Option<u8>) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Set block size and STmin of flow control frames transmitted when receiving.
+    pub fn set_flow_control(&mut self, block_size: u8, st_min: impl Into<StMin>) -> &mut Self {
+        self.block_size = block_size;
+        self.st_min = st_min.into();
+        self
+    }
+
+    /// Set N_As(transmit), N_Bs(wait flow control) and N_Cr(wait consecutive frame) timeouts in milliseconds.
+    pub fn set_timeouts(&mut self, n_as: u32, n_bs: u32, n_cr: u32) -> &mut Self {
+        self.n_as = n_as;
+        self.n_bs = n_bs;
+        self.n_cr = n_cr;
+        self
+    }
+
+    /// Set max count of `Wait` flow control frames accepted when sending.
+    pub fn set_max_wait(&mut self, max_wait: usize) -> &mut Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    #[inline]
+    fn max_length(&self) -> usize {
+        if self.fd { MAX_FD_FRAME_SIZE } else { MAX_FRAME_SIZE }
+    }
+}
+
+/// ISO-TP channel of a device, frames of the other identifiers received are dropped.
+pub struct IsoTp<D: Device> {
+    device: D,
+    channel: D::Channel,
+    config: IsoTpConfig,
+    buffer: VecDeque<(bool, Vec<u8>)>,
+}
+
+impl<D: Device> IsoTp<D>
+where
+    D::Channel: Clone,
+{
+    pub fn new(device: D, channel: D::Channel, config: IsoTpConfig) -> Self {
+        Self {
+            device,
+            channel,
+            config,
+            buffer: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &IsoTpConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Send data by physical addressing, it blocks until all frames transmitted.
+    pub fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        let max_length = self.config.max_length();
+        let sf_size = if self.config.fd { max_length - 2 } else { SINGLE_FRAME_SIZE };
+        if data.is_empty() || data.len() > u32::MAX as usize {
+            return Err(Error::OperationError(format!("isotp: invalid data length {}", data.len())));
+        }
+        if data.len() <= sf_size {
+            return self.write(self.config.tx_id, IsoTpFrame::SingleFrame { data: data.to_vec() });
+        }
+
+        let header = if data.len() > FIRST_FRAME_SIZE { 6 } else { 2 };
+        let (first, mut rest) = data.split_at(max_length - header);
+        self.write(self.config.tx_id, IsoTpFrame::FirstFrame { length: data.len() as u32, data: first.to_vec() })?;
+
+        let mut sequence = 1u8;
+        while !rest.is_empty() {
+            let (block_size, st_min) = self.wait_flow_control()?;
+            let mut count = 0;
+            while !rest.is_empty() && (block_size == 0 || count < block_size) {
+                let (chunk, remain) = rest.split_at(rest.len().min(max_length - 1));
+                self.write(self.config.tx_id, IsoTpFrame::ConsecutiveFrame { sequence, data: chunk.to_vec() })?;
+                rest = remain;
+                sequence = (sequence + 1) & 0x0F;
+                count += 1;
+                if !rest.is_empty() && !st_min.is_zero() {
+                    thread::sleep(st_min);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a single frame by functional addressing.
+    pub fn send_functional(&mut self, data: &[u8]) -> Result<(), Error> {
+        let id = self.config.functional_id
+            .ok_or_else(|| Error::OperationError("isotp: functional address is not set".into()))?;
+        let sf_size = if self.config.fd { self.config.max_length() - 2 } else { SINGLE_FRAME_SIZE };
+        if data.is_empty() || data.len() > sf_size {
+            return Err(Error::OperationError(format!("isotp: invalid functional data length {}", data.len())));
+        }
+
+        self.write(id, IsoTpFrame::SingleFrame { data: data.to_vec() })
+    }
+
+    /// Receive a message, `timeout` is the time in milliseconds to wait first frame(forever if `None`).
+    ///
+    /// The unexpected frames are dropped while waiting single or first frame.
+    pub fn receive(&mut self, timeout: Option<u32>) -> Result<Vec<u8>, Error> {
+        let deadline = timeout.map(|v| Instant::now() + Duration::from_millis(v as u64));
+        loop {
+            let (functional, frame) = self.read(deadline)?
+                .ok_or_else(|| Error::TimeoutError(format!("isotp: receive at channel {}", self.channel)))?;
+            match frame {
+                IsoTpFrame::SingleFrame { data } => return Ok(data),
+                IsoTpFrame::FirstFrame { length, data } if !functional => return self.receive_consecutive(length as usize, data),
+                frame => log::warn!("RUST-CAN - isotp unexpected frame: {:?}", frame),
+            }
+        }
+    }
+
+    fn receive_consecutive(&mut self, length: usize, mut data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let block_size = self.config.block_size;
+        let st_min = self.config.st_min;
+        let mut sequence = 1u8;
+        // the length of first frame is not trusted.
+        data.reserve((length - data.len()).min(u16::MAX as usize));
+
+        while data.len() < length {
+            self.write(self.config.tx_id, IsoTpFrame::FlowControl { status: FlowStatus::ContinueToSend, block_size, st_min })?;
+            let mut count = 0;
+            while data.len() < length && (block_size == 0 || count < block_size) {
+                let deadline = Instant::now() + Duration::from_millis(self.config.n_cr as u64);
+                let frame = loop {
+                    match self.read(Some(deadline))? {
+                        Some((false, IsoTpFrame::ConsecutiveFrame { sequence: seq, data })) => break (seq, data),
+                        Some((_, frame)) => log::warn!("RUST-CAN - isotp unexpected frame: {:?}", frame),
+                        None => return Err(Error::TimeoutError(format!("isotp: N_Cr at channel {}", self.channel))),
+                    }
+                };
+                if frame.0 != sequence {
+                    return Err(Error::OperationError(format!("isotp: sequence {} expected, but {} received", sequence, frame.0)));
+                }
+                let remain = length - data.len();
+                data.extend_from_slice(&frame.1[..frame.1.len().min(remain)]);
+                sequence = (sequence + 1) & 0x0F;
+                count += 1;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Wait `ContinueToSend` flow control and return block size and STmin.
+    fn wait_flow_control(&mut self) -> Result<(u8, Duration), Error> {
+        let mut waits = 0;
+        loop {
+            let deadline = Instant::now() + Duration::from_millis(self.config.n_bs as u64);
+            let frame = loop {
+                match self.read(Some(deadline))? {
+                    Some((false, frame @ IsoTpFrame::FlowControl { .. })) => break frame,
+                    Some((_, frame)) => log::warn!("RUST-CAN - isotp unexpected frame: {:?}", frame),
+                    None => return Err(Error::TimeoutError(format!("isotp: N_Bs at channel {}", self.channel))),
+                }
+            };
+
+            if let IsoTpFrame::FlowControl { status, block_size, st_min } = frame {
+                match status {
+                    FlowStatus::ContinueToSend => return Ok((block_size, st_min.duration())),
+                    FlowStatus::Wait => {
+                        waits += 1;
+                        if waits > self.config.max_wait {
+                            return Err(Error::OperationError("isotp: too many wait flow control".into()));
+                        }
+                    },
+                    FlowStatus::Overflow => return Err(Error::OperationError("isotp: receiver overflow".into())),
+                }
+            }
+        }
+    }
+
+    fn write(&self, id: Id, frame: IsoTpFrame) -> Result<(), Error> {
+        let data = frame.encode(self.config.max_length(), self.config.padding);
+        let mut msg = D::Frame::new(id, &data)
+            .ok_or_else(|| Error::OperationError(format!("isotp: invalid frame {:02X?}", data)))?;
+        if self.config.fd {
+            msg.set_can_type(Type::CanFd)
+                .set_bitrate_switch(self.config.bitrate_switch);
+        }
+        msg.set_channel(self.channel.clone())
+            .set_direct(Direct::Transmit);
+
+        self.device.transmit(msg, Some(self.config.n_as))
+    }
+
+    /// Read next frame of receive or functional address until deadline(forever if `None`).
+    ///
+    /// The device is received at least once, even if the deadline has passed.
+    fn read(&mut self, deadline: Option<Instant>) -> Result<Option<(bool, IsoTpFrame)>, Error> {
+        let mut received = false;
+        loop {
+            while let Some((functional, data)) = self.buffer.pop_front() {
+                match IsoTpFrame::decode(&data) {
+                    Ok(frame) => return Ok(Some((functional, frame))),
+                    Err(e) => log::warn!("RUST-CAN - {}", e),
+                }
+            }
+
+            let now = Instant::now();
+            if received && deadline.is_some_and(|v| now >= v) {
+                return Ok(None);
+            }
+            received = true;
+            // round up to avoid busy loop at the last millisecond.
+            let timeout = match deadline {
+                Some(v) => v.saturating_duration_since(now).as_millis() as u32 + 1,
+                None => POLL_INTERVAL,
+            };
+            let frames = match self.device.receive(self.channel.clone(), Some(timeout)) {
+                Ok(v) => v,
+                Err(Error::TimeoutError(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            for frame in frames {
+                if frame.is_error_frame() || frame.is_remote() {
+                    continue;
+                }
+                let id = frame.id();
+                if id == self.config.rx_id {
+                    self.buffer.push_back((false, frame.data().to_vec()));
+                }
+                else if Some(id) == self.config.functional_id {
+                    self.buffer.push_back((true, frame.data().to_vec()));
+                }
+            }
+        }
+    }
+}
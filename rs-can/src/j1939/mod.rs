@@ -0,0 +1,513 @@
+//! SAE J1939 over any [`Device`].
+//!
+//! [`J1939`] claims address with [`Name`], sends and receives parameter groups with
+//! BAM and RTS/CTS transport protocol(up to 1785 bytes), and responds request of address claimed.
+//! It's blocking and driven by the calling thread like [`crate::isotp::IsoTp`].
+
+mod name;
+
+pub use name::*;
+
+use std::{collections::{HashMap, VecDeque}, thread, time::{Duration, Instant}};
+use crate::constants::POLL_INTERVAL;
+use crate::device::Device;
+use crate::error::Error;
+use crate::frame::{Direct, Frame, J1939Id};
+
+/// PGN of request.
+pub const PGN_REQUEST: u32 = 0xEA00;
+/// PGN of address claimed.
+pub const PGN_ADDRESS_CLAIMED: u32 = 0xEE00;
+/// PGN of transport protocol connection management.
+pub const PGN_TP_CM: u32 = 0xEC00;
+/// PGN of transport protocol data transfer.
+pub const PGN_TP_DT: u32 = 0xEB00;
+/// The global(broadcast) address.
+pub const GLOBAL_ADDRESS: u8 = 0xFF;
+/// The null address, it's used to claim when address could not be claimed.
+pub const NULL_ADDRESS: u8 = 0xFE;
+/// The max size of data transferred by transport protocol.
+pub const MAX_TP_SIZE: usize = 1785;
+/// The default priority of parameter groups.
+pub const DEFAULT_PRIORITY: u8 = 6;
+
+const TP_PRIORITY: u8 = 7;
+const TP_CM_RTS: u8 = 16;
+const TP_CM_CTS: u8 = 17;
+const TP_CM_EOMA: u8 = 19;
+const TP_CM_BAM: u8 = 32;
+const TP_CM_ABORT: u8 = 255;
+/// The abort reason of timeout.
+const ABORT_TIMEOUT: u8 = 3;
+/// Timeouts in milliseconds.
+const T1: u64 = 750;
+const T2: u64 = 1250;
+const T3: u64 = 1250;
+const T4: u64 = 1050;
+const CLAIM_TIMEOUT: u64 = 250;
+
+/// The parameter group received or to send.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct J1939Message {
+    pub pgn: u32,
+    pub priority: u8,
+    pub source: u8,
+    /// The destination of PDU1, it's [`GLOBAL_ADDRESS`] if PDU2 or broadcast.
+    pub destination: u8,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Session {
+    pgn: u32,
+    priority: u8,
+    size: usize,
+    packets: u8,
+    next: u8,
+    /// The last sequence of current CTS block, it's 0 for BAM.
+    block_end: u8,
+    data: Vec<u8>,
+    deadline: Instant,
+}
+
+impl Session {
+    #[inline]
+    fn is_bam(&self) -> bool {
+        self.block_end == 0
+    }
+}
+
+#[inline(always)]
+fn pgn_bytes(pgn: u32) -> [u8; 3] {
+    let [b0, b1, b2, _] = pgn.to_le_bytes();
+    [b0, b1, b2]
+}
+
+pub struct J1939<D: Device> {
+    device: D,
+    channel: D::Channel,
+    name: Name,
+    address: Option<u8>,
+    controllers: HashMap<u8, Name>,
+    /// The frames skipped by claiming and sending, they're processed by next receiving.
+    pending: VecDeque<(J1939Id, Vec<u8>)>,
+    /// The rest of frames received from device in a batch, they're not read yet.
+    unread: VecDeque<(J1939Id, Vec<u8>)>,
+    sessions: HashMap<u8, Session>,
+    bam_interval: Duration,
+    max_packets: u8,
+}
+
+impl<D: Device> J1939<D>
+where
+    D::Channel: Clone,
+{
+    pub fn new(device: D, channel: D::Channel, name: Name) -> Self {
+        Self {
+            device,
+            channel,
+            name,
+            address: Default::default(),
+            controllers: Default::default(),
+            pending: Default::default(),
+            unread: Default::default(),
+            sessions: Default::default(),
+            bam_interval: Duration::from_millis(50),
+            max_packets: u8::MAX,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> Name {
+        self.name
+    }
+
+    /// The address claimed, it's `None` before claimed or lost.
+    #[inline]
+    pub fn address(&self) -> Option<u8> {
+        self.address
+    }
+
+    /// Use address without claiming.
+    pub fn set_address(&mut self, address: u8) -> &mut Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Set interval between data transfer frames of BAM, it should be 50~200ms.
+    pub fn set_bam_interval(&mut self, interval: Duration) -> &mut Self {
+        self.bam_interval = interval;
+        self
+    }
+
+    /// Set max number of packets in one CTS when receiving by RTS/CTS.
+    pub fn set_max_packets(&mut self, max_packets: u8) -> &mut Self {
+        self.max_packets = max_packets.max(1);
+        self
+    }
+
+    /// The NAME of controllers that claimed address.
+    #[inline]
+    pub fn controllers(&self) -> &HashMap<u8, Name> {
+        &self.controllers
+    }
+
+    /// Claim address and wait 250ms for contending claims.
+    ///
+    /// Another address in 128~247 is selected if lost and NAME is arbitrary address capable,
+    /// otherwise the cannot claim address message is sent and an error is returned.
+    pub fn claim_address(&mut self, preferred: u8) -> Result<u8, Error> {
+        self.address = None;
+        let mut candidate = preferred;
+        self.send_claim(candidate)?;
+        let mut deadline = Instant::now() + Duration::from_millis(CLAIM_TIMEOUT);
+
+        while let Some((id, data)) = self.read(Some(deadline), false)? {
+            if id.pgn() == PGN_ADDRESS_CLAIMED && data.len() >= 8 {
+                let other = Name::from_bytes([data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]]);
+                self.controllers.insert(id.sa(), other);
+                if id.sa() != candidate || other == self.name {
+                    continue;
+                }
+                if other > self.name {
+                    self.send_claim(candidate)?;
+                    continue;
+                }
+
+                let next = (128..=247u8).find(|v| !self.controllers.contains_key(v));
+                match next {
+                    Some(v) if self.name.is_arbitrary_address_capable() => {
+                        candidate = v;
+                        self.send_claim(candidate)?;
+                        deadline = Instant::now() + Duration::from_millis(CLAIM_TIMEOUT);
+                    },
+                    _ => {
+                        self.send_claim(NULL_ADDRESS)?;
+                        return Err(Error::OperationError(format!("j1939: address 0x{:02X} could not be claimed", candidate)));
+                    },
+                }
+            }
+            else if self.is_claim_request(&id, &data, Some(candidate)) {
+                self.send_claim(candidate)?;
+            }
+            else {
+                self.pending.push_back((id, data));
+            }
+        }
+
+        self.address = Some(candidate);
+        Ok(candidate)
+    }
+
+    /// Send request of PGN.
+    pub fn request(&mut self, pgn: u32, destination: u8) -> Result<(), Error> {
+        self.send(PGN_REQUEST, DEFAULT_PRIORITY, destination, &pgn_bytes(pgn))
+    }
+
+    /// Send parameter group, the data longer than 8 bytes is sent by BAM if destination is global
+    /// or PGN is PDU2 format, otherwise by RTS/CTS.
+    pub fn send(&mut self, pgn: u32, priority: u8, destination: u8, data: &[u8]) -> Result<(), Error> {
+        let source = self.address
+            .ok_or_else(|| Error::OperationError("j1939: address is not claimed".into()))?;
+        let id = J1939Id::new(priority, pgn, destination, source);
+        if data.len() <= 8 {
+            return self.write(id, data);
+        }
+        if data.len() > MAX_TP_SIZE {
+            return Err(Error::OperationError(format!("j1939: data length {} is too long", data.len())));
+        }
+
+        let destination = id.destination().unwrap_or(GLOBAL_ADDRESS);
+        let [size_lo, size_hi] = (data.len() as u16).to_le_bytes();
+        let packets = ((data.len() - 1) / 7 + 1) as u8;
+        let cm_id = J1939Id::new(TP_PRIORITY, PGN_TP_CM, destination, source);
+        let dt_id = J1939Id::new(TP_PRIORITY, PGN_TP_DT, destination, source);
+        let [p0, p1, p2] = pgn_bytes(pgn);
+
+        if destination == GLOBAL_ADDRESS {
+            self.write(cm_id, &[TP_CM_BAM, size_lo, size_hi, packets, 0xFF, p0, p1, p2])?;
+            for sequence in 1..=packets {
+                thread::sleep(self.bam_interval);
+                self.write_packet(dt_id, data, sequence)?;
+            }
+            return Ok(());
+        }
+
+        self.write(cm_id, &[TP_CM_RTS, size_lo, size_hi, packets, 0xFF, p0, p1, p2])?;
+        let mut deadline = Instant::now() + Duration::from_millis(T3);
+        loop {
+            let Some((id, cm)) = self.read(Some(deadline), false)? else {
+                self.write(cm_id, &[TP_CM_ABORT, ABORT_TIMEOUT, 0xFF, 0xFF, 0xFF, p0, p1, p2])?;
+                return Err(Error::TimeoutError(format!("j1939: RTS/CTS of PGN 0x{:04X}", pgn)));
+            };
+            if id.pgn() != PGN_TP_CM || id.sa() != destination || id.destination() != Some(source) || cm.len() < 8 {
+                self.pending.push_back((id, cm));
+                continue;
+            }
+
+            match cm[0] {
+                TP_CM_CTS => {
+                    let (count, next) = (cm[1], cm[2]);
+                    if count == 0 {
+                        deadline = Instant::now() + Duration::from_millis(T4);
+                        continue;
+                    }
+                    if next == 0 || next > packets {
+                        self.send_abort(destination, pgn)?;
+                        return Err(Error::OperationError(format!("j1939: CTS of invalid next packet {}", next)));
+                    }
+                    for sequence in next..=next.saturating_add(count - 1).min(packets) {
+                        self.write_packet(dt_id, data, sequence)?;
+                    }
+                    deadline = Instant::now() + Duration::from_millis(T3);
+                },
+                TP_CM_EOMA => return Ok(()),
+                TP_CM_ABORT => return Err(Error::OperationError(format!("j1939: RTS/CTS aborted with reason {}", cm[1]))),
+                _ => log::warn!("RUST-CAN - j1939 unexpected TP.CM: {:02X?}", cm),
+            }
+        }
+    }
+
+    /// Receive parameter group for this address or global, `timeout` is in milliseconds(forever if `None`).
+    ///
+    /// The transport protocol is reassembled, and the request of address claimed is responded.
+    pub fn receive(&mut self, timeout: Option<u32>) -> Result<J1939Message, Error> {
+        let deadline = timeout.map(|v| Instant::now() + Duration::from_millis(v as u64));
+        // the frames received by device already are read even if timeout is 0.
+        if self.pending.is_empty() && self.unread.is_empty() {
+            self.receive_device(0)?;
+        }
+        loop {
+            self.expire_sessions(Instant::now())?;
+            let wait = self.sessions.values()
+                .map(|s| s.deadline)
+                .chain(deadline)
+                .min();
+            match self.read(wait, true)? {
+                Some((id, data)) => {
+                    if let Some(msg) = self.process(id, data)? {
+                        return Ok(msg);
+                    }
+                },
+                None if deadline.is_some_and(|v| Instant::now() >= v) => {
+                    return Err(Error::TimeoutError(format!("j1939: receive at channel {}", self.channel)));
+                },
+                None => {},
+            }
+        }
+    }
+
+    fn process(&mut self, id: J1939Id, data: Vec<u8>) -> Result<Option<J1939Message>, Error> {
+        let destination = id.destination().unwrap_or(GLOBAL_ADDRESS);
+        if destination != GLOBAL_ADDRESS && Some(destination) != self.address {
+            return Ok(None);
+        }
+
+        match id.pgn() {
+            PGN_ADDRESS_CLAIMED if data.len() >= 8 => {
+                let other = Name::from_bytes([data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]]);
+                self.controllers.insert(id.sa(), other);
+                if Some(id.sa()) == self.address && other != self.name {
+                    if other > self.name {
+                        self.send_claim(id.sa())?;
+                    }
+                    else {
+                        log::warn!("RUST-CAN - j1939 address 0x{:02X} is lost", id.sa());
+                        self.address = None;
+                        self.send_claim(NULL_ADDRESS)?;
+                    }
+                }
+                Ok(None)
+            },
+            PGN_REQUEST if self.is_claim_request(&id, &data, self.address) => {
+                if let Some(address) = self.address {
+                    self.send_claim(address)?;
+                }
+                Ok(None)
+            },
+            PGN_TP_CM if data.len() >= 8 => {
+                let size = u16::from_le_bytes([data[1], data[2]]) as usize;
+                let pgn = u32::from_le_bytes([data[5], data[6], data[7], 0]);
+                match data[0] {
+                    TP_CM_BAM | TP_CM_RTS => {
+                        let bam = data[0] == TP_CM_BAM;
+                        let packets = data[3];
+                        let block_end = if bam { 0 } else { packets.min(self.max_packets) };
+                        self.sessions.insert(id.sa(), Session {
+                            pgn,
+                            priority: id.priority(),
+                            size,
+                            packets,
+                            next: 1,
+                            block_end,
+                            data: Vec::with_capacity(size),
+                            deadline: Instant::now() + Duration::from_millis(T1),
+                        });
+                        if !bam {
+                            self.send_cts(id.sa(), pgn, block_end, 1)?;
+                        }
+                    },
+                    TP_CM_ABORT => {
+                        self.sessions.remove(&id.sa());
+                    },
+                    _ => {},
+                }
+                Ok(None)
+            },
+            PGN_TP_DT if !data.is_empty() => self.process_packet(id, &data),
+            pgn => Ok(Some(J1939Message { pgn, priority: id.priority(), source: id.sa(), destination, data })),
+        }
+    }
+
+    fn process_packet(&mut self, id: J1939Id, data: &[u8]) -> Result<Option<J1939Message>, Error> {
+        let source = id.sa();
+        let Some(session) = self.sessions.get_mut(&source) else {
+            return Ok(None);
+        };
+        if data[0] != session.next {
+            log::warn!("RUST-CAN - j1939 sequence {} expected, but {} received", session.next, data[0]);
+            let session = self.sessions.remove(&source);
+            if let Some(session) = session.filter(|s| !s.is_bam()) {
+                self.send_abort(source, session.pgn)?;
+            }
+            return Ok(None);
+        }
+
+        let sequence = data[0];
+        session.data.extend_from_slice(&data[1..]);
+        session.next = sequence.wrapping_add(1);
+        session.deadline = Instant::now() + Duration::from_millis(T1);
+
+        if sequence == session.packets {
+            let Some(mut session) = self.sessions.remove(&source) else {
+                return Ok(None);
+            };
+            session.data.truncate(session.size);
+            let destination = if session.is_bam() {
+                GLOBAL_ADDRESS
+            }
+            else {
+                let [size_lo, size_hi] = (session.size as u16).to_le_bytes();
+                let [p0, p1, p2] = pgn_bytes(session.pgn);
+                self.write(J1939Id::new(TP_PRIORITY, PGN_TP_CM, source, id.ps()),
+                           &[TP_CM_EOMA, size_lo, size_hi, session.packets, 0xFF, p0, p1, p2])?;
+                id.ps()
+            };
+            return Ok(Some(J1939Message {
+                pgn: session.pgn,
+                priority: session.priority,
+                source,
+                destination,
+                data: session.data,
+            }));
+        }
+
+        if !session.is_bam() && sequence == session.block_end {
+            let count = (session.packets - session.block_end).min(self.max_packets);
+            let (pgn, next) = (session.pgn, session.next);
+            session.block_end += count;
+            session.deadline = Instant::now() + Duration::from_millis(T2);
+            self.send_cts(source, pgn, count, next)?;
+        }
+
+        Ok(None)
+    }
+
+    fn expire_sessions(&mut self, now: Instant) -> Result<(), Error> {
+        let expired = self.sessions.iter()
+            .filter(|(_, s)| s.deadline <= now)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        for source in expired {
+            if let Some(session) = self.sessions.remove(&source) {
+                log::warn!("RUST-CAN - j1939 transport of PGN 0x{:04X} from 0x{:02X} timeout", session.pgn, source);
+                if !session.is_bam() {
+                    self.send_abort(source, session.pgn)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_claim_request(&self, id: &J1939Id, data: &[u8], address: Option<u8>) -> bool {
+        id.pgn() == PGN_REQUEST
+            && data.get(..3) == Some(&pgn_bytes(PGN_ADDRESS_CLAIMED))
+            && (id.ps() == GLOBAL_ADDRESS || Some(id.ps()) == address)
+    }
+
+    fn send_claim(&self, address: u8) -> Result<(), Error> {
+        self.write(J1939Id::new(DEFAULT_PRIORITY, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, address), &self.name.to_bytes())
+    }
+
+    fn send_cts(&self, destination: u8, pgn: u32, count: u8, next: u8) -> Result<(), Error> {
+        let source = self.address.unwrap_or(NULL_ADDRESS);
+        let [p0, p1, p2] = pgn_bytes(pgn);
+        self.write(J1939Id::new(TP_PRIORITY, PGN_TP_CM, destination, source),
+                   &[TP_CM_CTS, count, next, 0xFF, 0xFF, p0, p1, p2])
+    }
+
+    fn send_abort(&self, destination: u8, pgn: u32) -> Result<(), Error> {
+        let source = self.address.unwrap_or(NULL_ADDRESS);
+        let [p0, p1, p2] = pgn_bytes(pgn);
+        self.write(J1939Id::new(TP_PRIORITY, PGN_TP_CM, destination, source),
+                   &[TP_CM_ABORT, ABORT_TIMEOUT, 0xFF, 0xFF, 0xFF, p0, p1, p2])
+    }
+
+    /// Write the data transfer packet of sequence(1-based), it's padded with 0xFF.
+    fn write_packet(&self, id: J1939Id, data: &[u8], sequence: u8) -> Result<(), Error> {
+        let offset = (sequence as usize - 1) * 7;
+        let mut packet = vec![sequence];
+        packet.extend_from_slice(&data[offset..data.len().min(offset + 7)]);
+        packet.resize(8, 0xFF);
+        self.write(id, &packet)
+    }
+
+    fn write(&self, id: J1939Id, data: &[u8]) -> Result<(), Error> {
+        let mut msg = D::Frame::new(id, data)
+            .ok_or_else(|| Error::OperationError(format!("j1939: invalid frame {:02X?}", data)))?;
+        msg.set_channel(self.channel.clone())
+            .set_direct(Direct::Transmit);
+
+        self.device.transmit(msg, None)
+    }
+
+    /// Read next extended frame until deadline(forever if `None`), the pending frames are read first if `pending` is true.
+    ///
+    /// The frames not read yet are read in order before receiving from device.
+    fn read(&mut self, deadline: Option<Instant>, pending: bool) -> Result<Option<(J1939Id, Vec<u8>)>, Error> {
+        if pending {
+            if let Some(v) = self.pending.pop_front() {
+                return Ok(Some(v));
+            }
+        }
+
+        loop {
+            if let Some(v) = self.unread.pop_front() {
+                return Ok(Some(v));
+            }
+
+            let now = Instant::now();
+            let timeout = match deadline {
+                Some(v) if now >= v => return Ok(None),
+                // round up to avoid busy loop at the last millisecond.
+                Some(v) => (v - now).as_millis() as u32 + 1,
+                None => POLL_INTERVAL,
+            };
+            self.receive_device(timeout)?;
+        }
+    }
+
+    /// Receive frames from device in `timeout` milliseconds, the extended frames are appended to unread.
+    fn receive_device(&mut self, timeout: u32) -> Result<(), Error> {
+        let frames = match self.device.receive(self.channel.clone(), Some(timeout)) {
+            Ok(v) => v,
+            Err(Error::TimeoutError(_)) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        self.unread.extend(frames.into_iter()
+            .filter(|f| f.is_extended() && !f.is_error_frame() && !f.is_remote())
+            .filter_map(|f| Some((J1939Id::try_from(f.id()).ok()?, f.data().to_vec()))));
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+/// The 64-bit NAME of J1939 controller application, the lower NAME wins the address claiming.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Name(pub u64);
+
+macro_rules! name_field {
+    ($getter: ident, $setter: ident, $type: ty, $offset: expr, $bits: expr) => {
+        #[inline]
+        pub fn $getter(&self) -> $type {
+            ((self.0 >> $offset) & ((1 << $bits) - 1)) as $type
+        }
+
+        #[inline]
+        pub fn $setter(&mut self, value: $type) -> &mut Self {
+            let mask = ((1u64 << $bits) - 1) << $offset;
+            self.0 = (self.0 & !mask) | ((value as u64) << $offset & mask);
+            self
+        }
+    };
+}
+
+impl Name {
+    name_field!(identity_number, set_identity_number, u32, 0, 21);
+    name_field!(manufacturer_code, set_manufacturer_code, u16, 21, 11);
+    name_field!(ecu_instance, set_ecu_instance, u8, 32, 3);
+    name_field!(function_instance, set_function_instance, u8, 35, 5);
+    name_field!(function, set_function, u8, 40, 8);
+    name_field!(vehicle_system, set_vehicle_system, u8, 49, 7);
+    name_field!(vehicle_system_instance, set_vehicle_system_instance, u8, 56, 4);
+    name_field!(industry_group, set_industry_group, u8, 60, 3);
+
+    /// The controller could select another address when it lost the address claiming.
+    #[inline]
+    pub fn is_arbitrary_address_capable(&self) -> bool {
+        self.0 >> 63 == 1
+    }
+
+    #[inline]
+    pub fn set_arbitrary_address_capable(&mut self, value: bool) -> &mut Self {
+        self.0 = (self.0 & !(1 << 63)) | (value as u64) << 63;
+        self
+    }
+
+    /// The data of address claimed message.
+    #[inline]
+    pub fn to_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    #[inline]
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self(u64::from_le_bytes(bytes))
+    }
+}
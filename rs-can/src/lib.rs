@@ -1,12 +1,30 @@
+#[cfg(feature = "async")]
+mod async_device;
+mod any_device;
 mod constants;
 mod device;
 mod error;
 mod frame;
+pub mod asc;
+pub mod blf;
+pub mod trc;
 pub mod can_utils;
+pub mod dbc;
+pub mod isotp;
+pub mod j1939;
+pub mod uds;
+pub mod registry;
+pub mod replay;
+pub mod service;
+pub mod virtual_can;
 
 pub(crate) use can_utils as utils;
 
+pub use crate::any_device::AnyDevice as AnyCanDevice;
 pub use crate::constants::*;
-pub use crate::device::{ChannelConfig, Device as CanDevice, DeviceBuilder, Listener as CanListener, CanResult};
+pub use crate::device::{ChannelConfig, Device as CanDevice, DeviceBuilder, DeviceConfig, Listener as CanListener, CanResult};
 pub use crate::error::{Error as CanError};
-pub use crate::frame::{Direct as CanDirect, Frame as CanFrame, Type as CanType, Id as CanId, Filter as CanFilter, IdentifierFlags};
+pub use crate::frame::{Direct as CanDirect, Frame as CanFrame, Type as CanType, Id as CanId, Filter as CanFilter, IdentifierFlags, J1939Id, Message as CanMessage, XlControl as CanXlControl};
+pub use crate::virtual_can::{VirtualBus, VirtualCan};
+#[cfg(feature = "async")]
+pub use crate::async_device::{AsyncDevice as AsyncCanDevice, BlockingAdapter, BLOCKING_POLL_INTERVAL};
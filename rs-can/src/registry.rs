@@ -0,0 +1,147 @@
+//! Registry of device backends, the device is opened by a URI-like string at runtime.
+//!
+//! The URI is `<backend>://<channel>[,<channel>...][?<key>=<value>[&<key>=<value>...]]`,
+//! like `socketcan://can0,can1?bitrate=500000`, and the parameters are interpreted by backend.
+//! The `virtual` backend is registered by default, and the other backends are registered by
+//! the `register` function of their crates.
+
+use std::{collections::HashMap, fmt::{Display, Formatter}, str::FromStr, sync::{Mutex, MutexGuard, OnceLock}};
+use crate::any_device::AnyDevice;
+use crate::device::DeviceConfig;
+use crate::error::Error;
+use crate::virtual_can::{VirtualCanConfig, VirtualChannelConfig};
+
+/// The name of [`VirtualCan`](crate::VirtualCan) backend.
+pub const VIRTUAL: &str = "virtual";
+
+/// Open the device of backend by URI.
+pub type Opener = fn(&DeviceUri) -> Result<Box<dyn AnyDevice>, Error>;
+
+/// The parsed URI of device, the values are not escaped, so `&`, `=` and `,` are not allowed in them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DeviceUri {
+    backend: String,
+    channels: Vec<String>,
+    params: Vec<(String, String)>,
+}
+
+impl DeviceUri {
+    #[inline]
+    pub fn backend(&self) -> &str {
+        &self.backend
+    }
+
+    #[inline]
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// The raw value of parameter, the last one is taken if repeated.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The value of parameter parsed, it's an error if the value is not a `T`.
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>, Error> {
+        self.param(key)
+            .map(|v| v.parse::<T>()
+                .map_err(|_| Error::OtherError(format!("invalid value of `{}`: {}", key, v))))
+            .transpose()
+    }
+}
+
+impl FromStr for DeviceUri {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (backend, rest) = s.split_once("://")
+            .ok_or_else(|| Error::OtherError(format!("invalid device URI: {}", s)))?;
+        if backend.is_empty() {
+            return Err(Error::OtherError(format!("backend not found in device URI: {}", s)));
+        }
+
+        let (channels, query) = rest.split_once('?')
+            .unwrap_or((rest, ""));
+        let channels = channels.split(',')
+            .filter(|c| !c.is_empty())
+            .map(|c| c.to_owned())
+            .collect();
+        let params = query.split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| match p.split_once('=') {
+                Some((k, v)) => (k.to_owned(), v.to_owned()),
+                // the flag without value is true.
+                None => (p.to_owned(), "true".to_owned()),
+            })
+            .collect();
+
+        Ok(Self { backend: backend.to_owned(), channels, params })
+    }
+}
+
+impl Display for DeviceUri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}://{}", self.backend, self.channels.join(","))?;
+        for (i, (k, v)) in self.params.iter().enumerate() {
+            write!(f, "{}{}={}", if i == 0 { '?' } else { '&' }, k, v)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn openers() -> &'static Mutex<HashMap<String, Opener>> {
+    static OPENERS: OnceLock<Mutex<HashMap<String, Opener>>> = OnceLock::new();
+    OPENERS.get_or_init(|| Mutex::new(HashMap::from([(VIRTUAL.to_owned(), open_virtual as Opener)])))
+}
+
+/// Register a backend, the opener registered by the same name is replaced and returned.
+pub fn register(name: &str, opener: Opener) -> Option<Opener> {
+    lock(openers()).insert(name.to_owned(), opener)
+}
+
+pub fn unregister(name: &str) -> Option<Opener> {
+    lock(openers()).remove(name)
+}
+
+/// The names of backends registered.
+pub fn backends() -> Vec<String> {
+    let mut names = lock(openers()).keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Open the device by URI, like `virtual://bus0?latency=5`.
+pub fn open(uri: &str) -> Result<Box<dyn AnyDevice>, Error> {
+    let uri = uri.parse::<DeviceUri>()?;
+    let opener = lock(openers()).get(uri.backend())
+        .copied()
+        .ok_or_else(|| Error::OtherError(format!("backend not registered: {}", uri.backend())))?;
+    opener(&uri)
+}
+
+/// The parameters are `latency` in milliseconds and `loss` probability.
+fn open_virtual(uri: &DeviceUri) -> Result<Box<dyn AnyDevice>, Error> {
+    let channel = VirtualChannelConfig {
+        filters: None,
+        latency: uri.get("latency")?,
+        loss: uri.get("loss")?,
+    };
+    let cfg = VirtualCanConfig {
+        channels: uri.channels().iter()
+            .map(|c| (c.clone(), channel.clone()))
+            .collect(),
+    };
+
+    Ok(Box::new(cfg.build()?))
+}
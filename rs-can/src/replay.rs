@@ -0,0 +1,174 @@
+//! Replay recorded frames through any [`Device`] with the original timing.
+//!
+//! The frames could be collected from a `Vec` or any log reader, like [`crate::asc::AscReader`],
+//! and the gaps between frames are taken from [`Frame::timestamp`].
+
+use std::{collections::HashMap, sync::{Arc, atomic::{AtomicBool, Ordering}}, thread, time::{Duration, Instant}};
+use crate::device::Device;
+use crate::error::Error;
+use crate::frame::{Direct, Frame, Id};
+
+/// The longest time to sleep once, so that [`Replay::stop`] is handled in time.
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+/// Replay engine, the frames are transmitted as `Direct::Transmit`.
+pub struct Replay<F: Frame> {
+    frames: Vec<F>,
+    speed: f64,
+    channels: HashMap<String, F::Channel>,
+    filter: Option<Box<dyn Fn(Id) -> bool + Send + Sync>>,
+    loops: Option<usize>,
+    skip_received: bool,
+    timeout: Option<u32>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl<F: Frame> Replay<F> {
+    pub fn new<I: IntoIterator<Item = F>>(frames: I) -> Self {
+        Self {
+            frames: frames.into_iter().collect(),
+            speed: 1.,
+            channels: Default::default(),
+            filter: Default::default(),
+            loops: Some(1),
+            skip_received: false,
+            timeout: Default::default(),
+            stopped: Default::default(),
+        }
+    }
+
+    /// Collect frames from a log reader, the first error is returned.
+    pub fn from_reader<I: IntoIterator<Item = Result<F, Error>>>(reader: I) -> Result<Self, Error> {
+        let frames = reader.into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(frames))
+    }
+
+    #[inline]
+    pub fn frames(&self) -> &[F] {
+        &self.frames
+    }
+
+    /// Set the speed factor, `2.0` replays twice as fast as recorded.
+    ///
+    /// The frames are transmitted without delay if the speed is not positive.
+    pub fn set_speed(&mut self, speed: f64) -> &mut Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Transmit the frames recorded on `from` channel to `to` channel.
+    pub fn set_channel<C: ToString>(&mut self, from: C, to: F::Channel) -> &mut Self {
+        self.channels.insert(from.to_string(), to);
+        self
+    }
+
+    /// Only the frames whose ID are accepted by `filter` are transmitted.
+    pub fn set_filter<P>(&mut self, filter: P) -> &mut Self
+    where
+        P: Fn(Id) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Set the times to replay, `None` replays until [`Replay::stop`] is called.
+    pub fn set_loops(&mut self, loops: Option<usize>) -> &mut Self {
+        self.loops = loops;
+        self
+    }
+
+    /// Skip the frames that recorded as [`Direct::Receive`].
+    pub fn set_skip_received(&mut self, skip: bool) -> &mut Self {
+        self.skip_received = skip;
+        self
+    }
+
+    /// Set the timeout passed to [`Device::transmit`].
+    pub fn set_timeout(&mut self, timeout: Option<u32>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Stop the running replay after the frame in transmitting.
+    ///
+    /// The next run returns immediately if it's called before running.
+    #[inline]
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Replay frames through device and return the count of transmitted frames.
+    ///
+    /// It blocks until all loops are finished, stopped or any frame transmit failed,
+    /// and the stop is cleared when it returns.
+    pub fn run<D>(&self, device: &D) -> Result<usize, Error>
+    where
+        D: Device<Channel = F::Channel, Frame = F>,
+        F: Clone,
+        F::Channel: Clone,
+    {
+        let result = self.replay(device);
+        self.stopped.store(false, Ordering::Relaxed);
+        result
+    }
+
+    fn replay<D>(&self, device: &D) -> Result<usize, Error>
+    where
+        D: Device<Channel = F::Channel, Frame = F>,
+        F: Clone,
+        F::Channel: Clone,
+    {
+        let frames = self.frames.iter()
+            .filter(|f| !(self.skip_received && f.direct() == Direct::Receive))
+            .filter(|f| match &self.filter {
+                Some(p) => p(f.id()),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        let Some(first) = frames.first().map(|f| f.timestamp()) else {
+            return Ok(0);
+        };
+
+        let mut count = 0;
+        let mut times = 0;
+        while self.loops != Some(times) {
+            let start = Instant::now();
+            for &frame in &frames {
+                if !self.wait_until(start, frame.timestamp().saturating_sub(first)) {
+                    return Ok(count);
+                }
+
+                let mut frame = frame.clone();
+                if let Some(channel) = self.channels.get(&frame.channel().to_string()) {
+                    frame.set_channel(channel.clone());
+                }
+                frame.set_direct(Direct::Transmit);
+                device.transmit(frame, self.timeout)?;
+                count += 1;
+            }
+            times += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Sleep until `offset` milliseconds after `start` with speed scaled, return false if stopped.
+    fn wait_until(&self, start: Instant, offset: u64) -> bool {
+        if self.speed > 0. {
+            let deadline = start + Duration::from_secs_f64(offset as f64 / 1000. / self.speed);
+            loop {
+                if self.stopped.load(Ordering::Relaxed) {
+                    return false;
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                thread::sleep((deadline - now).min(SLEEP_SLICE));
+            }
+        }
+
+        !self.stopped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,221 @@
+//! Background service which drives the [`Listener`]s of any [`Device`].
+//!
+//! The service receives frames from all opened channels of device by a thread, transmits the
+//! frames queued by [`Service::transmit`] in order between receiving channels, and the listeners
+//! are called back on both.
+
+use std::{collections::HashMap, sync::{Arc, Mutex, MutexGuard, atomic::{AtomicBool, Ordering}, mpsc}, thread::{self, JoinHandle}, time::Duration};
+use crate::device::{Device, Listener};
+use crate::error::Error;
+use crate::frame::Frame;
+
+/// The default timeout in milliseconds of receiving a channel once.
+pub const DEFAULT_POLL_TIMEOUT: u32 = 10;
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+type Listeners<D> = HashMap<String, Box<dyn Listener<<D as Device>::Channel, <D as Device>::Frame>>>;
+
+/// Dispatch service of a device, the listeners are called back on the thread of service.
+///
+/// The listeners must not be added or removed in their callbacks, and the service is stopped
+/// when it's dropped.
+pub struct Service<D: Device> {
+    device: D,
+    listeners: Arc<Mutex<Listeners<D>>>,
+    poll_timeout: u32,
+    timeout: Option<u32>,
+    stopped: Arc<AtomicBool>,
+    sender: Mutex<Option<mpsc::Sender<D::Frame>>>,
+    handle: Mutex<Option<JoinHandle<Result<(), Error>>>>,
+}
+
+impl<D> Service<D>
+where
+    D: Device + Send + 'static,
+    D::Channel: Clone,
+{
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            listeners: Default::default(),
+            poll_timeout: DEFAULT_POLL_TIMEOUT,
+            timeout: None,
+            stopped: Default::default(),
+            sender: Default::default(),
+            handle: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Set the timeout in milliseconds of receiving a channel once, it's taken by next start.
+    pub fn set_poll_timeout(&mut self, timeout: u32) -> &mut Self {
+        self.poll_timeout = timeout.max(1);
+        self
+    }
+
+    /// Set the timeout in milliseconds of transmitting a frame, it's taken by next start.
+    pub fn set_timeout(&mut self, timeout: Option<u32>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Add a listener, the listener of the same name is replaced and returned.
+    pub fn add_listener<S: Into<String>>(
+        &self,
+        name: S,
+        listener: Box<dyn Listener<D::Channel, D::Frame>>,
+    ) -> Option<Box<dyn Listener<D::Channel, D::Frame>>> {
+        lock(&self.listeners).insert(name.into(), listener)
+    }
+
+    pub fn remove_listener(&self, name: &str) -> Option<Box<dyn Listener<D::Channel, D::Frame>>> {
+        lock(&self.listeners).remove(name)
+    }
+
+    pub fn listener_names(&self) -> Vec<String> {
+        lock(&self.listeners).keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the thread of service is running, it exits after [`Service::stop`] or an error.
+    pub fn is_running(&self) -> bool {
+        lock(&self.handle).as_ref()
+            .is_some_and(|h| !h.is_finished())
+    }
+
+    /// Start the thread of service.
+    pub fn start(&self) -> Result<(), Error> {
+        let mut handle = lock(&self.handle);
+        if handle.as_ref().is_some_and(|h| !h.is_finished()) {
+            return Err(Error::OperationError("service is running".into()));
+        }
+
+        let (sender, requests) = mpsc::channel();
+        self.stopped.store(false, Ordering::Relaxed);
+        let worker = Worker {
+            device: self.device.clone(),
+            listeners: self.listeners.clone(),
+            poll_timeout: self.poll_timeout,
+            timeout: self.timeout,
+            stopped: self.stopped.clone(),
+        };
+        let join = thread::Builder::new()
+            .name("rs-can-service".into())
+            .spawn(move || worker.run(requests))
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        *lock(&self.sender) = Some(sender);
+        // the error of previous run is discarded.
+        *handle = Some(join);
+
+        Ok(())
+    }
+
+    /// Queue a frame to transmit, it's transmitted by the thread of service in order.
+    ///
+    /// The failure of transmitting is reported to listeners, and the service keeps running.
+    pub fn transmit(&self, frame: D::Frame) -> Result<(), Error> {
+        match lock(&self.sender).as_ref() {
+            Some(sender) => sender.send(frame)
+                .map_err(|_| Error::OperationError("service is stopped".into())),
+            None => Err(Error::OperationError("service is not started".into())),
+        }
+    }
+
+    /// Stop the service after the frames queued are transmitted, and wait for its thread.
+    ///
+    /// The error of receiving which stopped the service is returned.
+    pub fn stop(&self) -> Result<(), Error> {
+        self.stopped.store(true, Ordering::Relaxed);
+        lock(&self.sender).take();
+        match lock(&self.handle).take() {
+            Some(handle) => handle.join()
+                .map_err(|_| Error::OtherError("service thread panicked".into()))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl<D: Device> Drop for Service<D> {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        lock(&self.sender).take();
+        if let Some(handle) = lock(&self.handle).take() {
+            if let Ok(Err(e)) = handle.join() {
+                log::warn!("RUST-CAN - service stopped by error: {}", e);
+            }
+        }
+    }
+}
+
+struct Worker<D: Device> {
+    device: D,
+    listeners: Arc<Mutex<Listeners<D>>>,
+    poll_timeout: u32,
+    timeout: Option<u32>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl<D> Worker<D>
+where
+    D: Device,
+    D::Channel: Clone,
+{
+    fn run(self, requests: mpsc::Receiver<D::Frame>) -> Result<(), Error> {
+        while !self.stopped.load(Ordering::Relaxed) {
+            let channels = self.device.opened_channels();
+            if channels.is_empty() {
+                self.transmit_queued(&requests);
+                thread::sleep(Duration::from_millis(self.poll_timeout as u64));
+                continue;
+            }
+            for channel in channels {
+                // the frames queued wait for receiving a channel at most.
+                self.transmit_queued(&requests);
+                match self.device.receive(channel.clone(), Some(self.poll_timeout)) {
+                    Ok(frames) => if !frames.is_empty() {
+                        lock(&self.listeners).values()
+                            .for_each(|l| l.on_frame_received(channel.clone(), &frames));
+                    },
+                    Err(Error::TimeoutError(_)) => {},
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        // transmit the frames queued before stopped.
+        self.transmit_queued(&requests);
+
+        Ok(())
+    }
+
+    fn transmit_queued(&self, requests: &mpsc::Receiver<D::Frame>) {
+        while let Ok(frame) = requests.try_recv() {
+            self.transmit(frame);
+        }
+    }
+
+    fn transmit(&self, frame: D::Frame) {
+        let channel = frame.channel();
+        let id = frame.id();
+        lock(&self.listeners).values()
+            .for_each(|l| l.on_frame_transmitting(channel.clone(), &frame));
+        match self.device.transmit(frame, self.timeout) {
+            Ok(()) => lock(&self.listeners).values()
+                .for_each(|l| l.on_frame_transmitted(channel.clone(), id)),
+            Err(e) => {
+                log::warn!("RUST-CAN - service transmit 0x{:X} to {} failed: {}", id.into_bits(), channel, e);
+                lock(&self.listeners).values()
+                    .for_each(|l| l.on_frame_transmit_failed(channel.clone(), id, &e));
+            },
+        }
+    }
+}
@@ -0,0 +1,462 @@
+//! PEAK PCAN-View TRC trace file of version 1.1, 2.0 and 2.1.
+//!
+//! [`TrcWriter`] writes and [`TrcReader`] reads CAN, CAN-FD, remote and error frames.
+//! Only version 2.1 records the bus of frames, the frames of other versions are on bus 1.
+//!
+//! The timestamp of frame is milliseconds since UNIX epoch,
+//! and `$STARTTIME` of file is treated as UTC.
+
+use std::{collections::HashMap, fmt::Display, fs::File, io::{BufRead, BufReader, BufWriter, Write}, marker::PhantomData, path::Path, str::FromStr};
+use crate::error::Error;
+use crate::frame::{Direct, Frame, Id, Type};
+use crate::utils;
+
+/// Days between 1899-12-30 and 1970-01-01.
+const OLE_UNIX_EPOCH_DAYS: f64 = 25_569.;
+const MS_PER_DAY: f64 = 86_400_000.;
+
+/// The version of TRC file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V1_1,
+    V2_0,
+    #[default]
+    V2_1,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1_1 => f.write_str("1.1"),
+            Self::V2_0 => f.write_str("2.0"),
+            Self::V2_1 => f.write_str("2.1"),
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "1.1" => Ok(Self::V1_1),
+            "2.0" => Ok(Self::V2_0),
+            "2.1" => Ok(Self::V2_1),
+            v => Err(Error::OtherError(format!("trc version: {} is not supported", v))),
+        }
+    }
+}
+
+/// The message type of version 2.x.
+fn message_type<T: Display>(frame: &dyn Frame<Channel = T>) -> &'static str {
+    if frame.is_error_frame() {
+        return "ER";
+    }
+    match frame.can_type() {
+        Type::Can => if frame.is_remote() { "RR" } else { "DT" },
+        _ => match (frame.is_bitrate_switch(), frame.is_esi()) {
+            (false, false) => "FD",
+            (true, false) => "FB",
+            (false, true) => "FE",
+            (true, true) => "BI",
+        },
+    }
+}
+
+/// TRC file writer, the header is written when the first frame is written.
+pub struct TrcWriter<W: Write> {
+    writer: W,
+    version: Version,
+    start: Option<u64>,
+    count: usize,
+    channels: HashMap<String, u8>,
+    started: bool,
+}
+
+impl TrcWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::create(path)
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> TrcWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            version: Default::default(),
+            start: Default::default(),
+            count: Default::default(),
+            channels: Default::default(),
+            started: false,
+        }
+    }
+
+    /// Set the file version, it's ignored after first frame written.
+    pub fn set_version(&mut self, version: Version) -> &mut Self {
+        self.version = version;
+        self
+    }
+
+    /// Set start time of measurement, the timestamp of first frame is used by default.
+    pub fn set_start(&mut self, timestamp: u64) -> &mut Self {
+        self.start = Some(timestamp);
+        self
+    }
+
+    /// Map a frame channel to TRC bus(1~16) of version 2.1.
+    ///
+    /// The channels that are not mapped are numbered from 1 in order of appearance.
+    pub fn set_channel<C: Display>(&mut self, channel: C, bus: u8) -> &mut Self {
+        self.channels.insert(channel.to_string(), bus);
+        self
+    }
+
+    /// Write a frame, the CAN XL frames and the CAN-FD frames of version 1.1 are not supported.
+    pub fn write<T: Display>(&mut self, frame: &dyn Frame<Channel = T>) -> Result<(), Error> {
+        if frame.can_type() == Type::CanXl || (self.version == Version::V1_1 && frame.can_type() != Type::Can) {
+            return Err(Error::NotSupportedError);
+        }
+
+        let timestamp = frame.timestamp();
+        let start = *self.start.get_or_insert(timestamp);
+        if !self.started {
+            self.write_header(start)?;
+        }
+        self.count += 1;
+
+        let offset = timestamp.saturating_sub(start) as f64;
+        let id = if frame.is_extended() {
+            format!("{:08X}", frame.id().into_bits())
+        }
+        else {
+            format!("{:04X}", frame.id().into_bits())
+        };
+        let data = frame.data()
+            .iter()
+            .take(frame.length())
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let line = match self.version {
+            Version::V1_1 => {
+                let r#type = if frame.is_error_frame() { "Error".to_owned() } else { frame.direct().to_string() };
+                let data = if frame.is_remote() { "RTR".to_owned() } else { data };
+                format!("{:>6}) {:>11.1}  {:<5}  {:>8}  {}  {}", self.count, offset, r#type, id, frame.length(), data)
+            },
+            Version::V2_0 => {
+                let id = if frame.is_error_frame() { "-".to_owned() } else { id };
+                format!("{:>7} {:>13.3} {} {:>8} {} {:<4} {}",
+                        self.count, offset, message_type(frame), id, frame.direct(), frame.length(), data)
+            },
+            Version::V2_1 => {
+                let channel = frame.channel().to_string();
+                let next = self.channels.values().max().map_or(1, |v| v.saturating_add(1));
+                let bus = *self.channels.entry(channel).or_insert(next);
+                let id = if frame.is_error_frame() { "-".to_owned() } else { id };
+                format!("{:>7} {:>13.3} {} {:>2} {:>8} {} -  {:<4} {}",
+                        self.count, offset, message_type(frame), bus, id, frame.direct(), utils::dlc_code(frame.length()), data)
+            },
+        };
+
+        writeln!(self.writer, "{}", line.trim_end())
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+
+    pub fn finish(&mut self) -> Result<(), Error> {
+        if !self.started {
+            self.write_header(self.start.unwrap_or_else(utils::system_timestamp))?;
+        }
+        self.writer.flush()
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn write_header(&mut self, start: u64) -> Result<(), Error> {
+        self.started = true;
+        let days = start as f64 / MS_PER_DAY + OLE_UNIX_EPOCH_DAYS;
+        let (year, month, day) = utils::civil_from_days((start / 86_400_000) as i64);
+        let ms_of_day = start % 86_400_000;
+
+        let mut header = format!(";$FILEVERSION={}\n;$STARTTIME={:.10}\n", self.version, days);
+        if self.version == Version::V2_1 {
+            header.push_str(";$COLUMNS=N,O,T,B,I,d,R,L,D\n");
+        }
+        header.push_str(&format!(";\n;   Start time: {}/{}/{} {:02}:{:02}:{:02}.{:03}.0\n;   Generated by rs-can\n",
+                                 month, day, year,
+                                 ms_of_day / 3_600_000, ms_of_day / 60_000 % 60, ms_of_day / 1000 % 60, ms_of_day % 1000));
+        header.push_str(match self.version {
+            Version::V1_1 => ";   Message Number\n;   |         Time Offset (ms)\n;   |         |        Type\n;   |         |        |        ID (hex)\n;   |         |        |        |     Data Length\n;   |         |        |        |     |   Data Bytes (hex) ...\n;   |         |        |        |     |   |\n;---+--   ----+----  --+--  ----+---  +  -+ -- -- -- -- -- -- --\n",
+            Version::V2_0 => ";   Message Number\n;   |         Time Offset (ms)\n;   |         |       Type\n;   |         |       |        ID (hex)\n;   |         |       |        |     Rx/Tx\n;   |         |       |        |     |    Data Length\n;   |         |       |        |     |    |   Data Bytes (hex) ...\n;   |         |       |        |     |    |   |\n;---+-- ------+------ +- --+----- +- +- +- -- -- -- -- -- -- --\n",
+            Version::V2_1 => ";   Message Number\n;   |         Time Offset (ms)\n;   |         |       Type\n;   |         |       |  Bus (1-16)\n;   |         |       |  |  ID (hex)\n;   |         |       |  |  |    Rx/Tx\n;   |         |       |  |  |    |  Reserved\n;   |         |       |  |  |    |  |  Data Length Code\n;   |         |       |  |  |    |  |  |    Data Bytes (hex) ...\n;   |         |       |  |  |    |  |  |    |\n;---+-- ------+------ +- +- --+----- +- +- +--- +- -- -- -- -- -- -- --\n",
+        });
+
+        self.writer.write_all(header.as_bytes())
+            .map_err(|e| Error::OperationError(e.to_string()))
+    }
+}
+
+impl<W: Write> Drop for TrcWriter<W> {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::warn!("RUST-CAN - {} when trc writer dropped", e);
+        }
+    }
+}
+
+/// TRC file reader, iterating frames that parsed from file.
+///
+/// The TRC bus is converted to frame channel by `FromStr`
+/// unless it's mapped by [`TrcReader::set_channel`].
+/// The lines other than CAN, CAN-FD and error frames are skipped.
+pub struct TrcReader<R: BufRead, F: Frame> {
+    reader: R,
+    version: Version,
+    start: u64,
+    columns: HashMap<char, usize>,
+    line_no: usize,
+    channels: HashMap<u8, F::Channel>,
+    _frame: PhantomData<F>,
+}
+
+impl<F: Frame> TrcReader<BufReader<File>, F> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)
+            .map_err(|e| Error::OperationError(e.to_string()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead, F: Frame> TrcReader<R, F> {
+    pub fn new(reader: R) -> Self {
+        let mut result = Self {
+            reader,
+            version: Version::V1_1,
+            start: Default::default(),
+            columns: Default::default(),
+            line_no: Default::default(),
+            channels: Default::default(),
+            _frame: Default::default(),
+        };
+        result.set_columns("N,O,T,I,d,l,D");
+        result
+    }
+
+    /// Map a TRC bus to frame channel.
+    pub fn set_channel(&mut self, bus: u8, channel: F::Channel) -> &mut Self {
+        self.channels.insert(bus, channel);
+        self
+    }
+
+    /// The file version parsed from header.
+    #[inline]
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The start time of measurement parsed from header.
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    fn set_columns(&mut self, columns: &str) {
+        self.columns = columns.split(',')
+            .enumerate()
+            .filter_map(|(i, c)| Some((c.trim().chars().next()?, i)))
+            .collect();
+    }
+
+    fn parse_header(&mut self, line: &str) -> Result<(), Error> {
+        let Some((key, value)) = line.trim_start_matches(';').split_once('=') else {
+            return Ok(());
+        };
+        match key.trim() {
+            "$FILEVERSION" => self.version = value.parse()?,
+            "$STARTTIME" => if let Ok(days) = value.trim().parse::<f64>() {
+                self.start = ((days - OLE_UNIX_EPOCH_DAYS) * MS_PER_DAY).round().max(0.) as u64;
+            },
+            "$COLUMNS" => self.set_columns(value),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn parse_v1(&self, line: &str) -> Option<Result<F, Error>>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let error = || Error::OtherError(format!("invalid trc frame at line {}: `{}`", self.line_no, line));
+        let mut tokens = line.split_whitespace();
+        tokens.next()?.strip_suffix(')')?;
+        let offset = tokens.next()?.parse::<f64>().ok()?;
+        let (direct, error_frame) = match tokens.next()? {
+            "Rx" => (Direct::Receive, false),
+            "Tx" => (Direct::Transmit, false),
+            "Error" => (Direct::Receive, true),
+            _ => return None,
+        };
+        let id = tokens.next()?;
+        let id = match u32::from_str_radix(id, 16) {
+            Ok(v) => Id::from_bits(v, Some(id.len() > 4)),
+            Err(_) => return Some(Err(error())),
+        };
+        let Some(length) = tokens.next().and_then(|v| v.parse::<usize>().ok()) else {
+            return Some(Err(error()));
+        };
+        let data = tokens.collect::<Vec<_>>();
+
+        let frame = if data.first() == Some(&"RTR") {
+            F::new_remote(id, length)
+        }
+        else {
+            data.iter()
+                .take(length)
+                .map(|v| u8::from_str_radix(v, 16).ok())
+                .collect::<Option<Vec<_>>>()
+                .filter(|v| v.len() == length)
+                .and_then(|v| F::new(id, &v))
+        };
+        let Some(mut frame) = frame else {
+            return Some(Err(error()));
+        };
+        frame.set_direct(direct)
+            .set_error_frame(error_frame);
+
+        Some(self.finish_frame(frame, 1, offset))
+    }
+
+    fn parse_v2(&self, line: &str) -> Option<Result<F, Error>>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let error = || Error::OtherError(format!("invalid trc frame at line {}: `{}`", self.line_no, line));
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let column = |c: char| self.columns.get(&c).and_then(|&i| tokens.get(i)).copied();
+
+        let offset = column('O')?.parse::<f64>().ok()?;
+        let r#type = column('T')?;
+        let (can_type, remote, error_frame, brs, esi) = match r#type {
+            "DT" => (Type::Can, false, false, false, false),
+            "RR" => (Type::Can, true, false, false, false),
+            "FD" => (Type::CanFd, false, false, false, false),
+            "FB" => (Type::CanFd, false, false, true, false),
+            "FE" => (Type::CanFd, false, false, false, true),
+            "BI" => (Type::CanFd, false, false, true, true),
+            "ER" => (Type::Can, false, true, false, false),
+            _ => return None,
+        };
+        let bus = match column('B') {
+            Some(v) => match v.parse::<u8>() {
+                Ok(v) => v,
+                Err(_) => return Some(Err(error())),
+            },
+            None => 1,
+        };
+        let id = match column('I') {
+            Some("-") | None => Id::Standard(0),
+            Some(v) => match u32::from_str_radix(v, 16) {
+                Ok(id) => Id::from_bits(id, Some(v.len() > 4)),
+                Err(_) => return Some(Err(error())),
+            },
+        };
+        let direct = match column('d') {
+            Some("Tx") => Direct::Transmit,
+            _ => Direct::Receive,
+        };
+        let length = match (column('l'), column('L')) {
+            (Some(v), _) => v.parse::<usize>().ok(),
+            (None, Some(v)) => v.parse::<u8>().ok().map(utils::dlc_length),
+            (None, None) => None,
+        };
+        let Some(length) = length else {
+            return Some(Err(error()));
+        };
+
+        let frame = if remote {
+            F::new_remote(id, length)
+        }
+        else {
+            self.columns.get(&'D')
+                .and_then(|&i| tokens.get(i..))
+                .unwrap_or_default()
+                .iter()
+                .take(length)
+                .map(|v| u8::from_str_radix(v, 16).ok())
+                .collect::<Option<Vec<_>>>()
+                .filter(|v| v.len() == length)
+                .and_then(|v| F::new(id, &v))
+        };
+        let Some(mut frame) = frame else {
+            return Some(Err(error()));
+        };
+        if can_type == Type::CanFd {
+            frame.set_can_type(can_type);
+        }
+        frame.set_direct(direct)
+            .set_error_frame(error_frame)
+            .set_bitrate_switch(brs)
+            .set_esi(esi);
+
+        Some(self.finish_frame(frame, bus, offset))
+    }
+
+    fn finish_frame(&self, mut frame: F, bus: u8, offset: f64) -> Result<F, Error>
+    where
+        F::Channel: FromStr + Clone,
+    {
+        let channel = match self.channels.get(&bus) {
+            Some(v) => v.clone(),
+            None => bus.to_string().parse()
+                .map_err(|_| Error::OtherError(format!("trc bus: {} can't be converted", bus)))?,
+        };
+        frame.set_timestamp(Some(self.start + offset.round().max(0.) as u64))
+            .set_channel(channel);
+
+        Ok(frame)
+    }
+}
+
+impl<R: BufRead, F: Frame> Iterator for TrcReader<R, F>
+where
+    F::Channel: FromStr + Clone,
+{
+    type Item = Result<F, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_no += 1,
+                Err(e) => return Some(Err(Error::OperationError(e.to_string()))),
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with(';') {
+                if let Err(e) = self.parse_header(line) {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+
+            let result = match self.version {
+                Version::V1_1 => self.parse_v1(line),
+                _ => self.parse_v2(line),
+            };
+            if result.is_some() {
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! UDS(ISO 14229) diagnostic client.
+//!
+//! [`UdsClient`] sends requests through any [`Transport`], like [`IsoTp`] over a [`Device`],
+//! waits responses with P2/P2* timing and handles the response pending(NRC 0x78).
+//!
+//! [`Device`]: crate::CanDevice
+
+mod types;
+
+pub use types::*;
+
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+use crate::device::Device;
+use crate::error::Error;
+use crate::isotp::IsoTp;
+
+/// The default P2 timeout in milliseconds.
+pub const DEFAULT_P2: u32 = 150;
+/// The default P2* timeout in milliseconds.
+pub const DEFAULT_P2_STAR: u32 = 5000;
+
+/// The transport layer of UDS.
+pub trait Transport {
+    /// Send request by physical addressing.
+    fn send(&mut self, data: &[u8]) -> Result<(), Error>;
+    /// Send request by functional addressing, physical addressing is used by default.
+    #[inline]
+    fn send_functional(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.send(data)
+    }
+    /// Receive response, `timeout` is in milliseconds(forever if `None`).
+    fn receive(&mut self, timeout: Option<u32>) -> Result<Vec<u8>, Error>;
+}
+
+impl<D: Device> Transport for IsoTp<D>
+where
+    D::Channel: Clone,
+{
+    #[inline]
+    fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        IsoTp::send(self, data)
+    }
+
+    /// It's an error if the functional address is not set.
+    #[inline]
+    fn send_functional(&mut self, data: &[u8]) -> Result<(), Error> {
+        IsoTp::send_functional(self, data)
+    }
+
+    #[inline]
+    fn receive(&mut self, timeout: Option<u32>) -> Result<Vec<u8>, Error> {
+        IsoTp::receive(self, timeout)
+    }
+}
+
+type SecurityAlgorithm = Box<dyn Fn(u8, &[u8]) -> Result<Vec<u8>, Error> + Send>;
+
+pub struct UdsClient<T: Transport> {
+    transport: T,
+    timing: SessionTiming,
+    security_algorithm: Option<SecurityAlgorithm>,
+    nrc: Option<u8>,
+}
+
+impl<T: Transport> UdsClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            timing: SessionTiming { p2: DEFAULT_P2, p2_star: DEFAULT_P2_STAR },
+            security_algorithm: Default::default(),
+            nrc: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    #[inline]
+    pub fn timing(&self) -> SessionTiming {
+        self.timing
+    }
+
+    /// Set P2 and P2* timing, it's updated by the response of DiagnosticSessionControl.
+    pub fn set_timing(&mut self, timing: SessionTiming) -> &mut Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Set the algorithm that calculates key from security level and seed.
+    pub fn set_security_algorithm<F>(&mut self, algorithm: F) -> &mut Self
+    where
+        F: Fn(u8, &[u8]) -> Result<Vec<u8>, Error> + Send + 'static,
+    {
+        self.security_algorithm = Some(Box::new(algorithm));
+        self
+    }
+
+    /// The negative response code of last request, it's `None` if the response is positive.
+    #[inline]
+    pub fn nrc(&self) -> Option<u8> {
+        self.nrc
+    }
+
+    /// Send a raw request and return the positive response.
+    ///
+    /// The response pending(NRC 0x78) is waited with P2* timing,
+    /// and an error is returned when negative response received.
+    pub fn request(&mut self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        let service = *request.first()
+            .ok_or_else(|| Error::OperationError("uds: empty request".into()))?;
+        self.nrc = None;
+        self.transport.send(request)?;
+
+        let mut timeout = self.timing.p2;
+        loop {
+            let deadline = Instant::now() + Duration::from_millis(timeout as u64);
+            let response = loop {
+                let remain = deadline.saturating_duration_since(Instant::now()).as_millis() as u32;
+                let response = match self.transport.receive(Some(remain)) {
+                    Ok(v) => v,
+                    Err(Error::TimeoutError(_)) => {
+                        return Err(Error::TimeoutError(format!("uds: response of service 0x{:02X}", service)));
+                    },
+                    Err(e) => return Err(e),
+                };
+                match response.first() {
+                    Some(&NEGATIVE_RESPONSE) if response.get(1) == Some(&service) => break response,
+                    Some(&v) if v == service.wrapping_add(POSITIVE_OFFSET) => break response,
+                    _ => log::warn!("RUST-CAN - uds unexpected response: {:02X?}", response),
+                }
+            };
+
+            if response[0] != NEGATIVE_RESPONSE {
+                return Ok(response);
+            }
+            let nrc = response.get(2).copied().unwrap_or_default();
+            if nrc == NRC_RESPONSE_PENDING {
+                timeout = self.timing.p2_star;
+                continue;
+            }
+
+            self.nrc = Some(nrc);
+            return Err(Error::OperationError(
+                format!("uds: negative response 0x{:02X}({}) of service 0x{:02X}", nrc, nrc_description(nrc), service)
+            ));
+        }
+    }
+
+    /// Send request and check the echo of positive response.
+    fn request_echo(&mut self, request: &[u8], echo: usize) -> Result<Vec<u8>, Error> {
+        let response = self.request(request)?;
+        if response.get(1..=echo) != request.get(1..=echo) {
+            return Err(Error::OperationError(format!("uds: invalid response {:02X?}", response)));
+        }
+        Ok(response)
+    }
+
+    /// DiagnosticSessionControl, the timing of client is updated by response.
+    pub fn session_control(&mut self, session: SessionType) -> Result<SessionTiming, Error> {
+        let response = self.request_echo(&[DIAGNOSTIC_SESSION_CONTROL, session.into()], 1)?;
+        if let Some(v) = response.get(2..6) {
+            self.timing = SessionTiming {
+                p2: u16::from_be_bytes([v[0], v[1]]) as u32,
+                p2_star: u16::from_be_bytes([v[2], v[3]]) as u32 * 10,
+            };
+        }
+        Ok(self.timing)
+    }
+
+    /// ECUReset, return the power down time in seconds if responded.
+    pub fn ecu_reset(&mut self, reset: ResetType) -> Result<Option<u8>, Error> {
+        let response = self.request_echo(&[ECU_RESET, reset.into()], 1)?;
+        Ok(response.get(2).copied())
+    }
+
+    /// SecurityAccess of level(odd number), the key is calculated by the security algorithm.
+    pub fn security_access(&mut self, level: u8) -> Result<(), Error> {
+        if level & 1 == 0 {
+            return Err(Error::OperationError(format!("uds: security level 0x{:02X} is not request seed", level)));
+        }
+        let response = self.request_echo(&[SECURITY_ACCESS, level], 1)?;
+        let seed = &response[2..];
+        // the server is unlocked already.
+        if seed.iter().all(|v| *v == 0) {
+            return Ok(());
+        }
+
+        let algorithm = self.security_algorithm.as_ref()
+            .ok_or_else(|| Error::OperationError("uds: security algorithm is not set".into()))?;
+        let mut request = vec![SECURITY_ACCESS, level + 1];
+        request.extend(algorithm(level, seed)?);
+        self.request_echo(&request, 1)?;
+
+        Ok(())
+    }
+
+    /// ReadDataByIdentifier and return the data record.
+    pub fn read_data_by_identifier(&mut self, did: u16) -> Result<Vec<u8>, Error> {
+        let [hi, lo] = did.to_be_bytes();
+        let response = self.request_echo(&[READ_DATA_BY_IDENTIFIER, hi, lo], 2)?;
+        Ok(response[3..].to_vec())
+    }
+
+    pub fn write_data_by_identifier(&mut self, did: u16, data: &[u8]) -> Result<(), Error> {
+        let [hi, lo] = did.to_be_bytes();
+        let mut request = vec![WRITE_DATA_BY_IDENTIFIER, hi, lo];
+        request.extend_from_slice(data);
+        self.request_echo(&request, 2)?;
+        Ok(())
+    }
+
+    /// RoutineControl and return the routine status record.
+    pub fn routine_control(&mut self, r#type: RoutineControlType, routine: u16, options: &[u8]) -> Result<Vec<u8>, Error> {
+        let [hi, lo] = routine.to_be_bytes();
+        let mut request = vec![ROUTINE_CONTROL, r#type as u8, hi, lo];
+        request.extend_from_slice(options);
+        let response = self.request_echo(&request, 3)?;
+        Ok(response[4..].to_vec())
+    }
+
+    /// RequestDownload and return the max number of block length(include the service and sequence).
+    pub fn request_download(&mut self, data_format: u8, location: MemoryLocation) -> Result<usize, Error> {
+        let mut request = vec![REQUEST_DOWNLOAD, data_format];
+        request.extend(location.encode());
+        let response = self.request(&request)?;
+
+        let length = (response.get(1).copied().unwrap_or_default() >> 4) as usize;
+        match response.get(2..2 + length) {
+            Some(v) if (1..=8).contains(&length) => Ok(v.iter().fold(0, |r, b| r << 8 | *b as usize)),
+            _ => Err(Error::OperationError(format!("uds: invalid response {:02X?}", response))),
+        }
+    }
+
+    /// TransferData and return the transfer response parameter.
+    pub fn transfer_data(&mut self, sequence: u8, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut request = vec![TRANSFER_DATA, sequence];
+        request.extend_from_slice(data);
+        let response = self.request_echo(&request, 1)?;
+        Ok(response[2..].to_vec())
+    }
+
+    pub fn request_transfer_exit(&mut self, parameter: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut request = vec![REQUEST_TRANSFER_EXIT];
+        request.extend_from_slice(parameter);
+        let response = self.request(&request)?;
+        Ok(response[1..].to_vec())
+    }
+
+    /// Download data by RequestDownload, TransferData and RequestTransferExit.
+    pub fn download(&mut self, data_format: u8, address: u64, data: &[u8]) -> Result<(), Error> {
+        let block_length = self.request_download(data_format, MemoryLocation::new(address, data.len() as u64))?;
+        if block_length <= 2 {
+            return Err(Error::OperationError(format!("uds: invalid max block length {}", block_length)));
+        }
+
+        data.chunks(block_length - 2)
+            // the sequence starts from 1 and wraps around to 0.
+            .zip((1..=u8::MAX).chain((0..=u8::MAX).cycle()))
+            .try_for_each(|(chunk, sequence)| self.transfer_data(sequence, chunk).map(|_| ()))?;
+        self.request_transfer_exit(&[])?;
+
+        Ok(())
+    }
+
+    /// TesterPresent, the response is not waited if suppressed.
+    pub fn tester_present(&mut self, suppress: bool) -> Result<(), Error> {
+        if suppress {
+            return self.transport.send(&[TESTER_PRESENT, SUPPRESS_POSITIVE]);
+        }
+        self.request_echo(&[TESTER_PRESENT, 0x00], 1)?;
+        Ok(())
+    }
+
+    /// ReadDTCInformation with raw sub-function and return the response after sub-function.
+    pub fn read_dtc_information(&mut self, sub_function: u8, parameter: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut request = vec![READ_DTC_INFORMATION, sub_function];
+        request.extend_from_slice(parameter);
+        let response = self.request_echo(&request, 1)?;
+        Ok(response[2..].to_vec())
+    }
+
+    /// reportNumberOfDTCByStatusMask and return the count of DTCs.
+    pub fn dtc_count_by_status_mask(&mut self, mask: u8) -> Result<u16, Error> {
+        let response = self.read_dtc_information(REPORT_NUMBER_OF_DTC_BY_STATUS_MASK, &[mask])?;
+        match response.get(2..4) {
+            Some(v) => Ok(u16::from_be_bytes([v[0], v[1]])),
+            None => Err(Error::OperationError(format!("uds: invalid DTC count {:02X?}", response))),
+        }
+    }
+
+    /// reportDTCByStatusMask and return the DTCs.
+    pub fn dtc_by_status_mask(&mut self, mask: u8) -> Result<Vec<Dtc>, Error> {
+        let response = self.read_dtc_information(REPORT_DTC_BY_STATUS_MASK, &[mask])?;
+        // skip the DTCStatusAvailabilityMask.
+        Ok(response.get(1..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .map(|v| Dtc { code: u32::from_be_bytes([0, v[0], v[1], v[2]]), status: v[3] })
+            .collect())
+    }
+}
+
+/// Keep the non-default session alive by sending suppressed TesterPresent by functional addressing.
+///
+/// The transport should be independent of client, like an [`IsoTp`] of a cloned device.
+/// The sending is stopped when dropped.
+pub struct KeepAlive {
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl KeepAlive {
+    pub fn start<T: Transport + Send + 'static>(mut transport: T, interval: Duration) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let stopped = stopped.clone();
+            move || {
+                let mut last: Option<Instant> = None;
+                while !stopped.load(Ordering::Relaxed) {
+                    let expired = match last {
+                        Some(v) => v.elapsed() >= interval,
+                        None => true,
+                    };
+                    if expired {
+                        if let Err(e) = transport.send_functional(&[TESTER_PRESENT, SUPPRESS_POSITIVE]) {
+                            log::warn!("RUST-CAN - {} when tester present sent", e);
+                        }
+                        last = Some(Instant::now());
+                    }
+                    thread::sleep(interval.min(Duration::from_millis(10)));
+                }
+            }
+        });
+
+        Self { stopped, handle: Some(handle) }
+    }
+
+    pub fn stop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
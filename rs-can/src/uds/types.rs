@@ -0,0 +1,154 @@
+/// Service identifiers.
+pub const DIAGNOSTIC_SESSION_CONTROL: u8 = 0x10;
+pub const ECU_RESET: u8 = 0x11;
+pub const READ_DTC_INFORMATION: u8 = 0x19;
+pub const READ_DATA_BY_IDENTIFIER: u8 = 0x22;
+pub const SECURITY_ACCESS: u8 = 0x27;
+pub const WRITE_DATA_BY_IDENTIFIER: u8 = 0x2E;
+pub const ROUTINE_CONTROL: u8 = 0x31;
+pub const REQUEST_DOWNLOAD: u8 = 0x34;
+pub const TRANSFER_DATA: u8 = 0x36;
+pub const REQUEST_TRANSFER_EXIT: u8 = 0x37;
+pub const TESTER_PRESENT: u8 = 0x3E;
+/// Service identifier of negative response.
+pub const NEGATIVE_RESPONSE: u8 = 0x7F;
+/// The offset between request and positive response service identifier.
+pub const POSITIVE_OFFSET: u8 = 0x40;
+/// The bit of sub-function to suppress positive response.
+pub const SUPPRESS_POSITIVE: u8 = 0x80;
+
+/// Negative response code of request correctly received, but response is pending.
+pub const NRC_RESPONSE_PENDING: u8 = 0x78;
+
+/// The sub-functions of ReadDTCInformation.
+pub const REPORT_NUMBER_OF_DTC_BY_STATUS_MASK: u8 = 0x01;
+pub const REPORT_DTC_BY_STATUS_MASK: u8 = 0x02;
+
+/// The description of negative response code.
+pub fn nrc_description(code: u8) -> &'static str {
+    match code {
+        0x10 => "generalReject",
+        0x11 => "serviceNotSupported",
+        0x12 => "subFunctionNotSupported",
+        0x13 => "incorrectMessageLengthOrInvalidFormat",
+        0x14 => "responseTooLong",
+        0x21 => "busyRepeatRequest",
+        0x22 => "conditionsNotCorrect",
+        0x24 => "requestSequenceError",
+        0x25 => "noResponseFromSubnetComponent",
+        0x26 => "failurePreventsExecutionOfRequestedAction",
+        0x31 => "requestOutOfRange",
+        0x33 => "securityAccessDenied",
+        0x35 => "invalidKey",
+        0x36 => "exceedNumberOfAttempts",
+        0x37 => "requiredTimeDelayNotExpired",
+        0x70 => "uploadDownloadNotAccepted",
+        0x71 => "transferDataSuspended",
+        0x72 => "generalProgrammingFailure",
+        0x73 => "wrongBlockSequenceCounter",
+        0x78 => "requestCorrectlyReceived-ResponsePending",
+        0x7E => "subFunctionNotSupportedInActiveSession",
+        0x7F => "serviceNotSupportedInActiveSession",
+        _ => "unknown",
+    }
+}
+
+/// The sub-function of DiagnosticSessionControl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    Default,
+    Programming,
+    Extended,
+    SafetySystem,
+    Other(u8),
+}
+
+impl From<SessionType> for u8 {
+    fn from(value: SessionType) -> Self {
+        match value {
+            SessionType::Default => 0x01,
+            SessionType::Programming => 0x02,
+            SessionType::Extended => 0x03,
+            SessionType::SafetySystem => 0x04,
+            SessionType::Other(v) => v,
+        }
+    }
+}
+
+/// The sub-function of ECUReset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetType {
+    Hard,
+    KeyOffOn,
+    Soft,
+    EnableRapidPowerShutDown,
+    DisableRapidPowerShutDown,
+    Other(u8),
+}
+
+impl From<ResetType> for u8 {
+    fn from(value: ResetType) -> Self {
+        match value {
+            ResetType::Hard => 0x01,
+            ResetType::KeyOffOn => 0x02,
+            ResetType::Soft => 0x03,
+            ResetType::EnableRapidPowerShutDown => 0x04,
+            ResetType::DisableRapidPowerShutDown => 0x05,
+            ResetType::Other(v) => v,
+        }
+    }
+}
+
+/// The sub-function of RoutineControl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoutineControlType {
+    Start = 0x01,
+    Stop = 0x02,
+    RequestResults = 0x03,
+}
+
+/// The P2 and P2* timing of server in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTiming {
+    pub p2: u32,
+    pub p2_star: u32,
+}
+
+/// The memory address and size of RequestDownload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLocation {
+    pub(crate) address: u64,
+    pub(crate) size: u64,
+    pub(crate) address_length: u8,
+    pub(crate) size_length: u8,
+}
+
+impl MemoryLocation {
+    /// Create location with 4 bytes address and 4 bytes size.
+    pub fn new(address: u64, size: u64) -> Self {
+        Self { address, size, address_length: 4, size_length: 4 }
+    }
+
+    /// Set bytes length(1~8) of address and size.
+    pub fn set_lengths(&mut self, address_length: u8, size_length: u8) -> &mut Self {
+        self.address_length = address_length.clamp(1, 8);
+        self.size_length = size_length.clamp(1, 8);
+        self
+    }
+
+    /// The addressAndLengthFormatIdentifier, address and size bytes.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut result = vec![(self.size_length << 4) | self.address_length];
+        result.extend_from_slice(&self.address.to_be_bytes()[8 - self.address_length as usize..]);
+        result.extend_from_slice(&self.size.to_be_bytes()[8 - self.size_length as usize..]);
+        result
+    }
+}
+
+/// The diagnostic trouble code with status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dtc {
+    /// The 3 bytes DTC.
+    pub code: u32,
+    pub status: u8,
+}
@@ -0,0 +1,384 @@
+//! In-process virtual CAN bus.
+//!
+//! Every opened channel joins the bus of the same name, and a frame transmitted on
+//! a channel is delivered to all the other handles which joined that bus.
+//! Buses could be configured with latency, frame loss and bridged to each other by [`VirtualBus`].
+
+use std::{collections::{HashMap, VecDeque}, sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, Weak}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use serde::{Deserialize, Serialize};
+use crate::device::{ChannelConfig, Device, DeviceBuilder, DeviceConfig, CanResult};
+use crate::error::Error;
+use crate::frame::{Direct, Filter, Frame, Message};
+use crate::utils;
+
+/// Key of `Vec<CanFilter>` in `ChannelConfig`.
+pub const FILTERS: &str = "filters";
+/// Key of bus latency(`u64` in milliseconds) in `ChannelConfig`.
+pub const LATENCY: &str = "latency";
+/// Key of bus frame loss probability(`f64` in range `0.0..=1.0`) in `ChannelConfig`.
+pub const LOSS: &str = "loss";
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug)]
+struct Node {
+    queue: Mutex<VecDeque<(Instant, Message)>>,
+    cond: Condvar,
+    filters: Mutex<Vec<Filter>>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            queue: Default::default(),
+            cond: Default::default(),
+            filters: Default::default(),
+        }
+    }
+
+    /// A frame is accepted if no filter is set or it matches any of the filters.
+    fn accept(&self, msg: &Message) -> bool {
+        let filters = lock(&self.filters);
+        if filters.is_empty() {
+            return true;
+        }
+
+        let id = msg.id().into_bits();
+        filters.iter()
+            .any(|f| (!f.extended || msg.is_extended()) && (id & f.can_mask) == (f.can_id & f.can_mask))
+    }
+
+    fn push(&self, ready: Instant, msg: Message) {
+        lock(&self.queue).push_back((ready, msg));
+        self.cond.notify_all();
+    }
+}
+
+#[derive(Debug)]
+struct Bus {
+    nodes: Vec<Weak<Node>>,
+    bridges: Vec<String>,
+    latency: Duration,
+    loss: f64,
+    seed: u64,
+}
+
+impl Bus {
+    fn new() -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|v| v.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            nodes: Default::default(),
+            bridges: Default::default(),
+            latency: Default::default(),
+            loss: Default::default(),
+            seed: seed | 1,
+        }
+    }
+
+    /// xorshift64, it's enough for simulating frame loss.
+    fn lost(&mut self) -> bool {
+        if self.loss <= 0. {
+            return false;
+        }
+
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        ((self.seed >> 11) as f64 / (1u64 << 53) as f64) < self.loss
+    }
+
+    fn deliver(&mut self, msg: &Message, channel: &str, sender: Option<&Arc<Node>>) {
+        let ready = Instant::now() + self.latency;
+        let timestamp = utils::system_timestamp() + self.latency.as_millis() as u64;
+        self.nodes.retain(|n| n.strong_count() > 0);
+        self.nodes.iter()
+            .filter_map(|n| n.upgrade())
+            .filter(|n| !sender.is_some_and(|s| Arc::ptr_eq(s, n)))
+            .filter(|n| n.accept(msg))
+            .for_each(|n| {
+                let mut msg = msg.clone();
+                msg.set_channel(channel.to_owned())
+                    .set_direct(Direct::Receive)
+                    .set_timestamp(Some(timestamp));
+                n.push(ready, msg);
+            });
+    }
+}
+
+fn buses() -> &'static Mutex<HashMap<String, Bus>> {
+    static BUSES: OnceLock<Mutex<HashMap<String, Bus>>> = OnceLock::new();
+    BUSES.get_or_init(Default::default)
+}
+
+/// The handle of a named virtual bus.
+///
+/// The bus is created on first use and shared by all the [`VirtualCan`] channels that have the same name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualBus {
+    name: String,
+}
+
+impl VirtualBus {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        lock(buses()).entry(name.clone()).or_insert_with(Bus::new);
+        Self { name }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Delay of every frame before it can be received.
+    pub fn set_latency(&self, latency: Duration) -> &Self {
+        self.with_bus(|bus| bus.latency = latency);
+        self
+    }
+
+    /// Probability of losing a transmitted frame, it's limited into `0.0..=1.0`.
+    pub fn set_loss(&self, probability: f64) -> &Self {
+        self.with_bus(|bus| bus.loss = probability.clamp(0., 1.));
+        self
+    }
+
+    /// Forward the frames transmitted on this bus to the other bus, and vice versa.
+    ///
+    /// The forwarded frames are not forwarded again by the other bus's bridges.
+    pub fn bridge(&self, other: &VirtualBus) -> &Self {
+        if self != other {
+            let mut buses = lock(buses());
+            for (from, to) in [(&self.name, &other.name), (&other.name, &self.name)] {
+                let bus = buses.entry(from.clone()).or_insert_with(Bus::new);
+                if !bus.bridges.contains(to) {
+                    bus.bridges.push(to.clone());
+                }
+            }
+        }
+        self
+    }
+
+    /// Remove the bridge between this bus and the other bus.
+    pub fn unbridge(&self, other: &VirtualBus) -> &Self {
+        let mut buses = lock(buses());
+        for (from, to) in [(&self.name, &other.name), (&other.name, &self.name)] {
+            if let Some(bus) = buses.get_mut(from) {
+                bus.bridges.retain(|v| v != to);
+            }
+        }
+        self
+    }
+
+    /// Count of channel handles that joined this bus.
+    pub fn node_count(&self) -> usize {
+        lock(buses()).get(&self.name)
+            .map(|bus| bus.nodes.iter().filter(|n| n.strong_count() > 0).count())
+            .unwrap_or_default()
+    }
+
+    fn with_bus<R>(&self, callback: impl FnOnce(&mut Bus) -> R) -> R {
+        let mut buses = lock(buses());
+        callback(buses.entry(self.name.clone()).or_insert_with(Bus::new))
+    }
+}
+
+/// The virtual CAN device, each channel of it is a node on the [`VirtualBus`] of the same name.
+#[derive(Debug, Default, Clone)]
+pub struct VirtualCan {
+    nodes: Arc<Mutex<HashMap<String, Arc<Node>>>>,
+}
+
+impl VirtualCan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Join the bus named `channel`, nothing will be done if the channel is opened.
+    pub fn init_channel(&self, channel: &str) -> Result<VirtualBus, Error> {
+        let bus = VirtualBus::new(channel);
+        let mut nodes = lock(&self.nodes);
+        if !nodes.contains_key(channel) {
+            let node = Arc::new(Node::new());
+            bus.with_bus(|b| b.nodes.push(Arc::downgrade(&node)));
+            nodes.insert(channel.to_owned(), node);
+        }
+
+        Ok(bus)
+    }
+
+    /// Leave the bus named `channel`, the frames not yet received are discarded.
+    pub fn close_channel(&self, channel: &str) -> Result<(), Error> {
+        let node = lock(&self.nodes).remove(channel)
+            .ok_or(Error::channel_not_opened(channel))?;
+        VirtualBus::new(channel)
+            .with_bus(|b| b.nodes.retain(|n| !std::ptr::eq(n.as_ptr(), Arc::as_ptr(&node))));
+
+        Ok(())
+    }
+
+    /// Sets CAN ID filters of the channel.
+    ///
+    /// A frame is accepted when `(id & can_mask) == (can_id & can_mask)`,
+    /// and the filter with `extended` only accepts extended frames.
+    /// All frames are accepted when filters is empty.
+    pub fn set_filters(&self, channel: &str, filters: &[Filter]) -> Result<(), Error> {
+        let node = self.node(channel)?;
+        *lock(&node.filters) = filters.to_vec();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn node(&self, channel: &str) -> Result<Arc<Node>, Error> {
+        lock(&self.nodes).get(channel)
+            .cloned()
+            .ok_or(Error::channel_not_opened(channel))
+    }
+}
+
+impl TryFrom<DeviceBuilder> for VirtualCan {
+    type Error = Error;
+
+    fn try_from(builder: DeviceBuilder) -> Result<Self, Self::Error> {
+        let device = VirtualCan::new();
+        builder.channel_configs()
+            .iter()
+            .try_for_each(|(chl, cfg)| {
+                let bus = device.init_channel(chl)?;
+
+                if let Some(filters) = cfg.get_other::<Vec<Filter>>(FILTERS)? {
+                    device.set_filters(chl, &filters)?;
+                }
+
+                if let Some(latency) = cfg.get_other::<u64>(LATENCY)? {
+                    bus.set_latency(Duration::from_millis(latency));
+                }
+
+                if let Some(loss) = cfg.get_other::<f64>(LOSS)? {
+                    bus.set_loss(loss);
+                }
+
+                Ok(())
+            })?;
+
+        Ok(device)
+    }
+}
+
+/// The typed configuration of a virtual channel.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VirtualChannelConfig {
+    pub filters: Option<Vec<Filter>>,
+    /// The latency of bus in milliseconds.
+    pub latency: Option<u64>,
+    /// The frame loss probability of bus in range `0.0..=1.0`.
+    pub loss: Option<f64>,
+}
+
+/// The typed configuration of [`VirtualCan`], the channels are keyed by bus name.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VirtualCanConfig {
+    pub channels: HashMap<String, VirtualChannelConfig>,
+}
+
+impl DeviceConfig for VirtualCanConfig {
+    type Device = VirtualCan;
+
+    fn builder(&self) -> DeviceBuilder {
+        let mut builder = DeviceBuilder::new();
+        for (chl, cfg) in &self.channels {
+            let mut config = ChannelConfig::new(0);
+            if let Some(filters) = &cfg.filters {
+                config.add_other(FILTERS, Box::new(filters.clone()));
+            }
+            if let Some(latency) = cfg.latency {
+                config.add_other(LATENCY, Box::new(latency));
+            }
+            if let Some(loss) = cfg.loss {
+                config.add_other(LOSS, Box::new(loss));
+            }
+            builder.add_config(chl.as_str(), config);
+        }
+
+        builder
+    }
+}
+
+impl Device for VirtualCan {
+    type Channel = String;
+    type Frame = Message;
+
+    #[inline]
+    fn opened_channels(&self) -> Vec<Self::Channel> {
+        lock(&self.nodes).keys()
+            .cloned()
+            .collect()
+    }
+
+    fn transmit(&self, msg: Self::Frame, _: Option<u32>) -> CanResult<(), Error> {
+        let channel = msg.channel();
+        let sender = self.node(&channel)?;
+
+        let mut buses = lock(buses());
+        let bridges = match buses.get_mut(&channel) {
+            Some(bus) => {
+                if bus.lost() {
+                    log::trace!("RUST-CAN - virtual frame lost on: {}", channel);
+                    return Ok(());
+                }
+                bus.deliver(&msg, &channel, Some(&sender));
+                bus.bridges.clone()
+            },
+            None => return Err(Error::channel_not_opened(channel)),
+        };
+
+        bridges.iter()
+            .for_each(|name| if let Some(bus) = buses.get_mut(name) {
+                bus.deliver(&msg, name, None);
+            });
+
+        Ok(())
+    }
+
+    fn receive(&self, channel: Self::Channel, timeout: Option<u32>) -> CanResult<Vec<Self::Frame>, Error> {
+        let node = self.node(&channel)?;
+        let deadline = Instant::now() + Duration::from_millis(timeout.unwrap_or_default() as u64);
+
+        let mut queue = lock(&node.queue);
+        loop {
+            let now = Instant::now();
+            let mut results = Vec::new();
+            while queue.front().is_some_and(|(ready, _)| *ready <= now) {
+                if let Some((_, msg)) = queue.pop_front() {
+                    results.push(msg);
+                }
+            }
+
+            if !results.is_empty() || now >= deadline {
+                return Ok(results);
+            }
+
+            let wait = match queue.front() {
+                Some((ready, _)) => (*ready).min(deadline) - now,
+                None => deadline - now,
+            };
+            queue = node.cond.wait_timeout(queue, wait)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.opened_channels()
+            .into_iter()
+            .for_each(|c| {
+                let _ = self.close_channel(&c);
+            });
+    }
+}
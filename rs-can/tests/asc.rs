@@ -0,0 +1,96 @@
+use std::io::Cursor;
+use rs_can::{CanDirect, CanFrame, CanId, CanMessage, CanType, CanXlControl, asc::{AscReader, AscWriter, Timestamps}};
+
+const ASC: &str = r#"date Wed Jun 13 10:21:00.123 am 2018
+base hex  timestamps absolute
+internal events logged
+// version 9.0.0
+Begin Triggerblock Wed Jun 13 10:21:00.123 am 2018
+   0.000000 Start of measurement
+   0.015991 1  123             Rx   d 8 01 02 03 04 05 06 07 08  Length = 231910 BitCount = 120 ID = 291
+   0.017000 2  1ABCDEFx        Tx   d 2 AA BB
+   0.020000 1  ErrorFrame
+   0.030000 1  456             Rx   r 4
+   0.040000 CANFD   2 Rx        7E8  EngineData                    1 0 9 12 00 01 02 03 04 05 06 07 08 09 0A 0B        0    0     3000        0        0        0        0        0
+   0.050000 1  Statistic: D 0 R 0 XD 0 XR 0 E 0 O 0 B 0.00%
+End TriggerBlock
+"#;
+
+#[test]
+fn test_reader() -> anyhow::Result<()> {
+    let frames = AscReader::<_, CanMessage>::new(Cursor::new(ASC))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(frames.len(), 5);
+    // 2018-06-13T10:21:00.123Z
+    let start = 1_528_885_260_123;
+
+    assert_eq!(frames[0].timestamp(), start + 16);
+    assert_eq!(frames[0].channel(), "1");
+    assert_eq!(frames[0].id(), CanId::Standard(0x123));
+    assert_eq!(frames[0].direct(), CanDirect::Receive);
+    assert_eq!(frames[0].data(), &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+
+    assert_eq!(frames[1].id(), CanId::Extended(0x1ABCDEF));
+    assert_eq!(frames[1].direct(), CanDirect::Transmit);
+    assert_eq!(frames[1].channel(), "2");
+
+    assert!(frames[2].is_error_frame());
+    assert!(frames[3].is_remote());
+    assert_eq!(frames[3].length(), 4);
+
+    assert_eq!(frames[4].can_type(), CanType::CanFd);
+    assert!(frames[4].is_bitrate_switch());
+    assert!(!frames[4].is_esi());
+    assert_eq!(frames[4].length(), 12);
+    assert_eq!(frames[4].timestamp(), start + 40);
+
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> anyhow::Result<()> {
+    let start = 1_700_000_000_000;
+    let mut frames = Vec::new();
+    let mut msg = CanMessage::new(0x7DF, &[0x02, 0x10, 0x01]).unwrap();
+    msg.set_channel("can0".into()).set_timestamp(Some(start + 5));
+    frames.push(msg);
+    let mut msg = CanMessage::new(CanId::Extended(0x18DAF110), &[0x11; 20]).unwrap();
+    msg.set_channel("can1".into()).set_timestamp(Some(start + 1250)).set_bitrate_switch(true).set_esi(true);
+    frames.push(msg);
+    let mut msg = CanMessage::new_remote(0x321, 3).unwrap();
+    msg.set_channel("can0".into()).set_timestamp(Some(start + 1300)).set_direct(CanDirect::Receive);
+    frames.push(msg);
+    let mut msg = CanMessage::new(0x24, &[0x5A; 100]).unwrap();
+    msg.set_channel("can1".into()).set_timestamp(Some(start + 1400))
+        .set_xl_control(CanXlControl { sdu_type: 0x03, vcid: 0x12, acceptance_field: 0xDEADBEEF, sec: true });
+    frames.push(msg);
+
+    for timestamps in [Timestamps::Absolute, Timestamps::Relative] {
+        let mut writer = AscWriter::new(Vec::new());
+        writer.set_start(start)
+            .set_timestamps(timestamps);
+        for frame in &frames {
+            writer.write(frame)?;
+        }
+        writer.finish()?;
+        let content = String::from_utf8(writer.get_ref().clone())?;
+        assert!(content.starts_with("date Tue Nov 14 10:13:20.000 pm 2023\n"));
+        assert!(content.ends_with("End TriggerBlock\n"));
+
+        let mut reader = AscReader::<_, CanMessage>::new(Cursor::new(content));
+        reader.set_channel(1, "can0".into())
+            .set_channel(2, "can1".into());
+        let results = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(results, frames);
+        for (result, frame) in results.iter().zip(&frames) {
+            assert_eq!(result.timestamp(), frame.timestamp());
+            assert_eq!(result.channel(), frame.channel());
+            assert_eq!(result.direct(), frame.direct());
+            assert_eq!(result.can_type(), frame.can_type());
+            assert_eq!(result.is_bitrate_switch(), frame.is_bitrate_switch());
+            assert_eq!(result.xl_control(), frame.xl_control());
+        }
+    }
+
+    Ok(())
+}
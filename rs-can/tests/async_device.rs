@@ -0,0 +1,57 @@
+#![cfg(feature = "async")]
+
+mod utils;
+
+use futures::{executor::block_on, SinkExt, StreamExt};
+use rs_can::{AsyncCanDevice, BlockingAdapter, CanError, CanFrame, VirtualCan};
+use self::utils::message;
+
+#[test]
+fn test_blocking_adapter() -> anyhow::Result<(), CanError> {
+    let channel = "test-async-adapter";
+    let sender = VirtualCan::new();
+    sender.init_channel(channel)?;
+    let receiver = VirtualCan::new();
+    receiver.init_channel(channel)?;
+    let sender = BlockingAdapter::new(sender, None);
+    let receiver = BlockingAdapter::new(receiver, None);
+
+    block_on(async {
+        sender.transmit(message(channel, 0x123, &[0x01, 0x02])).await?;
+        let frames = receiver.receive(channel.into()).await?;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].id().into_bits(), 0x123);
+        assert_eq!(frames[0].data(), &[0x01, 0x02]);
+
+        let mut sink = sender.sink();
+        for id in 0x200..0x205 {
+            sink.send(message(channel, id, &[id as u8])).await?;
+        }
+
+        let ids = receiver.stream(channel.into())
+            .take(5)
+            .map(|r| r.map(|f| f.id().into_bits()))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(ids, (0x200..0x205).collect::<Vec<_>>());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn test_blocking_adapter_error() -> anyhow::Result<(), CanError> {
+    let adapter = BlockingAdapter::new(VirtualCan::new(), None);
+
+    block_on(async {
+        let mut stream = adapter.stream("test-async-unopened".into());
+        assert!(stream.next().await.unwrap().is_err());
+        // the stream is ended after an error.
+        assert!(stream.next().await.is_none());
+        assert!(adapter.transmit(message("test-async-unopened", 0x123, &[])).await.is_err());
+    });
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::io::Cursor;
+use rs_can::{CanDirect, CanFrame, CanId, CanMessage, CanType, blf::{BlfReader, BlfWriter}};
+
+fn frames(start: u64, count: usize) -> Vec<CanMessage> {
+    (0..count)
+        .map(|i| {
+            let mut msg = match i % 4 {
+                0 => CanMessage::new(0x123, &[i as u8; 8]).unwrap(),
+                1 => {
+                    let mut msg = CanMessage::new(CanId::Extended(0x18DAF110), &[i as u8; 48]).unwrap();
+                    msg.set_bitrate_switch(true);
+                    msg
+                },
+                2 => CanMessage::new_remote(0x456, 2).unwrap(),
+                _ => {
+                    let mut msg = CanMessage::new(0, &[]).unwrap();
+                    msg.set_error_frame(true);
+                    msg
+                },
+            };
+            msg.set_channel(if i % 2 == 0 { "can0" } else { "can1" }.into())
+                .set_timestamp(Some(start + i as u64))
+                .set_direct(if i % 3 == 0 { CanDirect::Transmit } else { CanDirect::Receive });
+            msg
+        })
+        .collect()
+}
+
+#[test]
+fn test_round_trip() -> anyhow::Result<()> {
+    let start = 1_700_000_000_000;
+    for (compression, count) in [(Some(6), 10_000), (None, 100)] {
+        let frames = frames(start, count);
+        let mut writer = BlfWriter::new(Cursor::new(Vec::new()));
+        writer.set_compression(compression);
+        for frame in &frames {
+            writer.write(frame)?;
+        }
+        writer.finish()?;
+        let content = writer.get_ref().get_ref().clone();
+        assert_eq!(&content[..4], b"LOGG");
+
+        let mut reader = BlfReader::<_, CanMessage>::new(Cursor::new(content));
+        reader.set_channel(1, "can0".into())
+            .set_channel(2, "can1".into());
+        let results = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(reader.start(), start);
+        assert_eq!(reader.stop(), start + count as u64 - 1);
+        assert_eq!(reader.object_count(), count as u32);
+        assert_eq!(results.len(), frames.len());
+        for (result, frame) in results.iter().zip(&frames) {
+            assert_eq!(result, frame);
+            assert_eq!(result.timestamp(), frame.timestamp());
+            assert_eq!(result.channel(), frame.channel());
+            assert_eq!(result.can_type(), frame.can_type());
+            assert_eq!(result.is_error_frame(), frame.is_error_frame());
+            if !frame.is_error_frame() {
+                assert_eq!(result.direct(), frame.direct());
+            }
+            if frame.can_type() == CanType::CanFd {
+                assert!(result.is_bitrate_switch());
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+use rs_can::{CanDevice, CanError, CanFilter, CanFrame, CanMessage, DeviceBuilder, DeviceConfig, VirtualCan, virtual_can::{self, VirtualCanConfig, VirtualChannelConfig}};
+
+/// A bench of devices described in one file.
+#[derive(Debug, Deserialize)]
+struct Bench {
+    tester: VirtualCanConfig,
+    ecu: VirtualCanConfig,
+}
+
+const BENCH: &str = r#"
+tester:
+  channels:
+    test-config-bench:
+      latency: 0
+ecu:
+  channels:
+    test-config-bench:
+      filters:
+        - can_id: 0x7E0
+          can_mask: 0x7F0
+          extended: false
+"#;
+
+#[test]
+fn test_bench() -> anyhow::Result<()> {
+    let channel = "test-config-bench";
+    let bench: Bench = serde_yaml::from_str(BENCH)?;
+    assert_eq!(bench.ecu.channels[channel].filters, Some(vec![CanFilter::from((0x7E0, 0x7F0))]));
+    assert_eq!(bench.tester.channels[channel].latency, Some(0));
+    assert_eq!(bench.tester.channels[channel].loss, None);
+
+    let tester = bench.tester.build()?;
+    let ecu: VirtualCan = DeviceBuilder::from_config(&bench.ecu).build()?;
+    for id in [0x123, 0x7E8] {
+        let mut msg = CanMessage::new(id, &[0x01]).unwrap();
+        msg.set_channel(channel.into());
+        tester.transmit(msg, None)?;
+    }
+
+    let frames = ecu.receive(channel.into(), Some(10))?;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].id().into_bits(), 0x7E8);
+
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> anyhow::Result<(), CanError> {
+    let mut cfg = VirtualCanConfig::default();
+    cfg.channels.insert("test-config-round-trip".into(), VirtualChannelConfig {
+        filters: Some(vec![CanFilter::from((0x100, 0x700))]),
+        latency: Some(5),
+        loss: Some(0.5),
+    });
+
+    let text = serde_yaml::to_string(&cfg)
+        .map_err(|e| CanError::OtherError(e.to_string()))?;
+    let loaded: VirtualCanConfig = serde_yaml::from_str(&text)
+        .map_err(|e| CanError::OtherError(e.to_string()))?;
+    assert_eq!(loaded, cfg);
+
+    let builder = loaded.builder();
+    let chl = &builder.channel_configs()["test-config-round-trip"];
+    assert_eq!(chl.get_other::<u64>(virtual_can::LATENCY)?, Some(5));
+    assert_eq!(chl.get_other::<f64>(virtual_can::LOSS)?, Some(0.5));
+
+    Ok(())
+}
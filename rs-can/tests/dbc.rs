@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use rs_can::{CanFrame, CanId, CanMessage, dbc::{AttributeType, AttributeValue, ByteOrder, Dbc, ValueType}};
+
+const DBC: &str = r#"VERSION "1.0"
+
+NS_ :
+	NS_DESC_
+	CM_
+	BA_DEF_
+	BA_
+	VAL_
+	SIG_VALTYPE_
+
+BS_:
+
+BU_: ECU Tester
+
+VAL_TABLE_ Gears 0 "P" 1 "R" 2 "N" 3 "D" ;
+
+BO_ 256 Engine: 8 ECU
+ SG_ Speed : 0|16@1+ (0.01,0) [0|655.35] "km/h" Tester
+ SG_ Temp : 16|8@1- (1,-40) [-168|87] "degC" Tester
+ SG_ Rpm : 31|16@0+ (0.25,0) [0|16383.75] "rpm" Tester
+ SG_ Gear : 48|4@1+ (1,0) [0|3] "" Tester,ECU
+
+BO_ 2147484672 Diag: 8 Tester
+ SG_ Mux M : 0|8@1+ (1,0) [0|255] "" ECU
+ SG_ A m0 : 8|16@1+ (1,0) [0|65535] "" ECU
+ SG_ B m1 : 8|32@1+ (1,0) [0|0] "" ECU
+ SG_ C : 56|8@1- (1,0) [-128|127] "" ECU
+
+EV_ EnvVar: 0 [0|100] "" 0 1 DUMMY_NODE_VECTOR0 Vector__XXX;
+
+CM_ "network";
+CM_ BU_ ECU "engine control unit";
+CM_ BO_ 256 "engine status";
+CM_ SG_ 256 Speed "vehicle
+speed \"raw\"";
+BA_DEF_ BO_ "GenMsgCycleTime" INT 0 10000;
+BA_DEF_  "BusType" STRING ;
+BA_DEF_ SG_ "GenSigStartValue" FLOAT -3.4E+038 3.4E+038;
+BA_DEF_ BO_ "VFrameFormat" ENUM "StandardCAN","ExtendedCAN";
+BA_DEF_DEF_ "GenMsgCycleTime" 100;
+BA_DEF_DEF_ "BusType" "CAN";
+BA_ "BusType" "CAN FD";
+BA_ "GenMsgCycleTime" BO_ 256 10;
+BA_ "GenSigStartValue" SG_ 256 Temp 40.5;
+BA_ "VFrameFormat" BO_ 2147484672 1;
+VAL_ 256 Gear 0 "P" 1 "R" 2 "N" 3 "D" ;
+SIG_VALTYPE_ 2147484672 B : 1;
+"#;
+
+fn values(items: &[(&str, f64)]) -> HashMap<String, f64> {
+    items.iter()
+        .map(|(k, v)| (k.to_string(), *v))
+        .collect()
+}
+
+#[test]
+fn test_parse() -> anyhow::Result<()> {
+    let dbc = DBC.parse::<Dbc>()?;
+    assert_eq!(dbc.version(), "1.0");
+    assert_eq!(dbc.nodes(), &["ECU", "Tester"]);
+    assert_eq!(dbc.value_tables()["Gears"][&3], "D");
+    assert_eq!(dbc.comment().as_deref(), Some("network"));
+    assert_eq!(dbc.node_comments()["ECU"], "engine control unit");
+    assert_eq!(dbc.messages().len(), 2);
+
+    let engine = dbc.message("Engine").unwrap();
+    assert_eq!(engine.id(), CanId::Standard(0x100));
+    assert_eq!(engine.size(), 8);
+    assert_eq!(engine.transmitter(), "ECU");
+    assert_eq!(engine.comment().as_deref(), Some("engine status"));
+    assert_eq!(engine.attributes()["GenMsgCycleTime"], AttributeValue::Int(10));
+
+    let speed = engine.signal("Speed").unwrap();
+    assert_eq!(speed.byte_order(), ByteOrder::LittleEndian);
+    assert_eq!(speed.factor(), 0.01);
+    assert_eq!(speed.unit(), "km/h");
+    assert_eq!(speed.comment().as_deref(), Some("vehicle\nspeed \"raw\""));
+    let temp = engine.signal("Temp").unwrap();
+    assert_eq!(temp.value_type(), ValueType::Signed);
+    assert_eq!(temp.offset(), -40.);
+    assert_eq!(temp.minimum(), -168.);
+    assert_eq!(temp.attributes()["GenSigStartValue"], AttributeValue::Float(40.5));
+    assert_eq!(engine.signal("Rpm").unwrap().byte_order(), ByteOrder::BigEndian);
+    let gear = engine.signal("Gear").unwrap();
+    assert_eq!(gear.receivers(), &["Tester", "ECU"]);
+    assert_eq!(gear.value_description(3).map(String::as_str), Some("D"));
+
+    let diag = dbc.message_by_id(CanId::Extended(0x400)).unwrap();
+    assert_eq!(diag.name(), "Diag");
+    assert_eq!(diag.multiplexor().unwrap().name(), "Mux");
+    assert_eq!(diag.signal("A").unwrap().multiplexer_value(), Some(0));
+    assert_eq!(diag.signal("B").unwrap().value_type(), ValueType::Float);
+
+    assert_eq!(dbc.attributes()["BusType"], AttributeValue::String("CAN FD".into()));
+    let def = dbc.attribute_definition("GenMsgCycleTime").unwrap();
+    assert_eq!(def.r#type(), &AttributeType::Int(0, 10000));
+    assert_eq!(def.default(), &Some(AttributeValue::Int(100)));
+    assert_eq!(dbc.attribute_definition("VFrameFormat").unwrap().r#type(),
+               &AttributeType::Enum(vec!["StandardCAN".into(), "ExtendedCAN".into()]));
+
+    assert!("BO_ 1 Bad: 8 ECU\n SG_ S : 0|8@2+ (1,0) [0|0] \"\" ECU\n".parse::<Dbc>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_encode_decode() -> anyhow::Result<()> {
+    let dbc = DBC.parse::<Dbc>()?;
+
+    let frame: CanMessage = dbc.encode("Engine", &values(&[("Speed", 100.5), ("Temp", -20.), ("Rpm", 1000.), ("Gear", 3.)]))?;
+    assert_eq!(frame.id(), CanId::Standard(0x100));
+    assert_eq!(frame.data(), &[0x42, 0x27, 0x14, 0x0F, 0xA0, 0x00, 0x03, 0x00]);
+
+    let decoded = dbc.decode(&frame)?;
+    assert_eq!(decoded.len(), 4);
+    assert!((decoded["Speed"] - 100.5).abs() < 1e-9);
+    assert_eq!(decoded["Temp"], -20.);
+    assert_eq!(decoded["Rpm"], 1000.);
+    assert_eq!(decoded["Gear"], 3.);
+
+    let frame = CanMessage::new(0x100, &[0, 0, 0xF6, 0, 0, 0, 0, 0]).unwrap();
+    assert_eq!(dbc.decode(&frame)?["Temp"], -50.);
+
+    let diag = dbc.message("Diag").unwrap();
+    let data = diag.encode(&values(&[("Mux", 0.), ("A", 0x1234 as f64), ("C", -2.)]))?;
+    assert_eq!(data, [0x00, 0x34, 0x12, 0x00, 0x00, 0x00, 0x00, 0xFE]);
+    let decoded = diag.decode(&data)?;
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded["A"], 0x1234 as f64);
+    assert_eq!(decoded["C"], -2.);
+
+    let frame: CanMessage = diag.encode_frame(&values(&[("Mux", 1.), ("B", 1.5), ("C", 127.)]))?;
+    assert_eq!(frame.id(), CanId::Extended(0x400));
+    assert_eq!(frame.data(), &[0x01, 0x00, 0x00, 0xC0, 0x3F, 0x00, 0x00, 0x7F]);
+    let decoded = dbc.decode(&frame)?;
+    assert_eq!(decoded["B"], 1.5);
+    assert!(!decoded.contains_key("A"));
+
+    // out of range and missing signals.
+    assert!(diag.encode(&values(&[("Mux", 0.), ("A", 0.), ("C", 128.)])).is_err());
+    assert!(diag.encode(&values(&[("Mux", 0.), ("C", 0.)])).is_err());
+    // data is too short.
+    assert!(diag.decode(&[0x00, 0x01]).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::{thread, time::Duration};
+use rs_can::{CanDevice, CanError, CanFrame, CanId, CanType, VirtualCan, DEFAULT_PADDING, isotp::{FlowStatus, IsoTp, IsoTpConfig, IsoTpFrame, StMin}};
+
+fn endpoints(channel: &str, tester: IsoTpConfig, ecu: IsoTpConfig) -> Result<(IsoTp<VirtualCan>, IsoTp<VirtualCan>), CanError> {
+    let device1 = VirtualCan::new();
+    device1.init_channel(channel)?;
+    let device2 = VirtualCan::new();
+    device2.init_channel(channel)?;
+    Ok((IsoTp::new(device1, channel.into(), tester), IsoTp::new(device2, channel.into(), ecu)))
+}
+
+fn transfer(tester: &mut IsoTp<VirtualCan>, ecu: IsoTp<VirtualCan>, data: &[u8]) -> anyhow::Result<()> {
+    let handle = thread::spawn(move || {
+        let mut ecu = ecu;
+        ecu.receive(Some(1000))
+    });
+    thread::sleep(Duration::from_millis(10));
+    tester.send(data)?;
+    let received = handle.join().unwrap()?;
+    assert_eq!(received, data);
+    Ok(())
+}
+
+#[test]
+fn test_frame() -> anyhow::Result<()> {
+    let sf = IsoTpFrame::SingleFrame { data: vec![0x10, 0x03] };
+    assert_eq!(sf.encode(8, Some(DEFAULT_PADDING)), [0x02, 0x10, 0x03, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]);
+    assert_eq!(sf.encode(8, None), [0x02, 0x10, 0x03]);
+    assert_eq!(IsoTpFrame::decode(&[0x02, 0x10, 0x03, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA])?, sf);
+
+    // escape sequence of CAN-FD single frame.
+    let sf = IsoTpFrame::SingleFrame { data: vec![0x11; 20] };
+    let data = sf.encode(64, Some(0xCC));
+    assert_eq!(data.len(), 24);
+    assert_eq!(&data[..3], &[0x00, 20, 0x11]);
+    assert_eq!(&data[22..], &[0xCC, 0xCC]);
+    assert_eq!(IsoTpFrame::decode(&data)?, sf);
+
+    let ff = IsoTpFrame::FirstFrame { length: 0x123, data: vec![0x22; 6] };
+    assert_eq!(ff.encode(8, None), [0x11, 0x23, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22]);
+    // escape sequence of first frame length.
+    let ff = IsoTpFrame::FirstFrame { length: 0x12345, data: vec![0x22; 58] };
+    let data = ff.encode(64, None);
+    assert_eq!(&data[..6], &[0x10, 0x00, 0x00, 0x01, 0x23, 0x45]);
+    assert_eq!(IsoTpFrame::decode(&data)?, ff);
+
+    assert_eq!(IsoTpFrame::decode(&[0x21, 0x01, 0x02])?, IsoTpFrame::ConsecutiveFrame { sequence: 1, data: vec![0x01, 0x02] });
+    assert_eq!(IsoTpFrame::decode(&[0x31, 0x08, 0xF5])?, IsoTpFrame::FlowControl { status: FlowStatus::Wait, block_size: 8, st_min: StMin(0xF5) });
+    assert_eq!(StMin(0xF5).duration(), Duration::from_micros(500));
+    assert_eq!(StMin(0x80).duration(), Duration::from_millis(127));
+    assert_eq!(StMin::from(Duration::from_micros(450)), StMin(0xF5));
+    assert_eq!(StMin::from(Duration::from_millis(20)), StMin(20));
+
+    assert!(IsoTpFrame::decode(&[]).is_err());
+    assert!(IsoTpFrame::decode(&[0x05, 0x01]).is_err());
+    assert!(IsoTpFrame::decode(&[0x40, 0x01]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_classic() -> anyhow::Result<()> {
+    let tester = IsoTpConfig::new(0x7E0, 0x7E8);
+    let mut ecu = IsoTpConfig::new(0x7E8, 0x7E0);
+    ecu.set_flow_control(4, Duration::from_micros(100));
+    let (mut tester, ecu) = endpoints("test-isotp-classic", tester, ecu)?;
+
+    transfer(&mut tester, ecu, &(0..200).map(|v| v as u8).collect::<Vec<_>>())?;
+
+    Ok(())
+}
+
+#[test]
+fn test_fd() -> anyhow::Result<()> {
+    let channel = "test-isotp-fd";
+    let mut tester = IsoTpConfig::new(CanId::Extended(0x18DA10F1), CanId::Extended(0x18DAF110));
+    tester.set_fd(true, true);
+    let mut ecu = IsoTpConfig::new(CanId::Extended(0x18DAF110), CanId::Extended(0x18DA10F1));
+    ecu.set_fd(true, true);
+    let (mut tester_tp, ecu_tp) = endpoints(channel, tester.clone(), ecu.clone())?;
+
+    let monitor = VirtualCan::new();
+    monitor.init_channel(channel)?;
+    transfer(&mut tester_tp, ecu_tp, &[0x5A; 5000])?;
+    let frames = monitor.receive(channel.into(), Some(10))?;
+    assert_eq!(frames[0].can_type(), CanType::CanFd);
+    assert!(frames[0].is_bitrate_switch());
+    assert_eq!(frames[0].length(), 64);
+    assert_eq!(&frames[0].data()[..6], &[0x10, 0x00, 0x00, 0x00, 0x13, 0x88]);
+
+    tester.set_padding(None);
+    let (mut tester, ecu) = endpoints(channel, tester, ecu)?;
+    transfer(&mut tester, ecu, &[0x01; 62])?;
+
+    Ok(())
+}
+
+#[test]
+fn test_functional() -> anyhow::Result<()> {
+    let mut tester = IsoTpConfig::new(0x7E0, 0x7E8);
+    tester.set_functional_id(0x7DF);
+    let mut ecu = IsoTpConfig::new(0x7E8, 0x7E0);
+    ecu.set_functional_id(0x7DF);
+    let (mut tester, mut ecu) = endpoints("test-isotp-functional", tester, ecu)?;
+
+    tester.send_functional(&[0x3E, 0x80])?;
+    assert_eq!(ecu.receive(Some(100))?, [0x3E, 0x80]);
+    assert!(tester.send_functional(&[0x00; 8]).is_err());
+
+    ecu.send(&[0x7E, 0x00])?;
+    assert_eq!(tester.receive(Some(100))?, [0x7E, 0x00]);
+
+    Ok(())
+}
+
+#[test]
+fn test_timeout() -> anyhow::Result<()> {
+    let mut tester = IsoTpConfig::new(0x7E0, 0x7E8);
+    tester.set_timeouts(100, 50, 50);
+    let mut ecu = IsoTpConfig::new(0x7E8, 0x7E0);
+    ecu.set_timeouts(100, 50, 50);
+    let (mut tester, mut ecu) = endpoints("test-isotp-timeout", tester, ecu)?;
+
+    // no flow control is responded.
+    assert!(matches!(tester.send(&[0x00; 20]), Err(CanError::TimeoutError(_))));
+    assert!(matches!(tester.receive(Some(10)), Err(CanError::TimeoutError(_))));
+    // the first frame is received, but no consecutive frame is sent.
+    assert!(matches!(ecu.receive(Some(10)), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_pending() -> anyhow::Result<()> {
+    let (mut tester, mut ecu) = endpoints("test-isotp-pending", IsoTpConfig::new(0x7E0, 0x7E8), IsoTpConfig::new(0x7E8, 0x7E0))?;
+
+    // the frames are pending on bus before receiving.
+    tester.send(&[0x10, 0x03])?;
+    tester.send(&[0x3E, 0x00])?;
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(ecu.receive(None)?, [0x10, 0x03]);
+    assert_eq!(ecu.receive(Some(0))?, [0x3E, 0x00]);
+    assert!(matches!(ecu.receive(Some(0)), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
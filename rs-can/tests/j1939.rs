@@ -0,0 +1,213 @@
+use std::{thread, time::Duration};
+use rs_can::{CanDevice, CanError, CanFrame, CanId, CanMessage, J1939Id, VirtualCan, j1939::{J1939, J1939Message, Name, DEFAULT_PRIORITY, GLOBAL_ADDRESS, PGN_ADDRESS_CLAIMED, PGN_REQUEST, PGN_TP_CM}};
+
+fn endpoint(channel: &str, identity: u32) -> Result<J1939<VirtualCan>, CanError> {
+    let device = VirtualCan::new();
+    device.init_channel(channel)?;
+    let mut name = Name::default();
+    name.set_identity_number(identity)
+        .set_manufacturer_code(0x123)
+        .set_arbitrary_address_capable(true);
+    Ok(J1939::new(device, channel.into(), name))
+}
+
+fn transfer(channel: &str, destination: u8, data: Vec<u8>) -> anyhow::Result<J1939Message> {
+    let mut sender = endpoint(channel, 1)?;
+    sender.set_address(0x10)
+        .set_bam_interval(Duration::from_millis(5));
+    let mut receiver = endpoint(channel, 2)?;
+    receiver.set_address(0x20)
+        .set_max_packets(2);
+
+    let handle = thread::spawn(move || receiver.receive(Some(2000)));
+    thread::sleep(Duration::from_millis(10));
+    sender.send(0xFEEC, DEFAULT_PRIORITY, destination, &data)?;
+    let msg = handle.join().unwrap()?;
+    assert_eq!(msg.data, data);
+    assert_eq!(msg.pgn, 0xFEEC);
+    assert_eq!(msg.source, 0x10);
+
+    Ok(msg)
+}
+
+#[test]
+fn test_id() -> anyhow::Result<()> {
+    // EEC1 from engine.
+    let id = J1939Id::try_from(CanId::from_bits(0x0CF00400, Some(true)))?;
+    assert_eq!(id.priority(), 3);
+    assert_eq!(id.pgn(), 0xF004);
+    assert_eq!(id.sa(), 0x00);
+    assert!(!id.is_pdu1());
+    assert_eq!(id.destination(), None);
+
+    // request to 0x00 from 0xF9.
+    let id = J1939Id::new(6, PGN_REQUEST, 0x00, 0xF9);
+    assert_eq!(CanId::from(id), CanId::Extended(0x18EA00F9));
+    assert_eq!(id.pgn(), PGN_REQUEST);
+    assert_eq!(id.destination(), Some(0x00));
+    assert_eq!(J1939Id::from_bits(id.into_bits()), id);
+
+    assert!(J1939Id::try_from(CanId::Standard(0x123)).is_err());
+
+    let mut name = Name::default();
+    name.set_identity_number(0x1F_FFFF)
+        .set_manufacturer_code(0x7FF)
+        .set_function(0x81)
+        .set_industry_group(0)
+        .set_arbitrary_address_capable(true);
+    assert_eq!(name.identity_number(), 0x1F_FFFF);
+    assert_eq!(name.manufacturer_code(), 0x7FF);
+    assert_eq!(name.function(), 0x81);
+    assert!(name.is_arbitrary_address_capable());
+    assert_eq!(Name::from_bytes(name.to_bytes()), name);
+
+    Ok(())
+}
+
+#[test]
+fn test_transport() -> anyhow::Result<()> {
+    let data = (0..100).collect::<Vec<u8>>();
+    let msg = transfer("j1939-bam", GLOBAL_ADDRESS, data)?;
+    assert_eq!(msg.destination, GLOBAL_ADDRESS);
+
+    // PGN 0xFEEC is PDU2, so it's broadcast by BAM even if destination is specific.
+    let msg = transfer("j1939-bam2", 0x20, vec![0x55; 9])?;
+    assert_eq!(msg.destination, GLOBAL_ADDRESS);
+
+    let mut sender = endpoint("j1939-cmdt", 1)?;
+    sender.set_address(0x10);
+    let mut receiver = endpoint("j1939-cmdt", 2)?;
+    receiver.set_address(0x20)
+        .set_max_packets(3);
+    let data = (0..=255).cycle().take(1785).collect::<Vec<u8>>();
+    let handle = thread::spawn(move || receiver.receive(Some(2000)));
+    thread::sleep(Duration::from_millis(10));
+    sender.send(0xEF00, DEFAULT_PRIORITY, 0x20, &data)?;
+    let msg = handle.join().unwrap()?;
+    assert_eq!(msg.pgn, 0xEF00);
+    assert_eq!(msg.destination, 0x20);
+    assert_eq!(msg.data, data);
+
+    assert!(sender.send(0xEF00, DEFAULT_PRIORITY, 0x20, &[0; 1786]).is_err());
+    // no receiver at 0x30.
+    assert!(matches!(sender.send(0xEF00, DEFAULT_PRIORITY, 0x30, &[0; 20]), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_address_claim() -> anyhow::Result<()> {
+    let mut winner = endpoint("j1939-claim", 1)?;
+    assert_eq!(winner.claim_address(0x80)?, 0x80);
+    assert_eq!(winner.address(), Some(0x80));
+
+    // the NAME of winner is lower, so loser selects another address.
+    let mut loser = endpoint("j1939-claim", 2)?;
+    let handle = thread::spawn(move || {
+        let _ = winner.receive(Some(500));
+        winner
+    });
+    let address = loser.claim_address(0x80)?;
+    assert_ne!(address, 0x80);
+    assert!((128..=247).contains(&address));
+    let winner = handle.join().unwrap();
+    assert_eq!(winner.address(), Some(0x80));
+    assert_eq!(loser.controllers().get(&0x80), Some(&winner.name()));
+
+    // request PGN to winner.
+    let mut winner = winner;
+    let handle = thread::spawn(move || winner.receive(Some(1000)));
+    thread::sleep(Duration::from_millis(10));
+    loser.request(0xFEE5, 0x80)?;
+    let msg = handle.join().unwrap()?;
+    assert_eq!(msg.pgn, PGN_REQUEST);
+    assert_eq!(msg.source, address);
+    assert_eq!(msg.data, [0xE5, 0xFE, 0x00]);
+
+    Ok(())
+}
+
+#[test]
+fn test_batch() -> anyhow::Result<()> {
+    let channel = "j1939-batch";
+    let mut node = endpoint(channel, 2)?;
+    let peer = VirtualCan::new();
+    peer.init_channel(channel)?;
+
+    // the broadcast and the claim of lower NAME are received by node in a batch.
+    let mut name = Name::default();
+    name.set_identity_number(1)
+        .set_manufacturer_code(0x123);
+    let frames = [
+        (J1939Id::new(DEFAULT_PRIORITY, 0xFEF1, GLOBAL_ADDRESS, 0x80), vec![0x01; 8]),
+        (J1939Id::new(DEFAULT_PRIORITY, PGN_ADDRESS_CLAIMED, GLOBAL_ADDRESS, 0x80), name.to_bytes().to_vec()),
+        (J1939Id::new(DEFAULT_PRIORITY, 0xFEF2, GLOBAL_ADDRESS, 0x80), vec![0x02; 8]),
+    ];
+    for (id, data) in frames {
+        let mut msg = CanMessage::new(id, &data).unwrap();
+        msg.set_channel(channel.into());
+        peer.transmit(msg, None)?;
+    }
+    thread::sleep(Duration::from_millis(10));
+
+    let address = node.claim_address(0x80)?;
+    assert_ne!(address, 0x80);
+    assert_eq!(node.controllers().get(&0x80), Some(&name));
+
+    // the frames skipped are received in order.
+    assert_eq!(node.receive(Some(100))?.pgn, 0xFEF1);
+    assert_eq!(node.receive(Some(100))?.pgn, 0xFEF2);
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_cts() -> anyhow::Result<()> {
+    let channel = "j1939-invalid-cts";
+    let mut sender = endpoint(channel, 1)?;
+    sender.set_address(0x10);
+    let receiver = VirtualCan::new();
+    receiver.init_channel(channel)?;
+
+    // the CTS requests sequence 0 of PGN 0xEF00.
+    let id = J1939Id::new(7, PGN_TP_CM, 0x10, 0x20);
+    let mut msg = CanMessage::new(id, &[0x11, 0x02, 0x00, 0xFF, 0xFF, 0x00, 0xEF, 0x00]).unwrap();
+    msg.set_channel(channel.into());
+    receiver.transmit(msg, None)?;
+    thread::sleep(Duration::from_millis(10));
+
+    assert!(matches!(sender.send(0xEF00, DEFAULT_PRIORITY, 0x20, &[0x00; 20]), Err(CanError::OperationError(_))));
+    let frames = receiver.receive(channel.into(), Some(100))?;
+    let data = frames.iter()
+        .map(|f| f.data().to_vec())
+        .collect::<Vec<_>>();
+    // RTS and abort are sent.
+    assert_eq!(data.len(), 2);
+    assert_eq!(data[0][0], 0x10);
+    assert_eq!(data[1][0], 0xFF);
+
+    Ok(())
+}
+
+#[test]
+fn test_pending() -> anyhow::Result<()> {
+    let channel = "j1939-pending";
+    let mut node = endpoint(channel, 2)?;
+    node.set_address(0x20);
+    let peer = VirtualCan::new();
+    peer.init_channel(channel)?;
+
+    // the frames are pending on bus before receiving.
+    for pgn in [0xFEF1, 0xFEF2] {
+        let mut msg = CanMessage::new(J1939Id::new(DEFAULT_PRIORITY, pgn, GLOBAL_ADDRESS, 0x80), &[0x01; 8]).unwrap();
+        msg.set_channel(channel.into());
+        peer.transmit(msg, None)?;
+    }
+    thread::sleep(Duration::from_millis(10));
+
+    assert_eq!(node.receive(Some(0))?.pgn, 0xFEF1);
+    assert_eq!(node.receive(None)?.pgn, 0xFEF2);
+    assert!(matches!(node.receive(Some(0)), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
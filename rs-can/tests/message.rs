@@ -0,0 +1,89 @@
+mod utils;
+
+use std::collections::HashSet;
+use rs_can::{CanDirect, CanError, CanFrame, CanId, CanMessage, CanType};
+use self::utils::message;
+
+#[test]
+fn test_serde() -> anyhow::Result<()> {
+    let mut msg = message("can0", 0x18DA00F1, &[0x01; 12]);
+    msg.set_timestamp_ns(Some(1_234_567_890))
+        .set_direct(CanDirect::Receive)
+        .set_bitrate_switch(true);
+
+    let text = serde_yaml::to_string(&msg)?;
+    let other: CanMessage = serde_yaml::from_str(&text)?;
+    assert_eq!(other, msg);
+    assert_eq!(other.timestamp_ns(), 1_234_567_890);
+    assert_eq!(other.timestamp(), 1_234);
+    assert_eq!(other.channel(), "can0");
+    assert_eq!(other.can_type(), CanType::CanFd);
+    assert_eq!(other.direct(), CanDirect::Receive);
+    assert!(other.is_extended());
+    assert!(other.is_bitrate_switch());
+
+    // the length and data must fit the type.
+    let text = text.replace("can_type: CanFd", "can_type: Can");
+    assert!(serde_yaml::from_str::<CanMessage>(&text).is_err());
+    let mut msg = message("can0", 0x100, &[0x01, 0x02]);
+    msg.set_timestamp_ns(Some(0));
+    let text = serde_yaml::to_string(&msg)?.replace("length: 2", "length: 3");
+    assert!(serde_yaml::from_str::<CanMessage>(&text).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_hash_ord() -> anyhow::Result<(), CanError> {
+    let mut a = message("can0", 0x200, &[0x01, 0x02]);
+    a.set_timestamp(Some(1));
+    let mut b = message("can0", 0x200, &[0x01, 0x02]);
+    b.set_timestamp(Some(2))
+        .set_channel("can1".into());
+    let c = message("can0", 0x100, &[0x03]);
+    let d = message("can0", 0x100, &[0x02]);
+    // the data of remote frame is ignored.
+    let mut remote = CanMessage::new_remote(0x100, 2).unwrap();
+    remote.set_channel("can0".into());
+
+    assert_eq!(a, b);
+    assert_ne!(c, d);
+    let mut fd = message("can0", 0x100, &[0x03]);
+    fd.set_can_type(CanType::CanFd);
+    assert_ne!(fd, c);
+    fd.set_can_type(CanType::Can)
+        .set_bitrate_switch(true);
+    assert_ne!(fd, c);
+    assert_ne!(remote, message("can0", 0x100, &[0x00, 0x00]));
+    assert_ne!(message("can0", 0x100, &[0x00, 0x00]), remote);
+    // the standard and extended remote frames of same identifier are different.
+    let mut extended = CanMessage::new_remote(CanId::from_bits(0x100, Some(true)), 2).unwrap();
+    extended.set_channel("can0".into());
+    assert_ne!(remote, extended);
+    assert_ne!(remote.cmp(&extended), std::cmp::Ordering::Equal);
+    assert_eq!([remote.clone(), extended].into_iter().collect::<HashSet<_>>().len(), 2);
+
+    let set = [a.clone(), b, c.clone(), d.clone()].into_iter().collect::<HashSet<_>>();
+    assert_eq!(set.len(), 3);
+
+    let mut frames = vec![a.clone(), remote.clone(), c.clone(), d.clone()];
+    frames.sort();
+    assert_eq!(frames, vec![d, c, remote, a]);
+
+    Ok(())
+}
+
+#[test]
+fn test_from_frame() {
+    let mut msg = message("can0", 0x7E8, &[0x02, 0x10, 0x03]);
+    msg.set_timestamp(Some(100))
+        .set_direct(CanDirect::Transmit)
+        .set_esi(true);
+
+    let other = CanMessage::from_frame(&msg);
+    assert_eq!(other, msg);
+    assert_eq!(other.timestamp(), 100);
+    assert_eq!(other.channel(), "can0");
+    assert_eq!(other.direct(), CanDirect::Transmit);
+    assert!(other.is_esi());
+}
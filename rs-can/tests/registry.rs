@@ -0,0 +1,65 @@
+use rs_can::{AnyCanDevice, CanError, CanFrame, CanMessage, VirtualCan, registry::{self, DeviceUri}};
+
+#[test]
+fn test_uri() -> anyhow::Result<(), CanError> {
+    let uri = "socketcan://can0,vcan0?bitrate=500000&canfd".parse::<DeviceUri>()?;
+    assert_eq!(uri.backend(), "socketcan");
+    assert_eq!(uri.channels(), &["can0".to_string(), "vcan0".to_string()]);
+    assert_eq!(uri.get::<u32>("bitrate")?, Some(500_000));
+    assert_eq!(uri.get::<bool>("canfd")?, Some(true));
+    assert_eq!(uri.get::<u32>("dbitrate")?, None);
+    assert!(uri.get::<bool>("bitrate").is_err());
+    assert_eq!(uri.to_string(), "socketcan://can0,vcan0?bitrate=500000&canfd=true");
+
+    assert!("can0".parse::<DeviceUri>().is_err());
+    assert!("://can0".parse::<DeviceUri>().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_open() -> anyhow::Result<(), CanError> {
+    let channel = "test-registry-open";
+    assert!(registry::backends().contains(&registry::VIRTUAL.to_string()));
+    let sender = registry::open(&format!("virtual://{}", channel))?;
+    let receiver: Box<dyn AnyCanDevice> = registry::open(&format!("virtual://{}?latency=0", channel))?;
+    assert_eq!(sender.opened_channels(), vec![channel.to_string()]);
+
+    let mut msg = CanMessage::new(0x123, &[0x01, 0x02]).unwrap();
+    msg.set_channel(channel.into());
+    sender.transmit(msg, None)?;
+
+    let frames = receiver.receive(channel, Some(10))?;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].id().into_bits(), 0x123);
+    assert_eq!(frames[0].channel(), channel);
+    assert!(receiver.as_any().downcast_ref::<VirtualCan>().is_some());
+
+    let mut receiver = receiver;
+    receiver.shutdown();
+    assert!(receiver.is_closed());
+
+    assert!(registry::open("unknown://can0").is_err());
+    assert!(registry::open("virtual://test-registry-open?loss=high").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_register() -> anyhow::Result<(), CanError> {
+    fn open_loopback(uri: &DeviceUri) -> Result<Box<dyn AnyCanDevice>, CanError> {
+        let device = VirtualCan::new();
+        for channel in uri.channels() {
+            device.init_channel(channel)?;
+        }
+        Ok(Box::new(device))
+    }
+
+    assert!(registry::register("loopback", open_loopback).is_none());
+    let device = registry::open("loopback://test-registry-loopback")?;
+    assert_eq!(device.opened_channels(), vec!["test-registry-loopback".to_string()]);
+    assert!(registry::unregister("loopback").is_some());
+    assert!(registry::open("loopback://test-registry-loopback").is_err());
+
+    Ok(())
+}
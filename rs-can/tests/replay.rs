@@ -0,0 +1,111 @@
+use std::{sync::Arc, thread, time::{Duration, Instant}};
+use rs_can::{CanDevice, CanDirect, CanError, CanFrame, CanMessage, VirtualCan, replay::Replay};
+
+fn trace() -> Vec<CanMessage> {
+    [(0, 0x100, CanDirect::Transmit), (40, 0x200, CanDirect::Receive), (100, 0x300, CanDirect::Transmit)]
+        .into_iter()
+        .map(|(offset, id, direct)| {
+            let mut msg = CanMessage::new(id, &[0x01, 0x02]).unwrap();
+            msg.set_channel("vehicle".into())
+                .set_timestamp(Some(1_700_000_000_000 + offset))
+                .set_direct(direct);
+            msg
+        })
+        .collect()
+}
+
+fn devices(channel: &str) -> Result<(VirtualCan, VirtualCan), CanError> {
+    let sender = VirtualCan::new();
+    sender.init_channel(channel)?;
+    let receiver = VirtualCan::new();
+    receiver.init_channel(channel)?;
+    Ok((sender, receiver))
+}
+
+fn ids(device: &VirtualCan, channel: &str) -> Result<Vec<u32>, CanError> {
+    Ok(device.receive(channel.into(), Some(10))?
+        .iter()
+        .map(|f| f.id().into_bits())
+        .collect())
+}
+
+#[test]
+fn test_timing() -> anyhow::Result<()> {
+    let channel = "test-replay-timing";
+    let (sender, receiver) = devices(channel)?;
+
+    let mut replay = Replay::new(trace());
+    replay.set_channel("vehicle", channel.into())
+        .set_speed(2.);
+    let start = Instant::now();
+    assert_eq!(replay.run(&sender)?, 3);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(50), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+
+    let frames = receiver.receive(channel.into(), Some(10))?;
+    assert_eq!(frames.iter().map(|f| f.id().into_bits()).collect::<Vec<_>>(), [0x100, 0x200, 0x300]);
+    assert!(frames.iter().all(|f| f.channel() == channel));
+
+    Ok(())
+}
+
+#[test]
+fn test_options() -> anyhow::Result<()> {
+    let channel = "test-replay-options";
+    let (sender, receiver) = devices(channel)?;
+
+    let mut replay = Replay::from_reader(trace().into_iter().map(Ok))?;
+    replay.set_channel("vehicle", channel.into())
+        .set_speed(0.)
+        .set_skip_received(true)
+        .set_loops(Some(2));
+    assert_eq!(replay.run(&sender)?, 4);
+    assert_eq!(ids(&receiver, channel)?, [0x100, 0x300, 0x100, 0x300]);
+
+    replay.set_skip_received(false)
+        .set_loops(Some(1))
+        .set_filter(|id| id.into_bits() != 0x300);
+    assert_eq!(replay.run(&sender)?, 2);
+    assert_eq!(ids(&receiver, channel)?, [0x100, 0x200]);
+
+    // the channel is not opened without remapping.
+    let replay = Replay::new(trace());
+    assert!(replay.run(&sender).is_err());
+
+    let mut replay = Replay::new(trace());
+    replay.set_channel("vehicle", channel.into())
+        .set_loops(None);
+    let replay = Arc::new(replay);
+    let handle = thread::spawn({
+        let replay = replay.clone();
+        let sender = sender.clone();
+        move || replay.run(&sender)
+    });
+    thread::sleep(Duration::from_millis(250));
+    replay.stop();
+    let count = handle.join().unwrap()?;
+    assert!(count >= 6, "{}", count);
+
+    Ok(())
+}
+
+#[test]
+fn test_stop_before_run() -> anyhow::Result<()> {
+    let channel = "test-replay-stop";
+    let (sender, receiver) = devices(channel)?;
+
+    let mut replay = Replay::new(trace());
+    replay.set_channel("vehicle", channel.into())
+        .set_loops(None);
+    // the stop before running is not discarded.
+    replay.stop();
+    assert_eq!(replay.run(&sender)?, 0);
+    assert!(ids(&receiver, channel)?.is_empty());
+
+    // the stop is cleared after returned.
+    replay.set_loops(Some(1));
+    assert_eq!(replay.run(&sender)?, 3);
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+mod utils;
+
+use std::{any::Any, sync::{Arc, Mutex}, thread, time::{Duration, Instant}};
+use rs_can::{CanDevice, CanError, CanFrame, CanId, CanListener, CanMessage, VirtualCan, service::Service};
+use self::utils::message;
+
+#[derive(Default)]
+struct Recorder {
+    transmitting: Mutex<Vec<u32>>,
+    transmitted: Mutex<Vec<u32>>,
+    received: Mutex<Vec<u32>>,
+    failed: Mutex<Vec<u32>>,
+}
+
+struct RecordListener(Arc<Recorder>);
+
+impl CanListener<String, CanMessage> for RecordListener {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn on_frame_transmitting(&self, _: String, frame: &CanMessage) {
+        self.0.transmitting.lock().unwrap().push(frame.id().into_bits());
+    }
+
+    fn on_frame_transmitted(&self, _: String, id: CanId) {
+        self.0.transmitted.lock().unwrap().push(id.into_bits());
+    }
+
+    fn on_frame_received(&self, _: String, frames: &[CanMessage]) {
+        self.0.received.lock().unwrap().extend(frames.iter().map(|f| f.id().into_bits()));
+    }
+
+    fn on_frame_transmit_failed(&self, _: String, id: CanId, _: &CanError) {
+        self.0.failed.lock().unwrap().push(id.into_bits());
+    }
+}
+
+fn wait_for<P: Fn() -> bool>(predicate: P) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline {
+        if predicate() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+#[test]
+fn test_dispatch() -> anyhow::Result<(), CanError> {
+    let channel = "test-service-dispatch";
+    let device = VirtualCan::new();
+    device.init_channel(channel)?;
+    let peer = VirtualCan::new();
+    peer.init_channel(channel)?;
+
+    let service = Service::new(device);
+    let recorder = Arc::new(Recorder::default());
+    assert!(service.add_listener("recorder", Box::new(RecordListener(recorder.clone()))).is_none());
+    assert_eq!(service.listener_names(), vec!["recorder".to_string()]);
+    assert!(service.transmit(message(channel, 0x100, &[0x01, 0x02])).is_err());
+    service.start()?;
+    assert!(service.is_running());
+    assert!(service.start().is_err());
+
+    service.transmit(message(channel, 0x100, &[0x01, 0x02]))?;
+    service.transmit(message(channel, 0x101, &[0x01, 0x02]))?;
+    peer.transmit(message(channel, 0x200, &[0x01, 0x02]), None)?;
+
+    assert!(wait_for(|| recorder.received.lock().unwrap().len() == 1));
+    assert_eq!(*recorder.received.lock().unwrap(), vec![0x200]);
+    assert!(wait_for(|| recorder.transmitted.lock().unwrap().len() == 2));
+    assert_eq!(*recorder.transmitting.lock().unwrap(), vec![0x100, 0x101]);
+    assert_eq!(*recorder.transmitted.lock().unwrap(), vec![0x100, 0x101]);
+    let ids = peer.receive(channel.into(), Some(10))?
+        .iter()
+        .map(|f| f.id().into_bits())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![0x100, 0x101]);
+
+    assert!(service.remove_listener("recorder").is_some());
+    peer.transmit(message(channel, 0x201, &[0x01, 0x02]), None)?;
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(*recorder.received.lock().unwrap(), vec![0x200]);
+
+    service.stop()?;
+    assert!(!service.is_running());
+    assert!(service.transmit(message(channel, 0x102, &[0x01, 0x02])).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_error() -> anyhow::Result<(), CanError> {
+    let channel = "test-service-error";
+    let device = VirtualCan::new();
+    device.init_channel(channel)?;
+
+    let peer = VirtualCan::new();
+    peer.init_channel(channel)?;
+
+    let service = Service::new(device);
+    let recorder = Arc::new(Recorder::default());
+    service.add_listener("recorder", Box::new(RecordListener(recorder.clone())));
+    service.start()?;
+    // the frame to channel not opened is reported, and the others are transmitted.
+    service.transmit(message("test-service-unopened", 0x100, &[0x01, 0x02]))?;
+    service.transmit(message(channel, 0x101, &[0x01, 0x02]))?;
+    assert!(wait_for(|| recorder.transmitted.lock().unwrap().len() == 1));
+    assert_eq!(*recorder.failed.lock().unwrap(), vec![0x100]);
+    assert_eq!(*recorder.transmitted.lock().unwrap(), vec![0x101]);
+    assert!(service.is_running());
+    assert_eq!(peer.receive(channel.into(), Some(10))?.len(), 1);
+
+    service.stop()?;
+    // restarted after stopped.
+    service.start()?;
+    assert!(service.is_running());
+    service.stop()?;
+
+    Ok(())
+}
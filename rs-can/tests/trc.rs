@@ -0,0 +1,149 @@
+use std::io::Cursor;
+use rs_can::{CanDirect, CanFrame, CanId, CanMessage, CanType, trc::{TrcReader, TrcWriter, Version}};
+
+const TRC_V1_1: &str = r#";$FILEVERSION=1.1
+;$STARTTIME=45244.9259259259
+;
+;   Start time: 11/14/2023 22:13:20.000.0
+;---+--   ----+----  --+--  ----+---  +  -+ -- -- -- -- -- -- --
+     1)      1841.0  Rx         0001  8  00 11 22 33 44 55 66 77
+     2)      3678.8  Tx     18EFC8AB  2  01 02
+     3)      5000.0  Rx         0100  4  RTR
+     4)      6000.0  Warng  FFFFFFFF  4  00 00 00 08  BUSHEAVY
+"#;
+
+const TRC_V2_1: &str = r#";$FILEVERSION=2.1
+;$STARTTIME=45244.9259259259
+;$COLUMNS=N,O,T,B,I,d,R,L,D
+;
+;---+-- ------+------ +- +- --+----- +- +- +--- +- -- -- -- -- -- -- --
+      1      1059.900 DT 1      0300 Rx -  8    00 00 00 00 04 00 00 00
+      2      1283.231 FB 2  18DAF110 Tx -  9    00 01 02 03 04 05 06 07 08 09 0A 0B
+      3      1300.000 RR 1      07DF Rx -  3
+      4      1400.000 ER 2         - Rx -  5    04 00 08 00 00
+      5      1500.000 ST 1  Rx 00000008
+"#;
+
+fn frames(start: u64) -> Vec<CanMessage> {
+    (0..20)
+        .map(|i| {
+            let mut msg = match i % 5 {
+                0 => CanMessage::new(0x123, &[i as u8; 8]).unwrap(),
+                1 => {
+                    let mut msg = CanMessage::new(CanId::Extended(0x18DAF110), &[i as u8; 48]).unwrap();
+                    msg.set_bitrate_switch(i % 2 == 0)
+                        .set_esi(i % 3 == 0);
+                    msg
+                },
+                2 => {
+                    let mut msg = CanMessage::new(0x7E8, &[i as u8; 3]).unwrap();
+                    msg.set_can_type(CanType::CanFd);
+                    msg
+                },
+                3 => CanMessage::new_remote(0x456, 2).unwrap(),
+                _ => {
+                    let mut msg = CanMessage::new(0, &[4, 0, 8, 0, 0]).unwrap();
+                    msg.set_error_frame(true);
+                    msg
+                },
+            };
+            msg.set_channel(if i % 2 == 0 { "can0" } else { "can1" }.into())
+                .set_timestamp(Some(start + i as u64 * 10))
+                .set_direct(if i % 3 == 0 { CanDirect::Transmit } else { CanDirect::Receive });
+            msg
+        })
+        .collect()
+}
+
+#[test]
+fn test_reader() -> anyhow::Result<()> {
+    let start = 1_700_000_000_000;
+
+    let mut reader = TrcReader::<_, CanMessage>::new(Cursor::new(TRC_V1_1));
+    let results = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(reader.version(), Version::V1_1);
+    assert_eq!(reader.start(), start);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].timestamp(), start + 1841);
+    assert_eq!(results[0].channel(), "1");
+    assert_eq!(results[0].data(), &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]);
+    assert_eq!(results[1].id(), CanId::Extended(0x18EFC8AB));
+    assert_eq!(results[1].direct(), CanDirect::Transmit);
+    assert_eq!(results[1].timestamp(), start + 3679);
+    assert!(results[2].is_remote());
+    assert_eq!(results[2].length(), 4);
+
+    let mut reader = TrcReader::<_, CanMessage>::new(Cursor::new(TRC_V2_1));
+    reader.set_channel(2, "can1".into());
+    let results = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(reader.version(), Version::V2_1);
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].id(), CanId::Standard(0x300));
+    assert_eq!(results[0].channel(), "1");
+    assert_eq!(results[1].can_type(), CanType::CanFd);
+    assert_eq!(results[1].channel(), "can1");
+    assert_eq!(results[1].length(), 12);
+    assert!(results[1].is_bitrate_switch());
+    assert!(!results[1].is_esi());
+    assert!(results[2].is_remote());
+    assert_eq!(results[2].length(), 3);
+    assert!(results[3].is_error_frame());
+    assert_eq!(results[3].data(), &[4, 0, 8, 0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> anyhow::Result<()> {
+    let start = 1_700_000_000_000;
+    let frames = frames(start);
+
+    for version in [Version::V2_0, Version::V2_1] {
+        let mut writer = TrcWriter::new(Vec::new());
+        writer.set_version(version)
+            .set_start(start);
+        for frame in &frames {
+            writer.write(frame)?;
+        }
+        writer.finish()?;
+        let content = writer.get_ref().clone();
+
+        let mut reader = TrcReader::<_, CanMessage>::new(Cursor::new(content));
+        reader.set_channel(1, "can0".into())
+            .set_channel(2, "can1".into());
+        let results = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(reader.version(), version);
+        assert_eq!(reader.start(), start);
+        assert_eq!(results.len(), frames.len());
+        for (result, frame) in results.iter().zip(&frames) {
+            assert_eq!(result, frame);
+            assert_eq!(result.timestamp(), frame.timestamp());
+            assert_eq!(result.can_type(), frame.can_type());
+            assert_eq!(result.direct(), frame.direct());
+            assert_eq!(result.is_error_frame(), frame.is_error_frame());
+            assert_eq!(result.is_bitrate_switch(), frame.is_bitrate_switch());
+            assert_eq!(result.is_esi(), frame.is_esi());
+            if version == Version::V2_1 {
+                assert_eq!(result.channel(), frame.channel());
+            }
+        }
+    }
+
+    let classic = frames.into_iter()
+        .filter(|f| f.can_type() == CanType::Can)
+        .collect::<Vec<_>>();
+    let mut writer = TrcWriter::new(Vec::new());
+    writer.set_version(Version::V1_1);
+    for frame in &classic {
+        writer.write(frame)?;
+    }
+    let fd = CanMessage::new(0x123, &[0; 12]).unwrap();
+    assert!(writer.write(&fd).is_err());
+    writer.finish()?;
+
+    let results = TrcReader::<_, CanMessage>::new(Cursor::new(writer.get_ref().clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(results, classic);
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use std::{collections::VecDeque, sync::{Arc, Mutex}, thread, time::Duration};
+use rs_can::{CanDevice, CanError, CanFrame, VirtualCan, isotp::{IsoTp, IsoTpConfig}, uds::{Dtc, KeepAlive, ResetType, RoutineControlType, SessionTiming, SessionType, Transport, UdsClient}};
+
+/// A server that responds requests in order.
+#[derive(Default, Clone)]
+struct Server {
+    requests: Arc<Mutex<Vec<Vec<u8>>>>,
+    responses: Arc<Mutex<VecDeque<Vec<u8>>>>,
+}
+
+impl Server {
+    fn respond(&self, responses: &[&[u8]]) {
+        let mut queue = self.responses.lock().unwrap();
+        responses.iter().for_each(|v| queue.push_back(v.to_vec()));
+    }
+
+    fn requests(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+impl Transport for Server {
+    fn send(&mut self, data: &[u8]) -> Result<(), CanError> {
+        self.requests.lock().unwrap().push(data.to_vec());
+        Ok(())
+    }
+
+    fn receive(&mut self, timeout: Option<u32>) -> Result<Vec<u8>, CanError> {
+        match self.responses.lock().unwrap().pop_front() {
+            Some(v) => Ok(v),
+            None => {
+                thread::sleep(Duration::from_millis(timeout.unwrap_or_default() as u64));
+                Err(CanError::TimeoutError("no response".into()))
+            },
+        }
+    }
+}
+
+#[test]
+fn test_services() -> anyhow::Result<()> {
+    let server = Server::default();
+    let mut client = UdsClient::new(server.clone());
+
+    server.respond(&[&[0x50, 0x03, 0x00, 0x32, 0x01, 0xF4]]);
+    assert_eq!(client.session_control(SessionType::Extended)?, SessionTiming { p2: 50, p2_star: 5000 });
+    assert_eq!(server.requests(), [vec![0x10, 0x03]]);
+
+    server.respond(&[&[0x51, 0x01]]);
+    assert_eq!(client.ecu_reset(ResetType::Hard)?, None);
+
+    server.respond(&[&[0x67, 0x01, 0x12, 0x34]]);
+    assert!(client.security_access(0x01).is_err());
+    client.set_security_algorithm(|level, seed| Ok(seed.iter().map(|v| v ^ level).collect()));
+    server.requests();
+    server.respond(&[&[0x67, 0x01, 0x12, 0x34], &[0x67, 0x02]]);
+    client.security_access(0x01)?;
+    assert_eq!(server.requests(), [vec![0x27, 0x01], vec![0x27, 0x02, 0x13, 0x35]]);
+
+    server.respond(&[&[0x62, 0xF1, 0x90, b'V', b'I', b'N']]);
+    assert_eq!(client.read_data_by_identifier(0xF190)?, b"VIN");
+    server.respond(&[&[0x6E, 0xF1, 0x90]]);
+    client.write_data_by_identifier(0xF190, b"VIN")?;
+    assert_eq!(server.requests()[1], [0x2E, 0xF1, 0x90, b'V', b'I', b'N']);
+
+    server.respond(&[&[0x71, 0x01, 0xFF, 0x00, 0x02]]);
+    assert_eq!(client.routine_control(RoutineControlType::Start, 0xFF00, &[0x01])?, [0x02]);
+
+    server.respond(&[&[0x59, 0x01, 0xFF, 0x01, 0x00, 0x02]]);
+    assert_eq!(client.dtc_count_by_status_mask(0x08)?, 2);
+    server.respond(&[&[0x59, 0x02, 0xFF, 0x12, 0x34, 0x56, 0x08, 0xC0, 0x01, 0x00, 0x09]]);
+    assert_eq!(client.dtc_by_status_mask(0x08)?, [Dtc { code: 0x123456, status: 0x08 }, Dtc { code: 0xC00100, status: 0x09 }]);
+
+    server.requests();
+    client.tester_present(true)?;
+    server.respond(&[&[0x7E, 0x00]]);
+    client.tester_present(false)?;
+    assert_eq!(server.requests(), [vec![0x3E, 0x80], vec![0x3E, 0x00]]);
+
+    Ok(())
+}
+
+#[test]
+fn test_negative_response() -> anyhow::Result<()> {
+    let server = Server::default();
+    let mut client = UdsClient::new(server.clone());
+    client.set_timing(SessionTiming { p2: 20, p2_star: 200 });
+
+    // response pending is waited.
+    server.respond(&[&[0x7F, 0x31, 0x78], &[0x7F, 0x31, 0x78], &[0x71, 0x01, 0x02, 0x03]]);
+    assert!(client.routine_control(RoutineControlType::Start, 0x0203, &[])?.is_empty());
+    assert_eq!(client.nrc(), None);
+
+    server.respond(&[&[0x7F, 0x22, 0x31]]);
+    let err = client.read_data_by_identifier(0x1234).unwrap_err();
+    assert!(err.to_string().contains("requestOutOfRange"), "{}", err);
+    assert_eq!(client.nrc(), Some(0x31));
+
+    // the responses of other services are ignored.
+    server.respond(&[&[0x50, 0x01], &[0x62, 0x12, 0x34, 0x00]]);
+    assert_eq!(client.read_data_by_identifier(0x1234)?, [0x00]);
+
+    assert!(matches!(client.ecu_reset(ResetType::Soft), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_download() -> anyhow::Result<()> {
+    let channel = "test-uds-download";
+    let tester = VirtualCan::new();
+    tester.init_channel(channel)?;
+    let ecu = VirtualCan::new();
+    ecu.init_channel(channel)?;
+    let data = (0..1000).map(|v| v as u8).collect::<Vec<_>>();
+
+    let handle = thread::spawn(move || -> Result<Vec<u8>, CanError> {
+        let mut ecu = IsoTp::new(ecu, channel.into(), IsoTpConfig::new(0x7E8, 0x7E0));
+        let mut received = Vec::new();
+        loop {
+            let request = ecu.receive(Some(1000))?;
+            match request[0] {
+                0x34 => {
+                    assert_eq!(request, [0x34, 0x00, 0x44, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x03, 0xE8]);
+                    ecu.send(&[0x74, 0x20, 0x01, 0x02])?;
+                },
+                0x36 => {
+                    assert_eq!(request[1] as usize, received.len() / 256 + 1);
+                    received.extend_from_slice(&request[2..]);
+                    ecu.send(&[0x7F, 0x36, 0x78])?;
+                    ecu.send(&[0x76, request[1]])?;
+                },
+                0x37 => {
+                    ecu.send(&[0x77])?;
+                    return Ok(received);
+                },
+                _ => ecu.send(&[0x7F, request[0], 0x11])?,
+            }
+        }
+    });
+
+    let mut client = UdsClient::new(IsoTp::new(tester.clone(), channel.into(), IsoTpConfig::new(0x7E0, 0x7E8)));
+    client.download(0x00, 0x10000, &data)?;
+    assert_eq!(handle.join().unwrap()?, data);
+
+    Ok(())
+}
+
+#[test]
+fn test_keep_alive() -> anyhow::Result<()> {
+    let server = Server::default();
+    let mut keep_alive = KeepAlive::start(server.clone(), Duration::from_millis(20));
+    thread::sleep(Duration::from_millis(110));
+    keep_alive.stop();
+    let requests = server.requests();
+    assert!(requests.len() >= 3, "{}", requests.len());
+    assert!(requests.iter().all(|v| v == &[0x3E, 0x80]));
+
+    Ok(())
+}
+
+#[test]
+fn test_functional() -> anyhow::Result<()> {
+    let channel = "test-uds-functional";
+    let tester = VirtualCan::new();
+    tester.init_channel(channel)?;
+    let ecu = VirtualCan::new();
+    ecu.init_channel(channel)?;
+
+    // no fallback to physical addressing.
+    let mut transport = IsoTp::new(tester.clone(), channel.into(), IsoTpConfig::new(0x7E0, 0x7E8));
+    assert!(Transport::send_functional(&mut transport, &[0x3E, 0x80]).is_err());
+    assert!(ecu.receive(channel.into(), Some(20))?.is_empty());
+
+    let mut config = IsoTpConfig::new(0x7E0, 0x7E8);
+    config.set_functional_id(0x7DF);
+    let mut transport = IsoTp::new(tester, channel.into(), config);
+    Transport::send_functional(&mut transport, &[0x3E, 0x80])?;
+    let frames = ecu.receive(channel.into(), Some(20))?;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].id().into_bits(), 0x7DF);
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+use rs_can::{CanFrame, CanMessage};
+
+pub fn message(channel: &str, id: u32, data: &[u8]) -> CanMessage {
+    let mut msg = CanMessage::new(id, data).unwrap();
+    msg.set_channel(channel.into());
+    msg
+}
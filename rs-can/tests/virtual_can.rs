@@ -0,0 +1,107 @@
+mod utils;
+
+use std::time::{Duration, Instant};
+use rs_can::{CanDevice, CanDirect, CanError, CanFilter, CanFrame, ChannelConfig, DeviceBuilder, VirtualBus, VirtualCan, virtual_can};
+use self::utils::message;
+
+#[test]
+fn test_route() -> anyhow::Result<(), CanError> {
+    let channel = "test-route";
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(channel, Default::default());
+    let node1 = builder.build::<VirtualCan>()?;
+    let node2 = VirtualCan::new();
+    node2.init_channel(channel)?;
+    let node3 = VirtualCan::new();
+    node3.init_channel(channel)?;
+    assert_eq!(VirtualBus::new(channel).node_count(), 3);
+
+    node1.transmit(message(channel, 0x123, &[0x01, 0x02]), None)?;
+
+    for node in [&node2, &node3] {
+        let frames = node.receive(channel.into(), Some(10))?;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].id().into_bits(), 0x123);
+        assert_eq!(frames[0].data(), &[0x01, 0x02]);
+        assert_eq!(frames[0].direct(), CanDirect::Receive);
+        assert_eq!(frames[0].channel(), channel);
+    }
+    // the sender does not receive its own frame.
+    assert!(node1.receive(channel.into(), Some(10))?.is_empty());
+
+    let mut node3 = node3;
+    node3.shutdown();
+    assert_eq!(VirtualBus::new(channel).node_count(), 2);
+    assert!(matches!(node3.receive(channel.into(), None), Err(CanError::OperationError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_filters() -> anyhow::Result<(), CanError> {
+    let channel = "test-filters";
+    let mut cfg = ChannelConfig::new(500_000);
+    cfg.add_other(virtual_can::FILTERS, Box::new(vec![CanFilter::from((0x7E0, 0x7F0))]));
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(channel, cfg);
+    let receiver = builder.build::<VirtualCan>()?;
+    let sender = VirtualCan::new();
+    sender.init_channel(channel)?;
+
+    sender.transmit(message(channel, 0x123, &[0x01]), None)?;
+    sender.transmit(message(channel, 0x7E8, &[0x02]), None)?;
+
+    let frames = receiver.receive(channel.into(), Some(10))?;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].id().into_bits(), 0x7E8);
+
+    Ok(())
+}
+
+#[test]
+fn test_latency_and_loss() -> anyhow::Result<(), CanError> {
+    let channel = "test-latency";
+    let sender = VirtualCan::new();
+    let bus = sender.init_channel(channel)?;
+    let receiver = VirtualCan::new();
+    receiver.init_channel(channel)?;
+
+    bus.set_latency(Duration::from_millis(50));
+    let start = Instant::now();
+    sender.transmit(message(channel, 0x123, &[0x01]), None)?;
+    assert!(receiver.receive(channel.into(), None)?.is_empty());
+    let frames = receiver.receive(channel.into(), Some(500))?;
+    assert_eq!(frames.len(), 1);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    bus.set_latency(Duration::ZERO)
+        .set_loss(1.);
+    sender.transmit(message(channel, 0x123, &[0x01]), None)?;
+    assert!(receiver.receive(channel.into(), Some(10))?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_bridge() -> anyhow::Result<(), CanError> {
+    let (chl1, chl2) = ("test-bridge1", "test-bridge2");
+    let device = VirtualCan::new();
+    let bus1 = device.init_channel(chl1)?;
+    let bus2 = device.init_channel(chl2)?;
+    let other = VirtualCan::new();
+    other.init_channel(chl2)?;
+
+    bus1.bridge(&bus2);
+    device.transmit(message(chl1, 0x456, &[0x01]), None)?;
+    for dev in [&device, &other] {
+        let frames = dev.receive(chl2.into(), Some(10))?;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].channel(), chl2);
+    }
+
+    bus2.unbridge(&bus1);
+    device.transmit(message(chl1, 0x456, &[0x01]), None)?;
+    assert!(other.receive(chl2.into(), Some(10))?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! The asynchronous SocketCAN device by tokio.
+//!
+//! The sockets are registered to the reactor of tokio by [`AsyncFd`] when they're first used in
+//! runtime, and they're read and written with `MSG_DONTWAIT`, so that the blocking API of the
+//! clones and the options of sockets are not affected.
+
+use std::{collections::HashMap, io, os::fd::{AsRawFd, OwnedFd}, sync::Arc};
+use futures::{future::BoxFuture, FutureExt};
+use rs_can::{AsyncCanDevice, CanDevice, CanError, CanFrame};
+use tokio::{io::unix::AsyncFd, runtime::Handle};
+use crate::{lock, raw_send_frame, read_frames, CanAnyFrame, CanMessage, SocketCan};
+
+pub(crate) type AsyncFds = HashMap<String, Arc<AsyncFd<Arc<OwnedFd>>>>;
+
+impl SocketCan {
+    /// The socket of channel registered to the reactor of current runtime.
+    fn async_fd(&self, channel: &str) -> Result<Arc<AsyncFd<Arc<OwnedFd>>>, CanError> {
+        let socket = self.socket(channel)
+            .ok_or_else(|| CanError::channel_not_opened(channel))?;
+        let mut fds = lock(&self.async_fds);
+        match fds.get(channel) {
+            // the socket is replaced if the channel is reopened.
+            Some(fd) if Arc::ptr_eq(fd.get_ref(), &socket) => Ok(fd.clone()),
+            _ => {
+                Handle::try_current()
+                    .map_err(|e| CanError::OperationError(e.to_string()))?;
+                // SAFETY: the socket is owned by the `Arc` and it's closed only after deregistered.
+                let fd = unsafe { AsyncFd::register(socket) }
+                    .map(Arc::new)
+                    .map_err(|e| CanError::OperationError(format!("{}: {}", channel, e)))?;
+                fds.insert(channel.to_owned(), fd.clone());
+                Ok(fd)
+            },
+        }
+    }
+}
+
+impl AsyncCanDevice for SocketCan {
+    type Channel = String;
+    type Frame = CanMessage;
+
+    #[inline]
+    fn opened_channels(&self) -> Vec<Self::Channel> {
+        CanDevice::opened_channels(self)
+    }
+
+    fn transmit(&self, msg: Self::Frame) -> BoxFuture<'_, Result<(), CanError>> {
+        async move {
+            let fd = self.async_fd(&msg.channel())?;
+            let frame: CanAnyFrame = msg.into();
+            loop {
+                let mut guard = fd.writable().await
+                    .map_err(|e| CanError::OperationError(e.to_string()))?;
+                if let Ok(result) = guard.try_io(|s| raw_send_frame(s.as_raw_fd(), frame.as_bytes())) {
+                    return result.map_err(|e| CanError::OperationError(e.to_string()));
+                }
+            }
+        }
+        .boxed()
+    }
+
+    fn receive(&self, channel: Self::Channel) -> BoxFuture<'_, Result<Vec<Self::Frame>, CanError>> {
+        async move {
+            let fd = self.async_fd(&channel)?;
+            loop {
+                let mut guard = fd.readable().await
+                    .map_err(|e| CanError::OperationError(e.to_string()))?;
+                let result = guard.try_io(|s| match read_frames(s.as_raw_fd(), self.receive_limit, &channel)? {
+                    Ok(frames) if frames.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+                    frames => Ok(frames),
+                });
+                if let Ok(result) = result {
+                    return result.map_err(|e| CanError::OperationError(e.to_string()))?;
+                }
+            }
+        }
+        .boxed()
+    }
+}
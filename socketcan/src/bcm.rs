@@ -0,0 +1,217 @@
+//! SocketCAN broadcast manager(BCM).
+//!
+//! [`BcmSocket`] lets kernel transmit cyclic messages with stable timing, and filter
+//! received messages by content change and reception timeout.
+
+use std::{io, mem, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, raw::c_void}, ptr, slice, time::Duration};
+use libc::{bcm_msg_head, bcm_timeval, can_frame, canfd_frame, read, write, CAN_FD_FRAME, RX_CHANGED, RX_CHECK_DLC, RX_DELETE, RX_FILTER_ID, RX_SETUP, RX_TIMEOUT, SETTIMER, STARTTIMER, TX_COUNTEVT, TX_DELETE, TX_EXPIRED, TX_SEND, TX_SETUP};
+use rs_can::{CanDirect, CanError, CanFrame, CanId, CanType, IdentifierFlags, EFF_MASK};
+use crate::{bcm_open_socket, raw_wait_readable, CanAddr, CanAnyFrame, CanMessage, FD_FRAME_SIZE};
+
+/// The max count of frames of a BCM operation.
+pub const BCM_MAX_FRAMES: usize = 256;
+
+const HEAD_SIZE: usize = mem::size_of::<bcm_msg_head>();
+
+/// The notification of BCM.
+#[derive(Debug, Clone)]
+pub enum BcmEvent {
+    /// The content of filtered message is changed, or it's received first time.
+    Changed(CanMessage),
+    /// The cyclic message is not received in time.
+    Timeout(CanId),
+    /// The cyclic transmission of count is finished.
+    Expired(CanId),
+    /// The other replies with opcode.
+    Other(u32, CanId),
+}
+
+/// BCM socket of a channel, the operations are identified by CAN identifier.
+#[derive(Debug)]
+pub struct BcmSocket {
+    fd: OwnedFd,
+    channel: String,
+}
+
+impl BcmSocket {
+    pub fn open(channel: &str) -> Result<Self, CanError> {
+        let addr = CanAddr::from_iface(channel)
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+        let fd = bcm_open_socket(&addr)
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+
+        Ok(Self { fd: unsafe { OwnedFd::from_raw_fd(fd) }, channel: channel.to_owned() })
+    }
+
+    #[inline]
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Transmit frames cyclically in turn by kernel.
+    ///
+    /// The frames are transmitted `count` times with interval `ival1` and then with interval `ival2`
+    /// forever, an [`BcmEvent::Expired`] is notified when `count` transmissions finished.
+    /// The timing is restarted if the cyclic message of first frame's identifier exists.
+    pub fn tx_setup(&self, frames: &[CanMessage], count: u32, ival1: Duration, ival2: Duration) -> Result<(), CanError> {
+        let mut flags = SETTIMER | STARTTIMER;
+        if count > 0 {
+            flags |= TX_COUNTEVT;
+        }
+        self.write_frames(TX_SETUP, flags, count, ival1, ival2, frames)
+    }
+
+    /// Update the frames of cyclic message without changing its timing,
+    /// the frames are transmitted from next interval.
+    pub fn tx_update(&self, frames: &[CanMessage]) -> Result<(), CanError> {
+        self.write_frames(TX_SETUP, 0, 0, Duration::ZERO, Duration::ZERO, frames)
+    }
+
+    /// Stop and remove the cyclic message of identifier.
+    pub fn tx_delete(&self, id: impl Into<CanId>) -> Result<(), CanError> {
+        self.write_head(TX_DELETE, 0, 0, Duration::ZERO, Duration::ZERO, can_id(id.into()), 0, &[])
+    }
+
+    /// Transmit a frame once.
+    pub fn tx_send(&self, frame: &CanMessage) -> Result<(), CanError> {
+        self.write_frames(TX_SEND, 0, 0, Duration::ZERO, Duration::ZERO, slice::from_ref(frame))
+    }
+
+    /// Filter received frames of identifier.
+    ///
+    /// [`BcmEvent::Changed`] is notified when the bits of `mask` or the length of data changed,
+    /// and it's notified for every frame if `mask` is `None`.
+    /// [`BcmEvent::Timeout`] is notified when no frame received in `timeout`,
+    /// and the notifications of changes are limited to once every `throttle`.
+    pub fn rx_setup(&self, id: impl Into<CanId>, mask: Option<&[u8]>, timeout: Option<Duration>, throttle: Option<Duration>) -> Result<(), CanError> {
+        let id = id.into();
+        let mut flags = 0;
+        if timeout.is_some() || throttle.is_some() {
+            flags |= SETTIMER | STARTTIMER;
+        }
+        let ival1 = timeout.unwrap_or_default();
+        let ival2 = throttle.unwrap_or_default();
+
+        match mask {
+            Some(mask) => {
+                let frame = CanMessage::new(id, mask)
+                    .ok_or_else(|| CanError::OperationError(format!("bcm: invalid mask {:02X?}", mask)))?;
+                self.write_frames(RX_SETUP, flags | RX_CHECK_DLC, 0, ival1, ival2, slice::from_ref(&frame))
+            },
+            None => self.write_head(RX_SETUP, flags | RX_FILTER_ID, 0, ival1, ival2, can_id(id), 0, &[]),
+        }
+    }
+
+    /// Remove the filter of identifier.
+    pub fn rx_delete(&self, id: impl Into<CanId>) -> Result<(), CanError> {
+        self.write_head(RX_DELETE, 0, 0, Duration::ZERO, Duration::ZERO, can_id(id.into()), 0, &[])
+    }
+
+    /// Read a notification, it blocks until `timeout`(forever if `None`).
+    pub fn read_event(&self, timeout: Option<Duration>) -> Result<BcmEvent, CanError> {
+        if let Some(timeout) = timeout {
+            if !raw_wait_readable(self.fd.as_raw_fd(), timeout).map_err(|e| CanError::OperationError(e.to_string()))? {
+                return Err(CanError::channel_timeout(&self.channel));
+            }
+        }
+
+        // aligned buffer of head and a frame.
+        let mut buffer = [0u64; (HEAD_SIZE + FD_FRAME_SIZE) / 8];
+        let size = mem::size_of_val(&buffer);
+        let rd = unsafe { read(self.fd.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, size) };
+        if rd < HEAD_SIZE as isize {
+            return Err(CanError::OperationError(io::Error::last_os_error().to_string()));
+        }
+
+        let head = unsafe { ptr::read(buffer.as_ptr() as *const bcm_msg_head) };
+        let id = CanId::from_bits(head.can_id & EFF_MASK, Some(head.can_id & IdentifierFlags::EXTENDED.bits() != 0));
+        let event = match head.opcode {
+            RX_CHANGED if head.nframes > 0 => {
+                let frame = unsafe {
+                    let ptr = (buffer.as_ptr() as *const u8).add(HEAD_SIZE);
+                    if head.flags & CAN_FD_FRAME != 0 {
+                        CanAnyFrame::from(ptr::read(ptr as *const canfd_frame))
+                    }
+                    else {
+                        CanAnyFrame::from(ptr::read(ptr as *const can_frame))
+                    }
+                };
+                let mut frame = CanMessage::from(frame);
+                frame.set_channel(self.channel.clone())
+                    .set_direct(CanDirect::Receive);
+                BcmEvent::Changed(frame)
+            },
+            RX_TIMEOUT => BcmEvent::Timeout(id),
+            TX_EXPIRED => BcmEvent::Expired(id),
+            opcode => BcmEvent::Other(opcode, id),
+        };
+
+        Ok(event)
+    }
+
+    fn write_frames(&self, opcode: u32, flags: u32, count: u32, ival1: Duration, ival2: Duration, frames: &[CanMessage]) -> Result<(), CanError> {
+        let first = frames.first()
+            .ok_or_else(|| CanError::OperationError("bcm: no frame".into()))?;
+        if frames.len() > BCM_MAX_FRAMES {
+            return Err(CanError::OperationError(format!("bcm: too many frames {}", frames.len())));
+        }
+        let fd = match first.can_type() {
+            CanType::Can => false,
+            CanType::CanFd => true,
+            CanType::CanXl => return Err(CanError::NotSupportedError),
+        };
+        if frames.iter().any(|f| f.can_type() != first.can_type()) {
+            return Err(CanError::OperationError("bcm: frames of different types".into()));
+        }
+
+        let mut data = Vec::new();
+        for frame in frames {
+            let frame: CanAnyFrame = frame.clone().into();
+            data.extend_from_slice(frame.as_bytes());
+        }
+
+        let flags = if fd { flags | CAN_FD_FRAME } else { flags };
+        self.write_head(opcode, flags, count, ival1, ival2, can_id(first.id()), frames.len() as u32, &data)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_head(&self, opcode: u32, flags: u32, count: u32, ival1: Duration, ival2: Duration, can_id: u32, nframes: u32, frames: &[u8]) -> Result<(), CanError> {
+        let mut head: bcm_msg_head = unsafe { mem::zeroed() };
+        head.opcode = opcode;
+        head.flags = flags;
+        head.count = count;
+        head.ival1 = bcm_timeval_new(ival1);
+        head.ival2 = bcm_timeval_new(ival2);
+        head.can_id = can_id;
+        head.nframes = nframes;
+
+        let mut buffer = Vec::with_capacity(HEAD_SIZE + frames.len());
+        buffer.extend_from_slice(unsafe { slice::from_raw_parts(&head as *const _ as *const u8, HEAD_SIZE) });
+        buffer.extend_from_slice(frames);
+
+        let ret = unsafe { write(self.fd.as_raw_fd(), buffer.as_ptr() as *const c_void, buffer.len()) };
+        if ret as usize == buffer.len() {
+            Ok(())
+        }
+        else {
+            Err(CanError::OperationError(io::Error::last_os_error().to_string()))
+        }
+    }
+}
+
+/// The identifier with extended flag of BCM head.
+#[inline]
+pub(crate) fn can_id(id: CanId) -> u32 {
+    match id {
+        CanId::Standard(v) => v as u32,
+        CanId::Extended(v) => v | IdentifierFlags::EXTENDED.bits(),
+    }
+}
+
+#[inline]
+fn bcm_timeval_new(t: Duration) -> bcm_timeval {
+    bcm_timeval {
+        tv_sec: t.as_secs() as _,
+        tv_usec: t.subsec_micros() as _,
+    }
+}
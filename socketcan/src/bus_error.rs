@@ -0,0 +1,249 @@
+//! The bus errors decoded from SocketCAN error frames.
+//!
+//! The error frames are received after [`SocketCan::set_error_filter`](crate::SocketCan::set_error_filter),
+//! the classes of identifier and the details of data are defined by `linux/can/error.h`.
+
+use bitflags::bitflags;
+use libc::{CAN_ERR_ACK, CAN_ERR_BUSERROR, CAN_ERR_BUSOFF, CAN_ERR_CNT, CAN_ERR_CRTL, CAN_ERR_LOSTARB, CAN_ERR_PROT, CAN_ERR_RESTARTED, CAN_ERR_TRX, CAN_ERR_TX_TIMEOUT};
+use rs_can::{CanError, CanFrame};
+use crate::CanMessage;
+
+bitflags! {
+    /// The problems of controller, `CAN_ERR_CRTL_*` of kernel.
+    #[repr(transparent)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ControllerProblems: u8 {
+        const RX_OVERFLOW = 0x01;
+        const TX_OVERFLOW = 0x02;
+        /// The RX error counter reached warning level(96).
+        const RX_WARNING = 0x04;
+        /// The TX error counter reached warning level(96).
+        const TX_WARNING = 0x08;
+        /// The RX error counter reached error passive level(128).
+        const RX_PASSIVE = 0x10;
+        /// The TX error counter reached error passive level(128).
+        const TX_PASSIVE = 0x20;
+        /// Recovered to error active state.
+        const ACTIVE = 0x40;
+    }
+}
+
+bitflags! {
+    /// The types of protocol violation, `CAN_ERR_PROT_*` of kernel.
+    #[repr(transparent)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct ProtocolViolations: u8 {
+        /// Single bit error.
+        const BIT = 0x01;
+        /// Frame format error.
+        const FORM = 0x02;
+        /// Bit stuffing error.
+        const STUFF = 0x04;
+        /// Unable to send dominant bit.
+        const BIT0 = 0x08;
+        /// Unable to send recessive bit.
+        const BIT1 = 0x10;
+        /// Bus overload.
+        const OVERLOAD = 0x20;
+        /// Active error announcement.
+        const ACTIVE = 0x40;
+        /// The error occurred on transmission.
+        const TX = 0x80;
+    }
+}
+
+/// The location of protocol violation in frame, `CAN_ERR_PROT_LOC_*` of kernel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtocolLocation {
+    #[default]
+    Unspecified,
+    StartOfFrame,
+    Id28To21,
+    Id20To18,
+    SubstituteRtr,
+    IdentifierExtension,
+    Id17To13,
+    Id12To05,
+    Id04To00,
+    Rtr,
+    Reserved1,
+    Reserved0,
+    Dlc,
+    Data,
+    CrcSequence,
+    CrcDelimiter,
+    AckSlot,
+    AckDelimiter,
+    EndOfFrame,
+    Intermission,
+    Other(u8),
+}
+
+impl From<u8> for ProtocolLocation {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Unspecified,
+            0x03 => Self::StartOfFrame,
+            0x02 => Self::Id28To21,
+            0x06 => Self::Id20To18,
+            0x04 => Self::SubstituteRtr,
+            0x05 => Self::IdentifierExtension,
+            0x07 => Self::Id17To13,
+            0x0F => Self::Id12To05,
+            0x0E => Self::Id04To00,
+            0x0C => Self::Rtr,
+            0x0D => Self::Reserved1,
+            0x09 => Self::Reserved0,
+            0x0B => Self::Dlc,
+            0x0A => Self::Data,
+            0x08 => Self::CrcSequence,
+            0x18 => Self::CrcDelimiter,
+            0x19 => Self::AckSlot,
+            0x1B => Self::AckDelimiter,
+            0x1A => Self::EndOfFrame,
+            0x12 => Self::Intermission,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// The status of transceiver, `CAN_ERR_TRX_*` of kernel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransceiverStatus {
+    #[default]
+    Unspecified,
+    CanHNoWire,
+    CanHShortToBat,
+    CanHShortToVcc,
+    CanHShortToGnd,
+    CanLNoWire,
+    CanLShortToBat,
+    CanLShortToVcc,
+    CanLShortToGnd,
+    CanLShortToCanH,
+    Other(u8),
+}
+
+impl From<u8> for TransceiverStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Unspecified,
+            0x04 => Self::CanHNoWire,
+            0x05 => Self::CanHShortToBat,
+            0x06 => Self::CanHShortToVcc,
+            0x07 => Self::CanHShortToGnd,
+            0x40 => Self::CanLNoWire,
+            0x50 => Self::CanLShortToBat,
+            0x60 => Self::CanLShortToVcc,
+            0x70 => Self::CanLShortToGnd,
+            0x80 => Self::CanLShortToCanH,
+            v => Self::Other(v),
+        }
+    }
+}
+
+/// A class of bus error reported by error frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanBusError {
+    /// TX timeout by driver.
+    TxTimeout,
+    /// Arbitration lost at the bit position, it's `None` if unspecified.
+    LostArbitration(Option<u8>),
+    Controller(ControllerProblems),
+    Protocol { violations: ProtocolViolations, location: ProtocolLocation },
+    Transceiver(TransceiverStatus),
+    /// No ACK received on transmission.
+    NoAck,
+    BusOff,
+    /// Bus error, it may flood the bus.
+    BusError,
+    /// Controller restarted after bus off.
+    Restarted,
+}
+
+/// The TX and RX error counters of controller.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCounters {
+    pub tx: u8,
+    pub rx: u8,
+}
+
+/// The bus errors of an error frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanErrorEvent {
+    /// The timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub channel: String,
+    /// The bus errors in order of classes, an error frame may report several.
+    pub errors: Vec<CanBusError>,
+    /// The error counters, it's `None` if the driver doesn't report them.
+    pub counters: Option<ErrorCounters>,
+}
+
+impl CanErrorEvent {
+    #[inline]
+    pub fn is_bus_off(&self) -> bool {
+        self.errors.contains(&CanBusError::BusOff)
+    }
+}
+
+impl TryFrom<&CanMessage> for CanErrorEvent {
+    type Error = CanError;
+
+    fn try_from(msg: &CanMessage) -> Result<Self, Self::Error> {
+        if !msg.is_error_frame() {
+            return Err(CanError::OperationError(format!("not an error frame: {:08X}", msg.arbitration_id)));
+        }
+
+        let class = msg.arbitration_id;
+        let byte = |i: usize| msg.data.get(i).copied().unwrap_or_default();
+        let mut errors = Vec::new();
+        if class & CAN_ERR_TX_TIMEOUT != 0 {
+            errors.push(CanBusError::TxTimeout);
+        }
+        if class & CAN_ERR_LOSTARB != 0 {
+            errors.push(CanBusError::LostArbitration(Some(byte(0)).filter(|&v| v != 0)));
+        }
+        if class & CAN_ERR_CRTL != 0 {
+            errors.push(CanBusError::Controller(ControllerProblems::from_bits_retain(byte(1))));
+        }
+        if class & CAN_ERR_PROT != 0 {
+            errors.push(CanBusError::Protocol {
+                violations: ProtocolViolations::from_bits_retain(byte(2)),
+                location: byte(3).into(),
+            });
+        }
+        if class & CAN_ERR_TRX != 0 {
+            errors.push(CanBusError::Transceiver(byte(4).into()));
+        }
+        if class & CAN_ERR_ACK != 0 {
+            errors.push(CanBusError::NoAck);
+        }
+        if class & CAN_ERR_BUSOFF != 0 {
+            errors.push(CanBusError::BusOff);
+        }
+        if class & CAN_ERR_BUSERROR != 0 {
+            errors.push(CanBusError::BusError);
+        }
+        if class & CAN_ERR_RESTARTED != 0 {
+            errors.push(CanBusError::Restarted);
+        }
+        let counters = if class & CAN_ERR_CNT != 0 {
+            Some(ErrorCounters { tx: byte(6), rx: byte(7) })
+        }
+        else {
+            None
+        };
+
+        Ok(Self { timestamp: msg.timestamp, channel: msg.channel.clone(), errors, counters })
+    }
+}
+
+impl TryFrom<CanMessage> for CanErrorEvent {
+    type Error = CanError;
+
+    #[inline]
+    fn try_from(msg: CanMessage) -> Result<Self, Self::Error> {
+        Self::try_from(&msg)
+    }
+}
@@ -0,0 +1,227 @@
+//! candump(`candump -l`) log file of [can-utils](https://github.com/linux-can/can-utils).
+//!
+//! Line format: `(1700000000.123456) can0 123#DEADBEEF`,
+//! the CAN-FD frame is formatted as `123##<flags>DATA` and remote frame as `123#R[len]`.
+
+use std::{fs::File, io::{BufRead, BufReader, BufWriter, Write}, path::Path};
+use libc::CAN_ERR_FLAG;
+use rs_can::{CanDirect, CanError, CanFrame, CanType, EFF_MASK, ERR_MASK, SFF_MASK};
+use crate::CanMessage;
+
+const CANFD_BRS: u8 = 0x01;
+const CANFD_ESI: u8 = 0x02;
+
+#[inline(always)]
+fn parse_error(line: &str) -> CanError {
+    CanError::OtherError(format!("invalid candump line: `{}`", line))
+}
+
+/// Parse data like `DEADBEEF` or `DE.AD.BE.EF`.
+fn parse_data(s: &str) -> Option<Vec<u8>> {
+    let s = s.replace('.', "");
+    if s.len() % 2 == 1 {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl CanMessage {
+    /// Parse a line of candump log, the direction is `Receive` unless it's marked by `T`.
+    pub fn from_candump(line: &str) -> Result<Self, CanError> {
+        let mut tokens = line.split_whitespace();
+        let timestamp = tokens.next()
+            .and_then(|v| v.strip_prefix('('))
+            .and_then(|v| v.strip_suffix(')'))
+            .ok_or_else(|| parse_error(line))?;
+        let (sec, usec) = timestamp.split_once('.')
+            .ok_or_else(|| parse_error(line))?;
+        let sec = sec.parse::<u64>().map_err(|_| parse_error(line))?;
+        let usec = format!("{:0<6}", usec).get(..6)
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| parse_error(line))?;
+        let channel = tokens.next()
+            .ok_or_else(|| parse_error(line))?;
+        let frame = tokens.next()
+            .ok_or_else(|| parse_error(line))?;
+        let direct = match tokens.next() {
+            Some("T") => CanDirect::Transmit,
+            _ => CanDirect::Receive,
+        };
+
+        let (id, rest) = frame.split_once('#')
+            .ok_or_else(|| parse_error(line))?;
+        let extended = id.len() > 3;
+        let can_id = u32::from_str_radix(id, 16)
+            .map_err(|_| parse_error(line))?;
+
+        let mut msg = if let Some(rest) = rest.strip_prefix('#') {
+            let flags = rest.get(..1)
+                .and_then(|v| u8::from_str_radix(v, 16).ok())
+                .ok_or_else(|| parse_error(line))?;
+            let data = parse_data(&rest[1..])
+                .ok_or_else(|| parse_error(line))?;
+            let mut msg = Self::new(can_id & EFF_MASK, &data)
+                .ok_or_else(|| parse_error(line))?;
+            msg.set_can_type(CanType::CanFd)
+                .set_bitrate_switch(flags & CANFD_BRS != 0)
+                .set_esi(flags & CANFD_ESI != 0);
+            msg
+        }
+        else if let Some(len) = rest.strip_prefix(['R', 'r']) {
+            let len = match len {
+                "" => 0,
+                v => v.parse::<usize>().map_err(|_| parse_error(line))?,
+            };
+            Self::new_remote(can_id & EFF_MASK, len)
+                .ok_or_else(|| parse_error(line))?
+        }
+        else {
+            // ignore the raw DLC of classic frame, like `123#11223344_9`.
+            let data = rest.split('_').next().unwrap_or_default();
+            let data = parse_data(data)
+                .ok_or_else(|| parse_error(line))?;
+            let mut msg = Self::new(can_id & EFF_MASK, &data)
+                .ok_or_else(|| parse_error(line))?;
+            if can_id & CAN_ERR_FLAG != 0 {
+                msg.arbitration_id = can_id & ERR_MASK;
+                msg.set_error_frame(true);
+            }
+            msg
+        };
+
+        msg.is_extended_id = extended && !msg.is_error_frame;
+        msg.set_timestamp_ns(Some(sec * 1_000_000_000 + usec * 1000))
+            .set_channel(channel.to_owned())
+            .set_direct(direct);
+
+        Ok(msg)
+    }
+
+    /// Format as a line of candump log without line ending.
+    pub fn to_candump(&self) -> String {
+        let id = if self.is_error_frame {
+            format!("{:08X}", (self.arbitration_id & ERR_MASK) | CAN_ERR_FLAG)
+        }
+        else if self.is_extended_id {
+            format!("{:08X}", self.arbitration_id & EFF_MASK)
+        }
+        else {
+            format!("{:03X}", self.arbitration_id & SFF_MASK)
+        };
+        let data = self.data.iter()
+            .take(self.length)
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>();
+
+        let frame = match self.can_type {
+            CanType::CanFd => {
+                let mut flags = 0;
+                if self.bitrate_switch {
+                    flags |= CANFD_BRS;
+                }
+                if self.error_state_indicator {
+                    flags |= CANFD_ESI;
+                }
+                format!("{}##{:X}{}", id, flags, data)
+            },
+            _ => if self.is_remote_frame {
+                match self.length {
+                    0 => format!("{}#R", id),
+                    len => format!("{}#R{}", id, len),
+                }
+            }
+            else {
+                format!("{}#{}", id, data)
+            },
+        };
+
+        format!("({}.{:06}) {} {}", self.timestamp / 1_000_000_000, self.timestamp % 1_000_000_000 / 1000, self.channel, frame)
+    }
+}
+
+/// candump log reader, iterating frames that parsed from file.
+///
+/// Empty lines and comments starting with `#` are skipped.
+pub struct CandumpReader<R: BufRead> {
+    reader: R,
+}
+
+impl CandumpReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CanError> {
+        let file = File::open(path)
+            .map_err(|e| CanError::OperationError(e.to_string()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> CandumpReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: BufRead> Iterator for CandumpReader<R> {
+    type Item = Result<CanMessage, CanError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(e) => return Some(Err(CanError::OperationError(e.to_string()))),
+            }
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            return Some(CanMessage::from_candump(line));
+        }
+    }
+}
+
+/// candump log writer, the output could be replayed by `canplayer`.
+pub struct CandumpWriter<W: Write> {
+    writer: W,
+}
+
+impl CandumpWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, CanError> {
+        let file = File::create(path)
+            .map_err(|e| CanError::OperationError(e.to_string()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> CandumpWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, msg: &CanMessage) -> Result<(), CanError> {
+        writeln!(self.writer, "{}", msg.to_candump())
+            .map_err(|e| CanError::OperationError(e.to_string()))
+    }
+
+    pub fn flush(&mut self) -> Result<(), CanError> {
+        self.writer.flush()
+            .map_err(|e| CanError::OperationError(e.to_string()))
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
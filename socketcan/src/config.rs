@@ -0,0 +1,144 @@
+//! The typed configuration of [`SocketCan`], it's converted to the options of [`DeviceBuilder`].
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use rs_can::{AnyCanDevice, CanError, CanFilter, ChannelConfig, DeviceBuilder, DeviceConfig, registry::{self, DeviceUri}};
+use crate::{CanCtrlModes, SocketCan, TimestampSource, CANFD, CANXL, CREATE_VCAN, CTRL_MODES, DATA_SAMPLE_POINT, FILTERS, LOOPBACK, RECEIVE_LIMIT, RECV_OWN_MSG, RESTART_MS, SAMPLE_POINT, TIMESTAMP_SOURCE};
+
+/// The name of [`SocketCan`] backend in [`registry`].
+pub const SOCKETCAN: &str = "socketcan";
+
+/// The typed configuration of a SocketCAN interface.
+///
+/// The controller is configured by rtnetlink only when `bitrate` is not 0, see [`SocketCan`].
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SocketCanChannelConfig {
+    pub bitrate: u32,
+    pub dbitrate: Option<u32>,
+    pub resistance: Option<bool>,
+    /// The sample point in range `0.0..1.0`, it's chosen by kernel if `None`.
+    pub sample_point: Option<f32>,
+    pub data_sample_point: Option<f32>,
+    pub restart_ms: Option<u32>,
+    /// The control modes set exactly, only FD is enabled for CAN-FD and the others are kept if `None`.
+    pub ctrl_modes: Option<CanCtrlModes>,
+    /// Create the vcan interface if not existed.
+    pub create_vcan: Option<bool>,
+    pub canfd: Option<bool>,
+    pub canxl: Option<bool>,
+    pub timestamp_source: Option<TimestampSource>,
+    pub filters: Option<Vec<CanFilter>>,
+    pub loopback: Option<bool>,
+    pub recv_own_msg: Option<bool>,
+}
+
+impl From<&SocketCanChannelConfig> for ChannelConfig {
+    fn from(cfg: &SocketCanChannelConfig) -> Self {
+        let mut config = ChannelConfig::new(cfg.bitrate);
+        if let Some(dbitrate) = cfg.dbitrate {
+            config.set_data_bitrate(dbitrate);
+        }
+        if let Some(resistance) = cfg.resistance {
+            config.set_resistance(resistance);
+        }
+        if let Some(v) = cfg.sample_point {
+            config.add_other(SAMPLE_POINT, Box::new(v));
+        }
+        if let Some(v) = cfg.data_sample_point {
+            config.add_other(DATA_SAMPLE_POINT, Box::new(v));
+        }
+        if let Some(v) = cfg.restart_ms {
+            config.add_other(RESTART_MS, Box::new(v));
+        }
+        if let Some(v) = cfg.ctrl_modes {
+            config.add_other(CTRL_MODES, Box::new(v));
+        }
+        if let Some(v) = cfg.create_vcan {
+            config.add_other(CREATE_VCAN, Box::new(v));
+        }
+        if let Some(v) = cfg.canfd {
+            config.add_other(CANFD, Box::new(v));
+        }
+        if let Some(v) = cfg.canxl {
+            config.add_other(CANXL, Box::new(v));
+        }
+        if let Some(v) = cfg.timestamp_source {
+            config.add_other(TIMESTAMP_SOURCE, Box::new(v));
+        }
+        if let Some(v) = &cfg.filters {
+            config.add_other(FILTERS, Box::new(v.clone()));
+        }
+        if let Some(v) = cfg.loopback {
+            config.add_other(LOOPBACK, Box::new(v));
+        }
+        if let Some(v) = cfg.recv_own_msg {
+            config.add_other(RECV_OWN_MSG, Box::new(v));
+        }
+
+        config
+    }
+}
+
+/// The typed configuration of [`SocketCan`], the channels are keyed by interface name.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SocketCanConfig {
+    pub receive_limit: Option<usize>,
+    pub channels: HashMap<String, SocketCanChannelConfig>,
+}
+
+impl DeviceConfig for SocketCanConfig {
+    type Device = SocketCan;
+
+    fn builder(&self) -> DeviceBuilder {
+        let mut builder = DeviceBuilder::new();
+        if let Some(limit) = self.receive_limit {
+            builder.add_other(RECEIVE_LIMIT, Box::new(limit));
+        }
+        for (chl, cfg) in &self.channels {
+            builder.add_config(chl.as_str(), cfg.into());
+        }
+
+        builder
+    }
+}
+
+impl SocketCanConfig {
+    /// The configuration of device URI, like `socketcan://can0,can1?bitrate=500000&canfd`.
+    ///
+    /// The channels share the parameters, which are named as the fields except `ctrl_modes`,
+    /// `timestamp_source` and `filters`.
+    pub fn from_uri(uri: &DeviceUri) -> Result<Self, CanError> {
+        let channel = SocketCanChannelConfig {
+            bitrate: uri.get("bitrate")?.unwrap_or_default(),
+            dbitrate: uri.get("dbitrate")?,
+            resistance: uri.get("resistance")?,
+            sample_point: uri.get("sample_point")?,
+            data_sample_point: uri.get("data_sample_point")?,
+            restart_ms: uri.get("restart_ms")?,
+            create_vcan: uri.get("create_vcan")?,
+            canfd: uri.get("canfd")?,
+            canxl: uri.get("canxl")?,
+            loopback: uri.get("loopback")?,
+            recv_own_msg: uri.get("recv_own_msg")?,
+            ..Default::default()
+        };
+
+        Ok(Self {
+            receive_limit: uri.get("receive_limit")?,
+            channels: uri.channels().iter()
+                .map(|c| (c.clone(), channel.clone()))
+                .collect(),
+        })
+    }
+}
+
+/// Register [`SocketCan`] to [`registry`] as [`SOCKETCAN`].
+pub fn register() {
+    registry::register(SOCKETCAN, open);
+}
+
+fn open(uri: &DeviceUri) -> Result<Box<dyn AnyCanDevice>, CanError> {
+    Ok(Box::new(SocketCanConfig::from_uri(uri)?.build()?))
+}
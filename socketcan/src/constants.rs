@@ -1,4 +1,12 @@
 pub const CANFD: &'static str = "canfd";
+pub const CANXL: &'static str = "canxl";
 pub const FILTERS: &'static str = "filters";
 pub const LOOPBACK: &'static str = "loopback";
 pub const RECV_OWN_MSG: &'static str = "recv-own-msg";
+pub const TIMESTAMP_SOURCE: &'static str = "timestamp-source";
+pub const RECEIVE_LIMIT: &'static str = "receive-limit";
+pub const CREATE_VCAN: &'static str = "create-vcan";
+pub const SAMPLE_POINT: &'static str = "sample-point";
+pub const DATA_SAMPLE_POINT: &'static str = "data-sample-point";
+pub const RESTART_MS: &'static str = "restart-ms";
+pub const CTRL_MODES: &'static str = "ctrl-modes";
@@ -1,7 +1,7 @@
 use std::fmt::{Display, Formatter};
-use libc::{can_frame, canfd_frame, canxl_frame};
-use rs_can::{CanDirect, IdentifierFlags, EFF_MASK, can_utils, CanFrame, CanId, MAX_FRAME_SIZE, CanType, MAX_FD_FRAME_SIZE, MAX_XL_FRAME_SIZE};
-use crate::{socket, FD_FRAME_SIZE, FRAME_SIZE, XL_FRAME_SIZE};
+use libc::{can_frame, canfd_frame, canxl_frame, CANXL_HDR_SIZE, CANXL_PRIO_MASK, CANXL_SEC, CANXL_XLF};
+use rs_can::{CanDirect, CanError, IdentifierFlags, EFF_MASK, can_utils, CanFrame, CanId, MAX_FRAME_SIZE, CanType, CanXlControl, MAX_FD_FRAME_SIZE, MAX_XL_FRAME_SIZE};
+use crate::{socket, CANXL_VCID_OFFSET, FD_FRAME_SIZE, FRAME_SIZE};
 
 pub enum CanAnyFrame {
     Normal(can_frame),
@@ -18,9 +18,22 @@ impl CanAnyFrame {
             CanAnyFrame::Remote(_) => FRAME_SIZE,
             CanAnyFrame::Error(_) => FRAME_SIZE,
             CanAnyFrame::Fd(_) => FD_FRAME_SIZE,
-            CanAnyFrame::Xl(_) => XL_FRAME_SIZE,
+            // the CAN XL frame is transferred with actual data length.
+            CanAnyFrame::Xl(f) => CANXL_HDR_SIZE + f.len as usize,
         }
     }
+
+    /// The bytes of frame written to socket.
+    pub fn as_bytes(&self) -> &[u8] {
+        let ptr = match self {
+            CanAnyFrame::Normal(f) |
+            CanAnyFrame::Remote(f) |
+            CanAnyFrame::Error(f) => f as *const _ as *const u8,
+            CanAnyFrame::Fd(f) => f as *const _ as *const u8,
+            CanAnyFrame::Xl(f) => f as *const _ as *const u8,
+        };
+        unsafe { std::slice::from_raw_parts(ptr, self.size()) }
+    }
 }
 
 impl From<can_frame> for CanAnyFrame {
@@ -54,6 +67,7 @@ impl From<canxl_frame> for CanAnyFrame {
 
 #[derive(Debug, Clone)]
 pub struct CanMessage {
+    /// The timestamp in nanoseconds.
     pub(crate) timestamp: u64,
     pub(crate) arbitration_id: u32,
     pub(crate) is_extended_id: bool,
@@ -66,11 +80,12 @@ pub struct CanMessage {
     pub(crate) direct: CanDirect,
     pub(crate) bitrate_switch: bool,
     pub(crate) error_state_indicator: bool,
+    pub(crate) xl_control: CanXlControl,
 }
 
 impl From<CanAnyFrame> for CanMessage {
     fn from(frame: CanAnyFrame) -> Self {
-        let timestamp = can_utils::system_timestamp();
+        let timestamp = can_utils::system_timestamp_ns();
         match frame {
             CanAnyFrame::Normal(f) => Self {
                 timestamp,
@@ -85,6 +100,7 @@ impl From<CanAnyFrame> for CanMessage {
                 direct: Default::default(),
                 bitrate_switch: false,
                 error_state_indicator: false,
+                xl_control: Default::default(),
             },
             CanAnyFrame::Remote(f) => Self {
                 timestamp,
@@ -99,6 +115,7 @@ impl From<CanAnyFrame> for CanMessage {
                 direct: Default::default(),
                 bitrate_switch: false,
                 error_state_indicator: false,
+                xl_control: Default::default(),
             },
             CanAnyFrame::Error(f) => Self {
                 timestamp,
@@ -113,6 +130,7 @@ impl From<CanAnyFrame> for CanMessage {
                 direct: Default::default(),
                 bitrate_switch: false,
                 error_state_indicator: false,
+                xl_control: Default::default(),
             },
             CanAnyFrame::Fd(f) => Self {
                 timestamp,
@@ -127,12 +145,48 @@ impl From<CanAnyFrame> for CanMessage {
                 direct: Default::default(),
                 bitrate_switch: f.flags & 0x01 != 0,
                 error_state_indicator: f.flags & 0x02 != 0,
+                xl_control: Default::default(),
+            },
+            CanAnyFrame::Xl(f) => Self {
+                timestamp,
+                arbitration_id: f.prio & CANXL_PRIO_MASK,
+                is_extended_id: false,
+                is_remote_frame: false,
+                is_error_frame: false,
+                channel: Default::default(),
+                length: f.len as usize,
+                data: f.data[..f.len as usize].to_vec(),
+                can_type: CanType::CanXl,
+                direct: Default::default(),
+                bitrate_switch: false,
+                error_state_indicator: false,
+                xl_control: CanXlControl {
+                    sdu_type: f.sdt,
+                    vcid: (f.prio >> CANXL_VCID_OFFSET) as u8,
+                    acceptance_field: f.af,
+                    sec: f.flags & CANXL_SEC as u8 != 0,
+                },
             },
-            CanAnyFrame::Xl(_) => todo!(),
         }
     }
 }
 
+impl CanMessage {
+    /// The timestamp in nanoseconds, it's the kernel timestamp if enabled by
+    /// [`crate::SocketCan::set_timestamp_source`], otherwise the system time when the frame is read.
+    #[inline]
+    pub fn timestamp_ns(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Set timestamp in nanoseconds, the system time is used if `None`.
+    #[inline]
+    pub fn set_timestamp_ns(&mut self, value: Option<u64>) -> &mut Self {
+        self.timestamp = value.unwrap_or_else(can_utils::system_timestamp_ns);
+        self
+    }
+}
+
 impl Into<CanAnyFrame> for CanMessage {
     fn into(self) -> CanAnyFrame {
         match self.can_type {
@@ -185,7 +239,25 @@ impl Into<CanAnyFrame> for CanMessage {
 
                 CanAnyFrame::Fd(frame)
             },
-            CanType::CanXl => todo!(),
+            CanType::CanXl => {
+                let mut frame = socket::canxl_frame_default();
+                if self.is_extended_id {
+                    log::warn!("RUST-CAN - CAN XL priority identifier is 11 bits: 0x{:X}", self.arbitration_id);
+                }
+
+                let length = self.data.len();
+                frame.prio = (self.arbitration_id & CANXL_PRIO_MASK) | (self.xl_control.vcid as u32) << CANXL_VCID_OFFSET;
+                frame.flags = CANXL_XLF as u8;
+                if self.xl_control.sec {
+                    frame.flags |= CANXL_SEC as u8;
+                }
+                frame.sdt = self.xl_control.sdu_type;
+                frame.len = length as u16;
+                frame.af = self.xl_control.acceptance_field;
+                frame.data[..length].copy_from_slice(&self.data);
+
+                CanAnyFrame::Xl(frame)
+            },
         }
     }
 }
@@ -212,6 +284,7 @@ impl CanFrame for CanMessage {
                     direct: Default::default(),
                     bitrate_switch: false,
                     error_state_indicator: false,
+                    xl_control: Default::default(),
                 })
             },
             Err(_) => None,
@@ -237,6 +310,7 @@ impl CanFrame for CanMessage {
                     direct: Default::default(),
                     bitrate_switch: false,
                     error_state_indicator: false,
+                    xl_control: Default::default(),
                 })
             },
             Err(_) => None,
@@ -245,12 +319,12 @@ impl CanFrame for CanMessage {
 
     #[inline]
     fn timestamp(&self) -> u64 {
-        self.timestamp
+        self.timestamp / 1_000_000
     }
 
     #[inline]
     fn set_timestamp(&mut self, value: Option<u64>) -> &mut Self {
-        self.timestamp = value.unwrap_or_else(can_utils::system_timestamp);
+        self.timestamp = value.unwrap_or_else(can_utils::system_timestamp) * 1_000_000;
         self
     }
 
@@ -280,6 +354,7 @@ impl CanFrame for CanMessage {
             },
         }
 
+        self.data.truncate(self.length);
         self.can_type = r#type;
         self
     }
@@ -338,6 +413,17 @@ impl CanFrame for CanMessage {
         self
     }
 
+    #[inline]
+    fn xl_control(&self) -> CanXlControl {
+        self.xl_control
+    }
+
+    #[inline]
+    fn set_xl_control(&mut self, value: CanXlControl) -> &mut Self {
+        self.xl_control = value;
+        self
+    }
+
     #[inline]
     fn channel(&self) -> Self::Channel {
         self.channel.clone()
@@ -374,6 +460,7 @@ impl PartialEq for CanMessage {
                 (self.is_extended_id == other.is_extended_id) &&
                 (self.is_error_frame == other.is_error_frame) &&
                 (self.error_state_indicator == other.error_state_indicator) &&
+                (self.xl_control == other.xl_control) &&
                 (self.data == other.data)
         }
     }
@@ -384,3 +471,43 @@ impl Display for CanMessage {
         <dyn CanFrame<Channel=String> as Display>::fmt(self, f)
     }
 }
+
+impl From<CanMessage> for rs_can::CanMessage {
+    fn from(msg: CanMessage) -> Self {
+        let mut frame = Self::from_frame(&msg);
+        frame.set_timestamp_ns(Some(msg.timestamp));
+        frame
+    }
+}
+
+/// The data must fit the type of frame.
+impl TryFrom<rs_can::CanMessage> for CanMessage {
+    type Error = CanError;
+
+    fn try_from(msg: rs_can::CanMessage) -> Result<Self, Self::Error> {
+        let max = match msg.can_type() {
+            CanType::Can => MAX_FRAME_SIZE,
+            CanType::CanFd => MAX_FD_FRAME_SIZE,
+            CanType::CanXl => MAX_XL_FRAME_SIZE,
+        };
+        if msg.data().len() > max {
+            return Err(CanError::OperationError(format!("data length {} is out of range of {:?}", msg.data().len(), msg.can_type())));
+        }
+
+        Ok(Self {
+            timestamp: msg.timestamp_ns(),
+            arbitration_id: msg.id().into_bits(),
+            is_extended_id: msg.is_extended(),
+            is_remote_frame: msg.is_remote(),
+            is_error_frame: msg.is_error_frame(),
+            channel: msg.channel(),
+            length: msg.length(),
+            data: msg.data().to_vec(),
+            can_type: msg.can_type(),
+            direct: msg.direct(),
+            bitrate_switch: msg.is_bitrate_switch(),
+            error_state_indicator: msg.is_esi(),
+            xl_control: msg.xl_control(),
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! SocketCAN ISO-TP(ISO 15765-2) transport.
+//!
+//! [`IsoTpSocket`] lets kernel segment, reassemble and flow control the messages, so that
+//! it keeps the timing of STmin and flow control even if the calling thread is busy.
+//! The `can-isotp` module is required, it's in mainline since linux 5.10.
+
+use std::{io, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, raw::{c_int, c_void}}, time::Duration};
+use libc::{read, write, CANFD_BRS, CANFD_MTU};
+use rs_can::{CanError, CanId, isotp::IsoTpConfig, uds::Transport};
+use crate::bcm::can_id;
+use crate::{isotp_open_socket, raw_wait_readable, set_socket_option, CanAddr, CanIsoTpFcOptions, CanIsoTpLlOptions, CanIsoTpOptions, CAN_ISOTP_LISTEN_MODE, CAN_ISOTP_LL_OPTS, CAN_ISOTP_OPTS, CAN_ISOTP_RECV_FC, CAN_ISOTP_SF_BROADCAST, CAN_ISOTP_TX_PADDING, CAN_ISOTP_WAIT_TX_DONE, SOL_CAN_ISOTP};
+
+/// The size of receive buffer, the longer PDU is truncated.
+pub const ISOTP_BUFFER_SIZE: usize = 1 << 16;
+
+/// ISO-TP socket of a channel, every read and write is a whole PDU.
+#[derive(Debug)]
+pub struct IsoTpSocket {
+    fd: OwnedFd,
+    functional: Option<OwnedFd>,
+    channel: String,
+}
+
+impl IsoTpSocket {
+    /// Open socket with the identifiers, padding, flow control and CAN-FD options of `config`.
+    ///
+    /// The N_As, N_Bs and N_Cr timeouts are kept by kernel, and single frames are
+    /// transmitted to functional address by [`IsoTpSocket::send_functional`] if it's set.
+    pub fn open(channel: &str, config: &IsoTpConfig) -> Result<Self, CanError> {
+        let fd = open_socket(channel, config.rx_id(), config.tx_id(), config, CAN_ISOTP_WAIT_TX_DONE)?;
+        let functional = match config.functional_id() {
+            Some(id) => Some(open_socket(channel, config.rx_id(), id, config, CAN_ISOTP_WAIT_TX_DONE | CAN_ISOTP_SF_BROADCAST)?),
+            None => None,
+        };
+
+        Ok(Self { fd, functional, channel: channel.to_owned() })
+    }
+
+    /// Open socket in listen mode, it receives PDUs of `rx_id` without transmitting flow control frames.
+    pub fn listen(channel: &str, config: &IsoTpConfig) -> Result<Self, CanError> {
+        let fd = open_socket(channel, config.rx_id(), config.tx_id(), config, CAN_ISOTP_LISTEN_MODE)?;
+
+        Ok(Self { fd, functional: None, channel: channel.to_owned() })
+    }
+
+    #[inline]
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Send data by physical addressing, it blocks until all frames transmitted.
+    pub fn send(&self, data: &[u8]) -> Result<(), CanError> {
+        write_pdu(&self.fd, data)
+    }
+
+    /// Send data by functional addressing, the data must fit in a single frame.
+    pub fn send_functional(&self, data: &[u8]) -> Result<(), CanError> {
+        match &self.functional {
+            Some(fd) => write_pdu(fd, data),
+            None => Err(CanError::OperationError("isotp: functional address is not set".into())),
+        }
+    }
+
+    /// Receive a PDU, it blocks until `timeout` in milliseconds(forever if `None`).
+    pub fn receive(&self, timeout: Option<u32>) -> Result<Vec<u8>, CanError> {
+        if let Some(timeout) = timeout {
+            let timeout = Duration::from_millis(timeout as u64);
+            if !raw_wait_readable(self.fd.as_raw_fd(), timeout).map_err(|e| CanError::OperationError(e.to_string()))? {
+                return Err(CanError::channel_timeout(&self.channel));
+            }
+        }
+
+        let mut buffer = vec![0u8; ISOTP_BUFFER_SIZE];
+        let rd = unsafe { read(self.fd.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, buffer.len()) };
+        if rd < 0 {
+            return Err(CanError::OperationError(io::Error::last_os_error().to_string()));
+        }
+        buffer.truncate(rd as usize);
+
+        Ok(buffer)
+    }
+}
+
+impl Transport for IsoTpSocket {
+    #[inline]
+    fn send(&mut self, data: &[u8]) -> Result<(), CanError> {
+        IsoTpSocket::send(self, data)
+    }
+
+    fn send_functional(&mut self, data: &[u8]) -> Result<(), CanError> {
+        match self.functional {
+            Some(_) => IsoTpSocket::send_functional(self, data),
+            None => IsoTpSocket::send(self, data),
+        }
+    }
+
+    #[inline]
+    fn receive(&mut self, timeout: Option<u32>) -> Result<Vec<u8>, CanError> {
+        IsoTpSocket::receive(self, timeout)
+    }
+}
+
+fn open_socket(channel: &str, rx_id: CanId, tx_id: CanId, config: &IsoTpConfig, flags: u32) -> Result<OwnedFd, CanError> {
+    let mut addr = CanAddr::from_iface(channel)
+        .map_err(|e| CanError::InitializeError(e.to_string()))?;
+    addr.set_tp(can_id(rx_id), can_id(tx_id));
+
+    let fd = isotp_open_socket(&addr, |fd| setup_options(fd, config, flags))
+        .map_err(|e| CanError::InitializeError(e.to_string()))?;
+
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn setup_options(fd: c_int, config: &IsoTpConfig, mut flags: u32) -> io::Result<()> {
+    let mut opts = CanIsoTpOptions::default();
+    if let Some(padding) = config.padding() {
+        flags |= CAN_ISOTP_TX_PADDING;
+        opts.txpad_content = padding;
+        opts.rxpad_content = padding;
+    }
+    opts.flags = flags;
+    set_socket_option(fd, SOL_CAN_ISOTP, CAN_ISOTP_OPTS, &opts)?;
+
+    let fc_opts = CanIsoTpFcOptions {
+        bs: config.block_size(),
+        stmin: config.st_min().0,
+        wftmax: config.max_wait().min(u8::MAX as usize) as u8,
+    };
+    set_socket_option(fd, SOL_CAN_ISOTP, CAN_ISOTP_RECV_FC, &fc_opts)?;
+
+    if config.fd() {
+        let ll_opts = CanIsoTpLlOptions {
+            mtu: CANFD_MTU as u8,
+            tx_dl: 64,
+            tx_flags: if config.bitrate_switch() { CANFD_BRS as u8 } else { 0 },
+        };
+        set_socket_option(fd, SOL_CAN_ISOTP, CAN_ISOTP_LL_OPTS, &ll_opts)?;
+    }
+
+    Ok(())
+}
+
+fn write_pdu(fd: &OwnedFd, data: &[u8]) -> Result<(), CanError> {
+    let ret = unsafe { write(fd.as_raw_fd(), data.as_ptr() as *const c_void, data.len()) };
+    if ret as usize == data.len() {
+        Ok(())
+    }
+    else {
+        Err(CanError::OperationError(io::Error::last_os_error().to_string()))
+    }
+}
@@ -0,0 +1,213 @@
+//! SocketCAN J1939(SAE J1939-21/81).
+//!
+//! [`J1939Socket`] lets kernel transmit and reassemble the large parameter groups by transport
+//! protocol, and resolve the NAME of controllers by its address claim cache.
+//! The `can-j1939` module is required, it's in mainline since linux 5.4.
+
+use std::{io, mem, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, raw::{c_int, c_void}}, ptr, time::Duration};
+use libc::{bind, iovec, j1939_filter, msghdr, recvmsg, sendto, sockaddr_can, CMSG_DATA, CMSG_FIRSTHDR, CMSG_NXTHDR, J1939_NO_ADDR, J1939_NO_NAME, J1939_NO_PGN, SCM_J1939_DEST_ADDR, SCM_J1939_PRIO, SOL_CAN_J1939, SOL_SOCKET, SO_BROADCAST, SO_J1939_FILTER, SO_J1939_PROMISC, SO_J1939_SEND_PRIO};
+use rs_can::{CanError, j1939::{J1939Message, Name, DEFAULT_PRIORITY, GLOBAL_ADDRESS, PGN_ADDRESS_CLAIMED}};
+use crate::{j1939_open_socket, raw_wait_readable, set_socket_option, set_socket_option_mult, CanAddr};
+
+/// The size of receive buffer, the longer parameter group is truncated.
+pub const J1939_BUFFER_SIZE: usize = 1 << 16;
+
+/// The filter of received parameter groups, the bits of masks 0 are not compared.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct J1939Filter {
+    pub name: u64,
+    pub name_mask: u64,
+    pub pgn: u32,
+    pub pgn_mask: u32,
+    pub address: u8,
+    pub address_mask: u8,
+}
+
+impl J1939Filter {
+    /// The filter of a PGN.
+    pub fn from_pgn(pgn: u32) -> Self {
+        Self { pgn, pgn_mask: u32::MAX, ..Default::default() }
+    }
+
+    /// The filter of a source address.
+    pub fn from_address(address: u8) -> Self {
+        Self { address, address_mask: u8::MAX, ..Default::default() }
+    }
+}
+
+impl From<J1939Filter> for j1939_filter {
+    fn from(value: J1939Filter) -> Self {
+        Self {
+            name: value.name,
+            name_mask: value.name_mask,
+            pgn: value.pgn,
+            pgn_mask: value.pgn_mask,
+            addr: value.address,
+            addr_mask: value.address_mask,
+        }
+    }
+}
+
+/// J1939 socket of a channel, every read and write is a whole parameter group.
+///
+/// Broadcast is enabled, so that the parameter groups to [`GLOBAL_ADDRESS`] are sent and received.
+#[derive(Debug)]
+pub struct J1939Socket {
+    fd: OwnedFd,
+    channel: String,
+    name: Option<Name>,
+    address: Option<u8>,
+    pgn: Option<u32>,
+}
+
+impl J1939Socket {
+    /// Open socket bound to `name`, `address` and `pgn`.
+    ///
+    /// The source address is resolved from the address claim cache of kernel if `address` is `None`,
+    /// and the parameter groups of all PGNs are received if `pgn` is `None`.
+    pub fn open(channel: &str, name: Option<Name>, address: Option<u8>, pgn: Option<u32>) -> Result<Self, CanError> {
+        let addr = j1939_addr(channel, name, address, pgn)
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+        let fd = j1939_open_socket(&addr)
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        set_socket_option(fd.as_raw_fd(), SOL_SOCKET, SO_BROADCAST, &(1 as c_int))
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+
+        Ok(Self { fd, channel: channel.to_owned(), name, address, pgn })
+    }
+
+    #[inline]
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<Name> {
+        self.name
+    }
+
+    #[inline]
+    pub fn address(&self) -> Option<u8> {
+        self.address
+    }
+
+    /// Bind to `address` and broadcast the address claimed of NAME.
+    ///
+    /// The kernel tracks the claims in its address claim cache, and the socket with the lower NAME
+    /// wins if the address is contended. The claim should be kept 250ms before sending by it.
+    pub fn claim_address(&mut self, address: u8) -> Result<(), CanError> {
+        let name = self.name
+            .ok_or_else(|| CanError::OperationError("j1939: NAME is required by address claim".into()))?;
+        let addr = j1939_addr(&self.channel, Some(name), Some(address), self.pgn)
+            .map_err(|e| CanError::OperationError(e.to_string()))?;
+        let ret = unsafe { bind(self.fd.as_raw_fd(), addr.as_sockaddr_ptr(), CanAddr::len() as u32) };
+        if ret == -1 {
+            return Err(CanError::OperationError(io::Error::last_os_error().to_string()));
+        }
+        self.address = Some(address);
+
+        self.send(PGN_ADDRESS_CLAIMED, DEFAULT_PRIORITY, GLOBAL_ADDRESS, &name.to_bytes())
+    }
+
+    /// Set filters of received parameter groups, all are received if empty.
+    pub fn set_filters(&self, filters: &[J1939Filter]) -> Result<(), CanError> {
+        let filters = filters.iter()
+            .map(|&f| f.into())
+            .collect::<Vec<j1939_filter>>();
+        set_socket_option_mult(self.fd.as_raw_fd(), SOL_CAN_J1939, SO_J1939_FILTER, &filters)
+            .map_err(|e| CanError::OperationError(e.to_string()))
+    }
+
+    /// Receive the parameter groups to all addresses, not only to the bound address and broadcast.
+    pub fn set_promisc(&self, enable: bool) -> Result<(), CanError> {
+        set_socket_option(self.fd.as_raw_fd(), SOL_CAN_J1939, SO_J1939_PROMISC, &(enable as c_int))
+            .map_err(|e| CanError::OperationError(e.to_string()))
+    }
+
+    /// Send a parameter group to `destination` address, it's transmitted by transport protocol if longer than 8 bytes.
+    pub fn send(&self, pgn: u32, priority: u8, destination: u8, data: &[u8]) -> Result<(), CanError> {
+        self.send_to(J1939_NO_NAME, pgn, priority, destination, data)
+    }
+
+    /// Send a parameter group to the controller of `name`, the address is resolved from the address claim cache.
+    pub fn send_to_name(&self, pgn: u32, priority: u8, name: Name, data: &[u8]) -> Result<(), CanError> {
+        self.send_to(name.0, pgn, priority, J1939_NO_ADDR, data)
+    }
+
+    /// Receive a parameter group, it blocks until `timeout` in milliseconds(forever if `None`).
+    ///
+    /// The destination is [`GLOBAL_ADDRESS`] if the parameter group is broadcast.
+    pub fn receive(&self, timeout: Option<u32>) -> Result<J1939Message, CanError> {
+        if let Some(timeout) = timeout {
+            let timeout = Duration::from_millis(timeout as u64);
+            if !raw_wait_readable(self.fd.as_raw_fd(), timeout).map_err(|e| CanError::OperationError(e.to_string()))? {
+                return Err(CanError::channel_timeout(&self.channel));
+            }
+        }
+
+        let mut data = vec![0u8; J1939_BUFFER_SIZE];
+        let mut iov = iovec { iov_base: data.as_mut_ptr().cast(), iov_len: data.len() };
+        let mut addr: sockaddr_can = unsafe { mem::zeroed() };
+        let mut control = [0u64; 8];
+        let mut msg: msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut addr as *mut _ as *mut c_void;
+        msg.msg_namelen = CanAddr::len() as _;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        let rd = unsafe { recvmsg(self.fd.as_raw_fd(), &mut msg, 0) };
+        if rd < 0 {
+            return Err(CanError::OperationError(io::Error::last_os_error().to_string()));
+        }
+        data.truncate(rd as usize);
+
+        let mut destination = GLOBAL_ADDRESS;
+        let mut priority = 0;
+        unsafe {
+            let mut cmsg = CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                let value = ptr::read(CMSG_DATA(cmsg));
+                match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+                    (SOL_CAN_J1939, SCM_J1939_DEST_ADDR) => destination = value,
+                    (SOL_CAN_J1939, SCM_J1939_PRIO) => priority = value,
+                    _ => {},
+                }
+                cmsg = CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+
+        let (_, pgn, source) = CanAddr::from(addr).j1939();
+        Ok(J1939Message { pgn, priority, source, destination, data })
+    }
+
+    fn send_to(&self, name: u64, pgn: u32, priority: u8, destination: u8, data: &[u8]) -> Result<(), CanError> {
+        set_socket_option(self.fd.as_raw_fd(), SOL_CAN_J1939, SO_J1939_SEND_PRIO, &(priority as c_int))
+            .map_err(|e| CanError::OperationError(e.to_string()))?;
+
+        let mut addr = CanAddr::default();
+        addr.set_j1939(name, pgn, destination);
+        let ret = unsafe {
+            sendto(self.fd.as_raw_fd(), data.as_ptr() as *const c_void, data.len(), 0, addr.as_sockaddr_ptr(), CanAddr::len() as u32)
+        };
+        if ret as usize == data.len() {
+            Ok(())
+        }
+        else {
+            Err(CanError::OperationError(io::Error::last_os_error().to_string()))
+        }
+    }
+}
+
+fn j1939_addr(channel: &str, name: Option<Name>, address: Option<u8>, pgn: Option<u32>) -> io::Result<CanAddr> {
+    let mut addr = CanAddr::from_iface(channel)?;
+    addr.set_j1939(
+        name.map(|v| v.0).unwrap_or(J1939_NO_NAME),
+        pgn.unwrap_or(J1939_NO_PGN),
+        address.unwrap_or(J1939_NO_ADDR),
+    );
+
+    Ok(addr)
+}
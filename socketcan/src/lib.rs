@@ -1,100 +1,143 @@
+#[cfg(feature = "async")]
+mod async_device;
+mod bcm;
+pub use bcm::*;
+mod bus_error;
+pub use bus_error::*;
+mod candump;
+pub use candump::*;
+mod config;
+pub use config::*;
 mod constants;
 pub use constants::*;
 mod frame;
 pub use frame::*;
+mod isotp;
+pub use isotp::*;
+mod j1939;
+pub use j1939::*;
+mod netlink;
+pub use netlink::*;
 mod socket;
 pub use socket::*;
 
-use std::{collections::HashMap, io, sync::Arc, os::{fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd}, raw::{c_int, c_void}}, time::{Instant, Duration}};
-use libc::{can_filter, can_frame, canfd_frame, canxl_frame, fcntl, read, CAN_RAW_ERR_FILTER, CAN_RAW_FILTER, CAN_RAW_JOIN_FILTERS, CAN_RAW_LOOPBACK, CAN_RAW_RECV_OWN_MSGS, EINPROGRESS, F_GETFL, F_SETFL, O_NONBLOCK, SOL_CAN_RAW, SOL_SOCKET, SO_RCVTIMEO, SO_SNDTIMEO};
-use rs_can::{CanDevice, CanError, CanFilter, CanDirect, CanFrame, CanResult, ERR_MASK, DeviceBuilder};
+use std::{cell::RefCell, collections::HashMap, io, ptr, sync::{Arc, Mutex, MutexGuard}, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, raw::c_int}, time::{Instant, Duration}};
+use libc::{can_filter, can_frame, canfd_frame, canxl_frame, fcntl, CANXL_HDR_SIZE, CANXL_XLF, CAN_RAW_ERR_FILTER, CAN_RAW_FILTER, CAN_RAW_JOIN_FILTERS, CAN_RAW_LOOPBACK, CAN_RAW_RECV_OWN_MSGS, CAN_RAW_XL_FRAMES, EINPROGRESS, F_GETFL, F_SETFL, O_NONBLOCK, SOF_TIMESTAMPING_RAW_HARDWARE, SOF_TIMESTAMPING_RX_HARDWARE, SOF_TIMESTAMPING_RX_SOFTWARE, SOF_TIMESTAMPING_SOFTWARE, SOL_CAN_RAW, SOL_SOCKET, SO_RCVTIMEO, SO_SNDTIMEO, SO_TIMESTAMPING, SO_TIMESTAMPNS};
+use serde::{Deserialize, Serialize};
+use rs_can::{CanDevice, CanError, CanFilter, CanDirect, CanFrame, CanResult, ChannelConfig, ERR_MASK, DeviceBuilder};
 
 pub(crate) const FRAME_SIZE: usize = std::mem::size_of::<can_frame>();
 pub(crate) const FD_FRAME_SIZE: usize = std::mem::size_of::<canfd_frame>();
 pub(crate) const XL_FRAME_SIZE: usize = std::mem::size_of::<canxl_frame>();
 
+/// The source of timestamps of received frames.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TimestampSource {
+    /// The system time when the frame is read from socket.
+    #[default]
+    System,
+    /// The kernel software timestamp when the frame is received(`SO_TIMESTAMPNS`).
+    Kernel,
+    /// The hardware timestamp of adapter(`SO_TIMESTAMPING`), it's in the clock of adapter,
+    /// and the kernel software timestamp is used if adapter doesn't report it.
+    Hardware,
+}
+
+/// The default max count of frames received by [`CanDevice::receive`].
+pub const DEFAULT_RECEIVE_LIMIT: usize = 64;
+
+thread_local! {
+    /// The buffers of reading frames in batch, they're reused by the calls on the same thread.
+    static BUFFERS: RefCell<Vec<[u8; XL_FRAME_SIZE]>> = const { RefCell::new(Vec::new()) };
+}
+
+#[inline(always)]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The SocketCAN device, the clones share the channels opened.
 #[derive(Debug, Clone)]
 pub struct SocketCan {
-    sockets: Arc<HashMap<String, OwnedFd>>,
+    sockets: Arc<Mutex<HashMap<String, Arc<OwnedFd>>>>,
+    receive_limit: usize,
+    #[cfg(feature = "async")]
+    async_fds: Arc<Mutex<async_device::AsyncFds>>,
 }
 
 impl SocketCan {
     pub fn new() -> Self {
-        Self { sockets: Default::default() }
+        Self {
+            sockets: Default::default(),
+            receive_limit: DEFAULT_RECEIVE_LIMIT,
+            #[cfg(feature = "async")]
+            async_fds: Default::default(),
+        }
+    }
+
+    /// Set max count of frames received by [`CanDevice::receive`], the frames available are drained up to it.
+    pub fn set_receive_limit(&mut self, limit: usize) -> &mut Self {
+        self.receive_limit = limit.max(1);
+        self
     }
 
-    pub fn init_channel(&mut self, channel: &str, canfd: bool) -> Result<(), CanError> {
+    /// Open the raw socket of channel, it's reopened if the channel is opened.
+    ///
+    /// The options set on the previous socket are discarded, and the reads and writes
+    /// in progress on it are finished before it's closed.
+    pub fn init_channel(&self, channel: &str, canfd: bool) -> Result<(), CanError> {
         let addr = CanAddr::from_iface(channel)
-            .map_err(|e| CanError::InitializeError(e.to_string()))?;
-
-        let _ = raw_open_socket(&addr)
-            .and_then(|fd| {
-                set_fd_mode(fd, canfd)
-            })
-            .and_then(|fd| {
-                Arc::get_mut(&mut self.sockets)
-                    .ok_or(io::Error::last_os_error())?
-                    .insert(channel.to_owned(), unsafe { OwnedFd::from_raw_fd(fd) });
-                // Rc::get_mut(&mut self.sockets)
-                //     .ok_or(io::Error::last_os_error())?
-                //     .insert(channel.to_owned(), unsafe { OwnedFd::from_raw_fd(fd) });
-                Ok(())
-            })
-            .map_err(|_| CanError::InitializeError("device open failed".into()));
+            .map_err(|e| CanError::InitializeError(format!("{}: {}", channel, e)))?;
+
+        let fd = raw_open_socket(&addr)
+            .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) })
+            .map_err(|e| CanError::InitializeError(format!("{}: {}", channel, e)))?;
+        set_fd_mode(fd.as_raw_fd(), canfd)
+            .map_err(|e| CanError::InitializeError(format!("{}: {}", channel, e)))?;
+        lock(&self.sockets).insert(channel.to_owned(), Arc::new(fd));
+
+        Ok(())
+    }
+
+    /// Close the socket of channel, the other channels are not affected.
+    pub fn close_channel(&self, channel: &str) -> Result<(), CanError> {
+        lock(&self.sockets).remove(channel)
+            .ok_or(CanError::channel_not_opened(channel))?;
+        #[cfg(feature = "async")]
+        lock(&self.async_fds).remove(channel);
 
         Ok(())
     }
 
     pub fn read(&self, channel: &str) -> Result<CanMessage, CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let mut buffer = [0; XL_FRAME_SIZE];
-
-                let rd = unsafe { read(
-                    s.as_raw_fd(),
-                    &mut buffer as *mut _ as *mut c_void,
-                    XL_FRAME_SIZE
-                ) };
-                match rd as usize {
-                    FRAME_SIZE => {
-                        let frame = unsafe { *(&buffer as *const _ as *const can_frame) };
-                        let mut frame = CanMessage::from(CanAnyFrame::from(frame));
-                        frame.set_direct(CanDirect::Receive);
-                        Ok(frame)
-                    },
-                    FD_FRAME_SIZE => {
-                        let frame = unsafe { *(&buffer as *const _ as *const canfd_frame) };
-                        let mut frame = CanMessage::from(CanAnyFrame::from(frame));
-                        frame.set_direct(CanDirect::Receive);
-                        Ok(frame)
-                    },
-                    XL_FRAME_SIZE => {
-                        let frame = unsafe { *(&buffer as *const _ as *const canxl_frame) };
-                        let mut frame = CanMessage::from(CanAnyFrame::from(frame));
-                        frame.set_direct(CanDirect::Receive);
-                        Ok(frame)
-                    },
-                    _ => Err(CanError::OperationError(io::Error::last_os_error().to_string()))
-                }
+                let (rd, timestamp) = raw_read_frame(s.as_raw_fd(), &mut buffer)
+                    .map_err(|e| CanError::OperationError(e.to_string()))?;
+                received_frame(&buffer, rd, timestamp, channel)
             },
             None => Err(CanError::channel_not_opened(channel))
         }
     }
 
+    /// Read the frames available without blocking, up to `max` frames.
+    pub fn read_batch(&self, channel: &str, max: usize) -> Result<Vec<CanMessage>, CanError> {
+        match self.socket(channel) {
+            Some(s) => read_frames(s.as_raw_fd(), max, channel)
+                .map_err(|e| CanError::OperationError(e.to_string()))?,
+            None => Err(CanError::channel_not_opened(channel))
+        }
+    }
+
     /// Blocking read a single can frame with timeout.
     pub fn read_timeout(&self, channel: &str, timeout: Duration) -> Result<CanMessage, CanError> {
-        match self.sockets.get(channel) {
-            Some(s) => {
-                use nix::poll::{poll, PollFd, PollFlags};
-                let borrowed_fd = unsafe { BorrowedFd::borrow_raw(s.as_raw_fd()) };
-                let pollfd = PollFd::new(borrowed_fd, PollFlags::POLLIN);
-
-                match poll::<u16>(&mut [pollfd], timeout.as_millis() as u16)
-                    .map_err(|e| CanError::OperationError(e.to_string()))?
-                {
-                    0 => Err(CanError::channel_timeout(channel)),
-                    _ => self.read(channel),
-                }
+        match self.socket(channel) {
+            Some(s) => match raw_wait_readable(s.as_raw_fd(), timeout)
+                .map_err(|e| CanError::OperationError(e.to_string()))?
+            {
+                true => self.read(channel),
+                false => Err(CanError::channel_timeout(channel)),
             },
             None => Err(CanError::channel_not_opened(channel)),
         }
@@ -102,7 +145,7 @@ impl SocketCan {
 
     pub fn write(&self, msg: CanMessage) -> Result<(), CanError> {
         let channel = msg.channel();
-        match self.sockets.get(&channel) {
+        match self.socket(&channel) {
             Some(s) => {
                 let frame: CanAnyFrame = msg.into();
                 match frame {
@@ -126,13 +169,57 @@ impl SocketCan {
         }
     }
 
+    /// Write frames with as few syscalls as possible, the frames of each channel are written in order.
+    ///
+    /// The count of frames written is returned, it's less than frames if a channel failed.
+    /// The error of the last failed channel is returned if no frame is written.
+    pub fn write_batch(&self, msgs: Vec<CanMessage>) -> Result<usize, CanError> {
+        let mut channels: Vec<(String, Vec<CanAnyFrame>)> = Vec::new();
+        for msg in msgs {
+            let channel = msg.channel();
+            match channels.iter_mut().find(|(c, _)| *c == channel) {
+                Some((_, frames)) => frames.push(msg.into()),
+                None => channels.push((channel, vec![msg.into()])),
+            }
+        }
+
+        let (mut count, mut error) = (0, None);
+        for (channel, frames) in channels {
+            let Some(s) = self.socket(&channel) else {
+                log::warn!("RUST-CAN - socketcan write {} frames failed: channel {} is not opened", frames.len(), channel);
+                error = Some(CanError::channel_not_opened(&channel));
+                continue;
+            };
+            let frames = frames.iter()
+                .map(|f| f.as_bytes())
+                .collect::<Vec<_>>();
+            let mut written = 0;
+            while written < frames.len() {
+                match raw_write_frames(s.as_raw_fd(), &frames[written..]) {
+                    Ok(n) => written += n,
+                    Err(e) => {
+                        log::warn!("RUST-CAN - socketcan write {} frames failed on {}: {}", frames.len() - written, channel, e);
+                        error = Some(CanError::OperationError(e.to_string()));
+                        break;
+                    },
+                }
+            }
+            count += written;
+        }
+
+        match error {
+            Some(e) if count == 0 => Err(e),
+            _ => Ok(count),
+        }
+    }
+
     /// Blocking write a single can frame, retrying until it gets sent successfully.
     pub fn write_timeout(&self, msg: CanMessage, timeout: Duration) -> Result<(), CanError> {
         let channel = msg.channel();
         let frame: CanAnyFrame = msg.into();
         let start = Instant::now();
         while start.elapsed() < timeout {
-            match self.sockets.get(&channel) {
+            match self.socket(&channel) {
                 Some(s) => {
                     if let Err(e) = match frame {
                         CanAnyFrame::Normal(f) |
@@ -169,7 +256,7 @@ impl SocketCan {
 
     /// Change socket to non-blocking mode or back to blocking mode.
     pub fn set_nonblocking(&self, channel: &str, nonblocking: bool) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 // retrieve current flags
                 let oldfl = unsafe { fcntl(s.as_raw_fd(), F_GETFL) };
@@ -202,7 +289,7 @@ impl SocketCan {
     /// For convenience, the result value can be checked using
     /// `ShouldRetry::should_retry` when a timeout is set.
     pub fn set_read_timeout(&self, channel: &str, duration: Duration) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 set_socket_option(
                     s.as_raw_fd(),
@@ -218,7 +305,7 @@ impl SocketCan {
 
     /// Sets the write timeout on the socket
     pub fn set_write_timeout(&self, channel: &str, duration: Duration) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 set_socket_option(
                     s.as_raw_fd(),
@@ -243,7 +330,7 @@ impl SocketCan {
     /// See `CanFilter` for details on how filtering works. By default, all
     /// single filter matching all incoming frames is installed.
     pub fn set_filters(&self, channel: &str, filters: &[CanFilter]) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let filters: Vec<can_filter> = filters.iter()
                     .map(|&f| {
@@ -264,7 +351,7 @@ impl SocketCan {
     ///
     /// Sets a completely empty filter; disabling all CAN frame reception.
     pub fn set_filter_drop_all(&self, channel: &str) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let filters: &[CanFilter] = &[];
                 set_socket_option_mult(s.as_raw_fd(), SOL_CAN_RAW, CAN_RAW_FILTER, filters)
@@ -290,7 +377,7 @@ impl SocketCan {
     /// setting `ERR_MASK_ALL` or another non-empty error mask causes the
     /// socket to receive notification about the specified conditions.
     pub fn set_error_filter(&self, channel: &str, mask: u32) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 set_socket_option(s.as_raw_fd(), SOL_CAN_RAW, CAN_RAW_ERR_FILTER, &mask)
                     .map_err(|e| CanError::OperationError(e.to_string()))
@@ -317,7 +404,7 @@ impl SocketCan {
     /// the same CAN bus to see frames emitted by different applications on
     /// the same system.
     pub fn set_loopback(&self, channel: &str, enabled: bool) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let loopback = c_int::from(enabled);
                 set_socket_option(s.as_raw_fd(), SOL_CAN_RAW, CAN_RAW_LOOPBACK, &loopback)
@@ -332,7 +419,7 @@ impl SocketCan {
     /// When loopback is enabled, this settings controls if CAN frames sent
     /// are received back immediately by sender. Default is off.
     pub fn set_recv_own_msgs(&self, channel: &str, enabled: bool) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let recv_own_msgs = c_int::from(enabled);
                 set_socket_option(
@@ -353,7 +440,7 @@ impl SocketCan {
     /// with `set_filters`. If join filters is enabled, a frame has to match
     /// _all_ filters to be accepted.
     pub fn set_join_filters(&self, channel: &str, enabled: bool) -> Result<(), CanError> {
-        match self.sockets.get(channel) {
+        match self.socket(channel) {
             Some(s) => {
                 let join_filters = c_int::from(enabled);
                 set_socket_option(
@@ -367,6 +454,173 @@ impl SocketCan {
             None => Err(CanError::channel_not_opened(channel)),
         }
     }
+
+    /// Enable or disable CAN XL frames, CAN-FD frames are enabled as well by kernel.
+    ///
+    /// The MTU of interface must be set to CAN XL MTU, like `ip link set vcan0 mtu 2060`.
+    pub fn set_xl_frames(&self, channel: &str, enabled: bool) -> Result<(), CanError> {
+        match self.socket(channel) {
+            Some(s) => {
+                let xl_frames = c_int::from(enabled);
+                set_socket_option(s.as_raw_fd(), SOL_CAN_RAW, CAN_RAW_XL_FRAMES, &xl_frames)
+                    .map_err(|e| CanError::OperationError(e.to_string()))
+            }
+            None => Err(CanError::channel_not_opened(channel)),
+        }
+    }
+
+    /// Select the source of timestamps of received frames.
+    ///
+    /// The hardware timestamps may require enabling by `hwstamp_ctl` on some adapters.
+    pub fn set_timestamp_source(&self, channel: &str, source: TimestampSource) -> Result<(), CanError> {
+        match self.socket(channel) {
+            Some(s) => {
+                let fd = s.as_raw_fd();
+                let (nanos, flags) = match source {
+                    TimestampSource::System => (0, 0),
+                    TimestampSource::Kernel => (1, 0),
+                    TimestampSource::Hardware => (0, SOF_TIMESTAMPING_RX_HARDWARE | SOF_TIMESTAMPING_RAW_HARDWARE
+                        | SOF_TIMESTAMPING_RX_SOFTWARE | SOF_TIMESTAMPING_SOFTWARE),
+                };
+                set_socket_option(fd, SOL_SOCKET, SO_TIMESTAMPNS, &(nanos as c_int))
+                    .and_then(|_| set_socket_option(fd, SOL_SOCKET, SO_TIMESTAMPING, &(flags as c_int)))
+                    .map_err(|e| CanError::OperationError(e.to_string()))
+            }
+            None => Err(CanError::channel_not_opened(channel)),
+        }
+    }
+
+    /// Set VCID options of CAN XL frames, it's supported since Linux 6.9.
+    ///
+    /// The VCID of transmitted frames is replaced by `tx_vcid` if set, otherwise it's passed as it is.
+    /// Only the frames that `vcid & rx_mask == rx_vcid & rx_mask` are received,
+    /// and the frames with VCID are dropped by kernel before this is set.
+    pub fn set_xl_vcid(&self, channel: &str, tx_vcid: Option<u8>, rx_vcid: u8, rx_mask: u8) -> Result<(), CanError> {
+        match self.socket(channel) {
+            Some(s) => {
+                let options = CanRawVcidOptions {
+                    flags: CAN_RAW_XL_VCID_RX_FILTER | if tx_vcid.is_some() { CAN_RAW_XL_VCID_TX_SET } else { CAN_RAW_XL_VCID_TX_PASS },
+                    tx_vcid: tx_vcid.unwrap_or_default(),
+                    rx_vcid,
+                    rx_vcid_mask: rx_mask,
+                };
+                set_socket_option(s.as_raw_fd(), SOL_CAN_RAW, CAN_RAW_XL_VCID_OPTS, &options)
+                    .map_err(|e| CanError::OperationError(e.to_string()))
+            }
+            None => Err(CanError::channel_not_opened(channel)),
+        }
+    }
+
+    /// The socket of channel, it's kept open by the returned handle even if the channel is closed.
+    #[inline(always)]
+    fn socket(&self, channel: &str) -> Option<Arc<OwnedFd>> {
+        lock(&self.sockets).get(channel)
+            .cloned()
+    }
+}
+
+/// Read the frames available of channel without blocking, up to `max` frames.
+///
+/// It's an error of I/O if failed to read, and an error of frame if a frame read is invalid.
+fn read_frames(fd: c_int, max: usize, channel: &str) -> io::Result<Result<Vec<CanMessage>, CanError>> {
+    BUFFERS.with_borrow_mut(|buffers| {
+        if buffers.len() < max {
+            buffers.resize(max, [0; XL_FRAME_SIZE]);
+        }
+        let buffers = &mut buffers[..max];
+        Ok(raw_read_frames(fd, buffers)?
+            .into_iter()
+            .zip(buffers.iter())
+            .map(|((rd, timestamp), buffer)| received_frame(buffer, rd, timestamp, channel))
+            .collect())
+    })
+}
+
+/// Convert a frame read from channel to message.
+fn received_frame(buffer: &[u8; XL_FRAME_SIZE], rd: usize, timestamp: Option<u64>, channel: &str) -> Result<CanMessage, CanError> {
+    // the CAN XL frame is read with actual data length, and it's told apart by XLF flag.
+    let frame = if rd > CANXL_HDR_SIZE && buffer[4] & CANXL_XLF as u8 != 0 {
+        CanAnyFrame::from(unsafe { ptr::read_unaligned(buffer as *const _ as *const canxl_frame) })
+    }
+    else {
+        match rd {
+            FRAME_SIZE => CanAnyFrame::from(unsafe { ptr::read_unaligned(buffer as *const _ as *const can_frame) }),
+            FD_FRAME_SIZE => CanAnyFrame::from(unsafe { ptr::read_unaligned(buffer as *const _ as *const canfd_frame) }),
+            _ => return Err(CanError::OperationError(format!("invalid frame size: {}", rd))),
+        }
+    };
+
+    let mut frame = CanMessage::from(frame);
+    if timestamp.is_some() {
+        frame.set_timestamp_ns(timestamp);
+    }
+    frame.set_direct(CanDirect::Receive)
+        .set_channel(channel.to_owned());
+    Ok(frame)
+}
+
+/// Create, configure and bring up the interface of channel by rtnetlink.
+///
+/// The controller is reconfigured only when its current settings differ from the configured,
+/// so that the interface configured already is opened without `CAP_NET_ADMIN`.
+fn configure_link(channel: &str, cfg: &ChannelConfig, canfd: bool) -> Result<(), CanError> {
+    if cfg.get_other::<bool>(CREATE_VCAN)?.unwrap_or_default() && CanAddr::from_iface(channel).is_err() {
+        CanInterface::create_vcan(channel)?;
+    }
+
+    let iface = CanInterface::open(channel)?;
+    if iface.kind()?.as_deref() == Some("can") && cfg.bitrate() > 0 {
+        let sample_point = cfg.get_other::<f32>(SAMPLE_POINT)?;
+        let data_sample_point = cfg.get_other::<f32>(DATA_SAMPLE_POINT)?;
+        let restart_ms = cfg.get_other::<u32>(RESTART_MS)?;
+        // the control modes are set exactly if configured, otherwise only FD is enabled.
+        let fd = canfd && cfg.dbitrate().is_some();
+        let ctrl_modes = cfg.get_other::<CanCtrlModes>(CTRL_MODES)?
+            .map(|v| if fd { v | CanCtrlModes::FD } else { v });
+
+        let termination = cfg.resistance()
+            .map(|v| if v { DEFAULT_TERMINATION } else { 0 });
+        let details = iface.details()?;
+        let matches = timing_matches(details.bittiming, cfg.bitrate(), sample_point)
+            && cfg.dbitrate().is_none_or(|v| timing_matches(details.data_bittiming, v, data_sample_point))
+            && restart_ms.is_none_or(|v| details.restart_ms == Some(v))
+            && match ctrl_modes {
+                Some(v) => details.ctrl_modes.map(|m| m & CanCtrlModes::all()) == Some(v),
+                None => !fd || details.ctrl_modes.is_some_and(|m| m.contains(CanCtrlModes::FD)),
+            }
+            && termination.is_none_or(|v| details.termination == Some(v));
+
+        if !matches {
+            iface.bring_down()?;
+            match ctrl_modes {
+                Some(modes) => iface.replace_ctrl_modes(modes)?,
+                None if fd => iface.set_ctrl_modes(CanCtrlModes::FD, true)?,
+                None => {},
+            }
+            iface.set_bitrate(cfg.bitrate(), sample_point)?;
+            if let Some(dbitrate) = cfg.dbitrate() {
+                iface.set_data_bitrate(dbitrate, data_sample_point)?;
+            }
+            if let Some(ms) = restart_ms {
+                iface.set_restart_ms(ms)?;
+            }
+            if let Some(ohm) = termination {
+                iface.set_termination(ohm)?;
+            }
+        }
+    }
+
+    if !iface.is_up()? {
+        iface.bring_up()?;
+    }
+
+    Ok(())
+}
+
+/// Whether the bit timing is of bitrate, the sample point is compared in one-tenth of a percent if set.
+fn timing_matches(timing: Option<CanBitTiming>, bitrate: u32, sample_point: Option<f32>) -> bool {
+    timing.is_some_and(|t| t.bitrate == bitrate
+        && sample_point.is_none_or(|v| t.sample_point == (v * 1000.).round() as u32))
 }
 
 impl TryFrom<DeviceBuilder> for SocketCan {
@@ -374,13 +628,25 @@ impl TryFrom<DeviceBuilder> for SocketCan {
 
     fn try_from(builder: DeviceBuilder) -> Result<Self, Self::Error> {
         let mut device = SocketCan::new();
+        if let Some(limit) = builder.get_other::<usize>(RECEIVE_LIMIT)? {
+            device.set_receive_limit(limit);
+        }
         builder.channel_configs()
             .iter()
             .try_for_each(|(chl, cfg)| {
                 let canfd = cfg.get_other::<bool>(CANFD)?
                     .unwrap_or_default();
+                configure_link(chl, cfg, canfd)?;
                 device.init_channel(chl, canfd)?;
 
+                if let Some(canxl) = cfg.get_other::<bool>(CANXL)? {
+                    device.set_xl_frames(chl, canxl)?;
+                }
+
+                if let Some(source) = cfg.get_other::<TimestampSource>(TIMESTAMP_SOURCE)? {
+                    device.set_timestamp_source(chl, source)?;
+                }
+
                 if let Some(filters) = cfg.get_other::<Vec<CanFilter>>(FILTERS)? {
                     device.set_filters(chl, &filters)?;
                 }
@@ -406,8 +672,8 @@ impl CanDevice for SocketCan {
 
     #[inline(always)]
     fn opened_channels(&self) -> Vec<Self::Channel> {
-        self.sockets.iter()
-            .map(|(c, _)| c.clone())
+        lock(&self.sockets).keys()
+            .cloned()
             .collect()
     }
 
@@ -422,15 +688,21 @@ impl CanDevice for SocketCan {
     #[inline(always)]
     fn receive(&self, channel: Self::Channel, timeout: Option<u32>) -> CanResult<Vec<Self::Frame>, CanError> {
         let timeout = timeout.unwrap_or(0);
-        let msg = self.read_timeout(&channel, Duration::from_millis(timeout as u64))?;
-        Ok(vec![msg, ])
+        let mut frames = vec![self.read_timeout(&channel, Duration::from_millis(timeout as u64))?];
+        if self.receive_limit > 1 {
+            // the frame read is returned even if the others failed.
+            match self.read_batch(&channel, self.receive_limit - 1) {
+                Ok(others) => frames.extend(others),
+                Err(e) => log::warn!("RUST-CAN - read frames of {} failed: {}", channel, e),
+            }
+        }
+        Ok(frames)
     }
 
     #[inline(always)]
     fn shutdown(&mut self) {
-        match Arc::get_mut(&mut self.sockets) {
-            Some(s) => s.clear(),
-            None => (),
-        }
+        lock(&self.sockets).clear();
+        #[cfg(feature = "async")]
+        lock(&self.async_fds).clear();
     }
 }
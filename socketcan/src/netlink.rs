@@ -0,0 +1,603 @@
+//! SocketCAN interface configuration by rtnetlink.
+//!
+//! [`CanInterface`] sets the bit timing, control modes, restart and termination of CAN interfaces,
+//! brings them up or down, and creates or deletes the virtual interfaces, like `ip link` does.
+//! The interfaces are listed by [`CanInterface::list`], and their state, error counters and
+//! statistics are queried by [`CanInterface::details`], like `ip -details -statistics link show`.
+//! The configurations require `CAP_NET_ADMIN`, and the bit timing can be set only when the interface is down.
+
+use std::{ffi::CString, io, mem, os::{fd::{AsRawFd, FromRawFd, OwnedFd}, raw::{c_int, c_void}}, ptr, slice, sync::atomic::{AtomicU32, Ordering}};
+use bitflags::bitflags;
+use libc::{can_berr_counter, can_bittiming, can_bittiming_const, can_clock, can_ctrlmode, can_device_stats, ARPHRD_CAN, IFLA_CAN_BERR_COUNTER, IFLA_CAN_BITTIMING_CONST, IFLA_CAN_CLOCK, IFLA_CAN_DATA_BITTIMING_CONST, IFLA_CAN_STATE, IFLA_INFO_XSTATS, IFLA_MTU, IFLA_STATS64, NLM_F_DUMP, if_nametoindex, ifinfomsg, nlmsgerr, nlmsghdr, recv, send, socket, AF_NETLINK, AF_UNSPEC, CAN_CTRLMODE_3_SAMPLES, CAN_CTRLMODE_BERR_REPORTING, CAN_CTRLMODE_CC_LEN8_DLC, CAN_CTRLMODE_FD, CAN_CTRLMODE_FD_NON_ISO, CAN_CTRLMODE_LISTENONLY, CAN_CTRLMODE_LOOPBACK, CAN_CTRLMODE_ONE_SHOT, CAN_CTRLMODE_PRESUME_ACK, IFF_UP, IFLA_CAN_BITTIMING, IFLA_CAN_CTRLMODE, IFLA_CAN_DATA_BITTIMING, IFLA_CAN_RESTART, IFLA_CAN_RESTART_MS, IFLA_CAN_TERMINATION, IFLA_IFNAME, IFLA_INFO_DATA, IFLA_INFO_KIND, IFLA_LINKINFO, NETLINK_ROUTE, NLA_TYPE_MASK, NLMSG_DONE, NLMSG_ERROR, NLM_F_ACK, NLM_F_CREATE, NLM_F_EXCL, NLM_F_REQUEST, RTM_DELLINK, RTM_GETLINK, RTM_NEWLINK, SOCK_CLOEXEC, SOCK_RAW};
+use rs_can::CanError;
+use serde::{Deserialize, Serialize};
+
+/// The termination resistance in ohm when `ChannelConfig::resistance` is enabled.
+pub const DEFAULT_TERMINATION: u16 = 120;
+
+/// The peer attribute of vxcan info data.
+const VXCAN_INFO_PEER: u16 = 1;
+
+const NLMSG_HDR_SIZE: usize = mem::size_of::<nlmsghdr>();
+const IFINFO_SIZE: usize = mem::size_of::<ifinfomsg>();
+const NLA_HDR_SIZE: usize = 4;
+
+bitflags! {
+    /// The control modes of CAN controller, `CAN_CTRLMODE_*` of kernel.
+    #[repr(transparent)]
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+    pub struct CanCtrlModes: u32 {
+        /// Loopback the transmitted frames in controller.
+        const LOOPBACK = CAN_CTRLMODE_LOOPBACK;
+        /// Receive only, no ACK or error frame is transmitted.
+        const LISTEN_ONLY = CAN_CTRLMODE_LISTENONLY;
+        /// Sample 3 times of a bit.
+        const TRIPLE_SAMPLING = CAN_CTRLMODE_3_SAMPLES;
+        /// Transmit once without retransmission when arbitration lost or error.
+        const ONE_SHOT = CAN_CTRLMODE_ONE_SHOT;
+        /// Report bus errors by error frames.
+        const BERR_REPORTING = CAN_CTRLMODE_BERR_REPORTING;
+        /// CAN-FD mode.
+        const FD = CAN_CTRLMODE_FD;
+        /// Ignore missing ACK.
+        const PRESUME_ACK = CAN_CTRLMODE_PRESUME_ACK;
+        /// CAN-FD of Bosch, not ISO 11898-1.
+        const FD_NON_ISO = CAN_CTRLMODE_FD_NON_ISO;
+        /// Classical CAN DLC of 9-15.
+        const CC_LEN8_DLC = CAN_CTRLMODE_CC_LEN8_DLC;
+    }
+}
+
+/// The CAN interface of kernel, identified by interface index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanInterface {
+    index: u32,
+    name: String,
+}
+
+impl CanInterface {
+    /// Open the interface of name.
+    pub fn open(name: &str) -> Result<Self, CanError> {
+        let c_name = CString::new(name)
+            .map_err(|e| CanError::InitializeError(e.to_string()))?;
+        let index = unsafe { if_nametoindex(c_name.as_ptr()) };
+        if index == 0 {
+            return Err(CanError::InitializeError(io::Error::last_os_error().to_string()));
+        }
+
+        Ok(Self { index, name: name.to_owned() })
+    }
+
+    /// List the CAN interfaces, like `can`, `vcan`, `vxcan` and `slcan`.
+    pub fn list() -> Result<Vec<Self>, CanError> {
+        let msg = NlMessage::new(RTM_GETLINK, NLM_F_DUMP, 0, 0, 0);
+        let replies = request(msg).map_err(|e| CanError::OperationError(e.to_string()))?;
+
+        Ok(replies.into_iter()
+            .filter(|r| r.len() >= IFINFO_SIZE)
+            .filter_map(|r| {
+                let info = unsafe { ptr::read_unaligned(r.as_ptr() as *const ifinfomsg) };
+                if info.ifi_type != ARPHRD_CAN {
+                    return None;
+                }
+                let name = find_attr(&r[IFINFO_SIZE..], IFLA_IFNAME)
+                    .map(|v| String::from_utf8_lossy(v).trim_end_matches('\0').to_owned())?;
+                Some(Self { index: info.ifi_index as u32, name })
+            })
+            .collect())
+    }
+
+    /// Create a `vcan` interface.
+    pub fn create_vcan(name: &str) -> Result<Self, CanError> {
+        let mut msg = NlMessage::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL, 0, 0, 0);
+        msg.push_str(IFLA_IFNAME, name);
+        let linkinfo = msg.begin_nested(IFLA_LINKINFO);
+        msg.push_str(IFLA_INFO_KIND, "vcan");
+        msg.end_nested(linkinfo);
+        request(msg).map_err(|e| CanError::InitializeError(e.to_string()))?;
+
+        Self::open(name)
+    }
+
+    /// Create a pair of `vxcan` interfaces, the frames transmitted to one are received by the other.
+    pub fn create_vxcan(name: &str, peer: &str) -> Result<(Self, Self), CanError> {
+        let mut msg = NlMessage::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL, 0, 0, 0);
+        msg.push_str(IFLA_IFNAME, name);
+        let linkinfo = msg.begin_nested(IFLA_LINKINFO);
+        msg.push_str(IFLA_INFO_KIND, "vxcan");
+        let data = msg.begin_nested(IFLA_INFO_DATA);
+        let info_peer = msg.begin_nested(VXCAN_INFO_PEER);
+        msg.push_ifinfo(0, 0, 0);
+        msg.push_str(IFLA_IFNAME, peer);
+        msg.end_nested(info_peer);
+        msg.end_nested(data);
+        msg.end_nested(linkinfo);
+        request(msg).map_err(|e| CanError::InitializeError(e.to_string()))?;
+
+        Ok((Self::open(name)?, Self::open(peer)?))
+    }
+
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Delete the interface, the pair of `vxcan` is deleted together.
+    pub fn delete(self) -> Result<(), CanError> {
+        let msg = NlMessage::new(RTM_DELLINK, 0, self.index, 0, 0);
+        request(msg).map_err(|e| CanError::OperationError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The state, bit timing, counters and statistics of interface.
+    pub fn details(&self) -> Result<CanInterfaceDetails, CanError> {
+        let (flags, attrs) = self.link()?;
+        let info = find_attr(&attrs, IFLA_LINKINFO);
+        let kind = info.and_then(|info| find_attr(info, IFLA_INFO_KIND))
+            .map(|kind| String::from_utf8_lossy(kind).trim_end_matches('\0').to_owned());
+        let data = info.and_then(|info| find_attr(info, IFLA_INFO_DATA))
+            .unwrap_or_default();
+
+        Ok(CanInterfaceDetails {
+            name: self.name.clone(),
+            index: self.index,
+            kind,
+            is_up: flags & IFF_UP as u32 != 0,
+            mtu: read_attr::<u32>(&attrs, IFLA_MTU),
+            state: read_attr::<u32>(data, IFLA_CAN_STATE as u16).and_then(CanState::from_raw),
+            ctrl_modes: read_attr::<can_ctrlmode>(data, IFLA_CAN_CTRLMODE as u16)
+                .map(|v| CanCtrlModes::from_bits_retain(v.flags)),
+            restart_ms: read_attr::<u32>(data, IFLA_CAN_RESTART_MS as u16),
+            termination: read_attr::<u16>(data, IFLA_CAN_TERMINATION as u16),
+            bittiming: read_attr::<can_bittiming>(data, IFLA_CAN_BITTIMING as u16).map(Into::into),
+            data_bittiming: read_attr::<can_bittiming>(data, IFLA_CAN_DATA_BITTIMING as u16).map(Into::into),
+            bittiming_const: read_attr::<can_bittiming_const>(data, IFLA_CAN_BITTIMING_CONST as u16).map(Into::into),
+            data_bittiming_const: read_attr::<can_bittiming_const>(data, IFLA_CAN_DATA_BITTIMING_CONST as u16).map(Into::into),
+            clock: read_attr::<can_clock>(data, IFLA_CAN_CLOCK as u16).map(|v| v.freq),
+            berr_counter: read_attr::<can_berr_counter>(data, IFLA_CAN_BERR_COUNTER as u16)
+                .map(|v| BerrCounter { tx: v.txerr, rx: v.rxerr }),
+            device_stats: info.and_then(|info| read_attr::<can_device_stats>(info, IFLA_INFO_XSTATS))
+                .map(Into::into),
+            stats: find_attr(&attrs, IFLA_STATS64).and_then(LinkStats::from_bytes),
+        })
+    }
+
+    /// The kind of link, like `can`, `vcan` and `vxcan`, it's `None` for `slcan`.
+    pub fn kind(&self) -> Result<Option<String>, CanError> {
+        let (_, attrs) = self.link()?;
+        Ok(find_attr(&attrs, IFLA_LINKINFO)
+            .and_then(|info| find_attr(info, IFLA_INFO_KIND))
+            .map(|kind| String::from_utf8_lossy(kind).trim_end_matches('\0').to_owned()))
+    }
+
+    pub fn is_up(&self) -> Result<bool, CanError> {
+        let (flags, _) = self.link()?;
+        Ok(flags & IFF_UP as u32 != 0)
+    }
+
+    /// The bitrate of controller, it's `None` if the interface is virtual or not configured.
+    pub fn bitrate(&self) -> Result<Option<u32>, CanError> {
+        let (_, attrs) = self.link()?;
+        Ok(find_attr(&attrs, IFLA_LINKINFO)
+            .and_then(|info| find_attr(info, IFLA_INFO_DATA))
+            .and_then(|data| read_attr::<can_bittiming>(data, IFLA_CAN_BITTIMING as u16))
+            .map(|v| v.bitrate)
+            .filter(|&v| v > 0))
+    }
+
+    pub fn bring_up(&self) -> Result<(), CanError> {
+        self.set_flags(IFF_UP as u32, IFF_UP as u32)
+    }
+
+    pub fn bring_down(&self) -> Result<(), CanError> {
+        self.set_flags(0, IFF_UP as u32)
+    }
+
+    /// Set nominal bitrate, the `sample_point` is ratio(like 0.875) and calculated by kernel if `None`.
+    pub fn set_bitrate(&self, bitrate: u32, sample_point: Option<f32>) -> Result<(), CanError> {
+        let timing = bittiming(bitrate, sample_point);
+        self.set_can_attr(IFLA_CAN_BITTIMING as u16, as_bytes(&timing))
+    }
+
+    /// Set data bitrate of CAN-FD, the `sample_point` is ratio(like 0.75) and calculated by kernel if `None`.
+    pub fn set_data_bitrate(&self, bitrate: u32, sample_point: Option<f32>) -> Result<(), CanError> {
+        let timing = bittiming(bitrate, sample_point);
+        self.set_can_attr(IFLA_CAN_DATA_BITTIMING as u16, as_bytes(&timing))
+    }
+
+    /// Enable or disable the control modes, the other modes are not changed.
+    pub fn set_ctrl_modes(&self, modes: CanCtrlModes, enable: bool) -> Result<(), CanError> {
+        let ctrlmode = can_ctrlmode {
+            mask: modes.bits(),
+            flags: if enable { modes.bits() } else { 0 },
+        };
+        self.set_can_attr(IFLA_CAN_CTRLMODE as u16, as_bytes(&ctrlmode))
+    }
+
+    /// Set the control modes exactly, the other modes are disabled.
+    pub fn replace_ctrl_modes(&self, modes: CanCtrlModes) -> Result<(), CanError> {
+        let ctrlmode = can_ctrlmode {
+            mask: CanCtrlModes::all().bits(),
+            flags: modes.bits(),
+        };
+        self.set_can_attr(IFLA_CAN_CTRLMODE as u16, as_bytes(&ctrlmode))
+    }
+
+    /// Set the delay of automatic restart after bus off, it's disabled if 0.
+    pub fn set_restart_ms(&self, ms: u32) -> Result<(), CanError> {
+        self.set_can_attr(IFLA_CAN_RESTART_MS as u16, &ms.to_ne_bytes())
+    }
+
+    /// Restart the controller in bus off manually.
+    pub fn restart(&self) -> Result<(), CanError> {
+        self.set_can_attr(IFLA_CAN_RESTART as u16, &1u32.to_ne_bytes())
+    }
+
+    /// Set the termination resistance in ohm, it's disabled if 0.
+    pub fn set_termination(&self, ohm: u16) -> Result<(), CanError> {
+        self.set_can_attr(IFLA_CAN_TERMINATION as u16, &ohm.to_ne_bytes())
+    }
+
+    /// The flags of `ifinfomsg` and the attributes of link.
+    pub(crate) fn link(&self) -> Result<(u32, Vec<u8>), CanError> {
+        let msg = NlMessage::new(RTM_GETLINK, 0, self.index, 0, 0);
+        let replies = request(msg).map_err(|e| CanError::OperationError(e.to_string()))?;
+        let reply = replies.into_iter()
+            .find(|r| r.len() >= IFINFO_SIZE)
+            .ok_or_else(|| CanError::OperationError(format!("netlink: no link of {}", self.name)))?;
+        let info = unsafe { ptr::read_unaligned(reply.as_ptr() as *const ifinfomsg) };
+
+        Ok((info.ifi_flags, reply[IFINFO_SIZE..].to_vec()))
+    }
+
+    fn set_flags(&self, flags: u32, change: u32) -> Result<(), CanError> {
+        let msg = NlMessage::new(RTM_NEWLINK, 0, self.index, flags, change);
+        request(msg).map_err(|e| CanError::OperationError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn set_can_attr(&self, r#type: u16, value: &[u8]) -> Result<(), CanError> {
+        let mut msg = NlMessage::new(RTM_NEWLINK, 0, self.index, 0, 0);
+        let linkinfo = msg.begin_nested(IFLA_LINKINFO);
+        msg.push_str(IFLA_INFO_KIND, "can");
+        let data = msg.begin_nested(IFLA_INFO_DATA);
+        msg.push_attr(r#type, value);
+        msg.end_nested(data);
+        msg.end_nested(linkinfo);
+        request(msg).map_err(|e| CanError::OperationError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// The state of CAN controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanState {
+    ErrorActive,
+    ErrorWarning,
+    ErrorPassive,
+    BusOff,
+    Stopped,
+    Sleeping,
+}
+
+impl CanState {
+    fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(Self::ErrorActive),
+            1 => Some(Self::ErrorWarning),
+            2 => Some(Self::ErrorPassive),
+            3 => Some(Self::BusOff),
+            4 => Some(Self::Stopped),
+            5 => Some(Self::Sleeping),
+            _ => None,
+        }
+    }
+}
+
+/// The bit timing of controller, `sample_point` is in one-tenth of a percent and `tq` is in nanoseconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CanBitTiming {
+    pub bitrate: u32,
+    pub sample_point: u32,
+    pub tq: u32,
+    pub prop_seg: u32,
+    pub phase_seg1: u32,
+    pub phase_seg2: u32,
+    pub sjw: u32,
+    pub brp: u32,
+}
+
+impl From<can_bittiming> for CanBitTiming {
+    fn from(value: can_bittiming) -> Self {
+        Self {
+            bitrate: value.bitrate,
+            sample_point: value.sample_point,
+            tq: value.tq,
+            prop_seg: value.prop_seg,
+            phase_seg1: value.phase_seg1,
+            phase_seg2: value.phase_seg2,
+            sjw: value.sjw,
+            brp: value.brp,
+        }
+    }
+}
+
+/// The bit timing constants of controller hardware.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct CanBitTimingConst {
+    pub name: String,
+    pub tseg1_min: u32,
+    pub tseg1_max: u32,
+    pub tseg2_min: u32,
+    pub tseg2_max: u32,
+    pub sjw_max: u32,
+    pub brp_min: u32,
+    pub brp_max: u32,
+    pub brp_inc: u32,
+}
+
+impl From<can_bittiming_const> for CanBitTimingConst {
+    fn from(value: can_bittiming_const) -> Self {
+        let name = value.name.iter()
+            .take_while(|&&c| c != 0)
+            .map(|&c| c as u8 as char)
+            .collect();
+        Self {
+            name,
+            tseg1_min: value.tseg1_min,
+            tseg1_max: value.tseg1_max,
+            tseg2_min: value.tseg2_min,
+            tseg2_max: value.tseg2_max,
+            sjw_max: value.sjw_max,
+            brp_min: value.brp_min,
+            brp_max: value.brp_max,
+            brp_inc: value.brp_inc,
+        }
+    }
+}
+
+/// The TX and RX error counters of controller.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BerrCounter {
+    pub tx: u16,
+    pub rx: u16,
+}
+
+/// The statistics of CAN device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CanDeviceStats {
+    pub bus_error: u32,
+    pub error_warning: u32,
+    pub error_passive: u32,
+    pub bus_off: u32,
+    pub arbitration_lost: u32,
+    pub restarts: u32,
+}
+
+impl From<can_device_stats> for CanDeviceStats {
+    fn from(value: can_device_stats) -> Self {
+        Self {
+            bus_error: value.bus_error,
+            error_warning: value.error_warning,
+            error_passive: value.error_passive,
+            bus_off: value.bus_off,
+            arbitration_lost: value.arbitration_lost,
+            restarts: value.restarts,
+        }
+    }
+}
+
+/// The statistics of network interface, the leading fields of `rtnl_link_stats64`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinkStats {
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+impl LinkStats {
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let field = |i: usize| bytes.get(i * 8..(i + 1) * 8)
+            .map(|v| u64::from_ne_bytes([v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7]]));
+        Some(Self {
+            rx_packets: field(0)?,
+            tx_packets: field(1)?,
+            rx_bytes: field(2)?,
+            tx_bytes: field(3)?,
+            rx_errors: field(4)?,
+            tx_errors: field(5)?,
+            rx_dropped: field(6)?,
+            tx_dropped: field(7)?,
+        })
+    }
+}
+
+/// The details of interface, the CAN specific fields are `None` if the interface is virtual.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanInterfaceDetails {
+    pub name: String,
+    pub index: u32,
+    /// The kind of link, it's `None` for `slcan`.
+    pub kind: Option<String>,
+    pub is_up: bool,
+    pub mtu: Option<u32>,
+    pub state: Option<CanState>,
+    pub ctrl_modes: Option<CanCtrlModes>,
+    pub restart_ms: Option<u32>,
+    /// The termination resistance in ohm, it's `None` if not supported by controller.
+    pub termination: Option<u16>,
+    pub bittiming: Option<CanBitTiming>,
+    pub data_bittiming: Option<CanBitTiming>,
+    pub bittiming_const: Option<CanBitTimingConst>,
+    pub data_bittiming_const: Option<CanBitTimingConst>,
+    /// The clock frequency of controller in Hz.
+    pub clock: Option<u32>,
+    pub berr_counter: Option<BerrCounter>,
+    pub device_stats: Option<CanDeviceStats>,
+    pub stats: Option<LinkStats>,
+}
+
+/// The rtnetlink request of link.
+pub(crate) struct NlMessage(Vec<u8>);
+
+impl NlMessage {
+    pub(crate) fn new(r#type: u16, flags: c_int, index: u32, ifi_flags: u32, ifi_change: u32) -> Self {
+        static SEQUENCE: AtomicU32 = AtomicU32::new(1);
+
+        let mut header: nlmsghdr = unsafe { mem::zeroed() };
+        header.nlmsg_type = r#type;
+        header.nlmsg_flags = (NLM_F_REQUEST | NLM_F_ACK | flags) as u16;
+        header.nlmsg_seq = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        let mut msg = Self(as_bytes(&header).to_vec());
+        msg.push_ifinfo(index, ifi_flags, ifi_change);
+        msg
+    }
+
+    pub(crate) fn push_ifinfo(&mut self, index: u32, flags: u32, change: u32) {
+        let mut info: ifinfomsg = unsafe { mem::zeroed() };
+        info.ifi_family = AF_UNSPEC as u8;
+        info.ifi_index = index as c_int;
+        info.ifi_flags = flags;
+        info.ifi_change = change;
+        self.0.extend_from_slice(as_bytes(&info));
+    }
+
+    fn push_attr(&mut self, r#type: u16, value: &[u8]) {
+        self.0.extend_from_slice(&((NLA_HDR_SIZE + value.len()) as u16).to_ne_bytes());
+        self.0.extend_from_slice(&r#type.to_ne_bytes());
+        self.0.extend_from_slice(value);
+        self.align();
+    }
+
+    fn push_str(&mut self, r#type: u16, value: &str) {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.push(0);
+        self.push_attr(r#type, &bytes);
+    }
+
+    /// Begin the nested attribute, the position returned is passed to `end_nested`.
+    fn begin_nested(&mut self, r#type: u16) -> usize {
+        let pos = self.0.len();
+        self.push_attr(r#type, &[]);
+        pos
+    }
+
+    fn end_nested(&mut self, pos: usize) {
+        let len = (self.0.len() - pos) as u16;
+        self.0[pos..pos + 2].copy_from_slice(&len.to_ne_bytes());
+    }
+
+    #[inline]
+    fn align(&mut self) {
+        self.0.resize(align(self.0.len()), 0);
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        let len = self.0.len() as u32;
+        self.0[..4].copy_from_slice(&len.to_ne_bytes());
+        self.0
+    }
+}
+
+/// Send the request and receive the payloads of replies until acknowledged.
+pub(crate) fn request(msg: NlMessage) -> io::Result<Vec<Vec<u8>>> {
+    let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let bytes = msg.into_bytes();
+    let ret = unsafe { send(fd.as_raw_fd(), bytes.as_ptr() as *const c_void, bytes.len(), 0) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut replies = Vec::new();
+    // dump replies are up to 32KiB each.
+    let mut buffer = vec![0u64; 8192];
+    loop {
+        let size = mem::size_of_val(buffer.as_slice());
+        let rd = unsafe { recv(fd.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, size, 0) };
+        if rd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let data = unsafe { slice::from_raw_parts(buffer.as_ptr() as *const u8, rd as usize) };
+
+        let mut offset = 0;
+        while offset + NLMSG_HDR_SIZE <= data.len() {
+            let header = unsafe { ptr::read_unaligned(data[offset..].as_ptr() as *const nlmsghdr) };
+            let len = header.nlmsg_len as usize;
+            if len < NLMSG_HDR_SIZE || offset + len > data.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "netlink: truncated message"));
+            }
+            let payload = &data[offset + NLMSG_HDR_SIZE..offset + len];
+            match header.nlmsg_type as c_int {
+                NLMSG_ERROR => {
+                    let error = unsafe { ptr::read_unaligned(payload.as_ptr() as *const nlmsgerr) }.error;
+                    return if error == 0 { Ok(replies) } else { Err(io::Error::from_raw_os_error(-error)) };
+                },
+                NLMSG_DONE => return Ok(replies),
+                _ => replies.push(payload.to_vec()),
+            }
+            offset += align(len);
+        }
+    }
+}
+
+/// Find the attribute of type in attributes.
+pub(crate) fn find_attr(attrs: &[u8], r#type: u16) -> Option<&[u8]> {
+    let mut offset = 0;
+    while offset + NLA_HDR_SIZE <= attrs.len() {
+        let len = u16::from_ne_bytes([attrs[offset], attrs[offset + 1]]) as usize;
+        let t = u16::from_ne_bytes([attrs[offset + 2], attrs[offset + 3]]) & NLA_TYPE_MASK as u16;
+        if len < NLA_HDR_SIZE || offset + len > attrs.len() {
+            return None;
+        }
+        if t == r#type {
+            return Some(&attrs[offset + NLA_HDR_SIZE..offset + len]);
+        }
+        offset += align(len);
+    }
+
+    None
+}
+
+/// Read the attribute of type as structure, it's `None` if not found or too short.
+fn read_attr<T: Copy>(attrs: &[u8], r#type: u16) -> Option<T> {
+    find_attr(attrs, r#type)
+        .filter(|v| v.len() >= mem::size_of::<T>())
+        .map(|v| unsafe { ptr::read_unaligned(v.as_ptr() as *const T) })
+}
+
+#[inline(always)]
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+#[inline(always)]
+fn as_bytes<T>(value: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+fn bittiming(bitrate: u32, sample_point: Option<f32>) -> can_bittiming {
+    let mut timing: can_bittiming = unsafe { mem::zeroed() };
+    timing.bitrate = bitrate;
+    // in one-tenth of a percent.
+    timing.sample_point = sample_point.map(|v| (v * 1000.).round() as u32)
+        .unwrap_or_default();
+    timing
+}
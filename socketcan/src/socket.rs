@@ -3,6 +3,18 @@
 use std::{ffi::CString, fmt, io, mem, os::raw::{c_int, c_void}, time::Duration, ptr};
 use libc::*;
 
+/// Wait until the socket, fd, is readable, `false` is returned if timeout.
+pub fn raw_wait_readable(fd: c_int, timeout: Duration) -> io::Result<bool> {
+    let mut pfd = pollfd { fd, events: POLLIN, revents: 0 };
+    let timeout = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+    let ret = unsafe { poll(&mut pfd, 1, timeout) };
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret > 0)
+    }
+}
+
 /// Tries to open the CAN socket by the interface number.
 pub fn raw_open_socket(addr: &CanAddr) -> io::Result<c_int> {
     let fd = unsafe { socket(PF_CAN, SOCK_RAW, CAN_RAW) };
@@ -22,6 +34,127 @@ pub fn raw_open_socket(addr: &CanAddr) -> io::Result<c_int> {
     }
 }
 
+/// Tries to open the CAN broadcast manager socket by the interface number.
+pub fn bcm_open_socket(addr: &CanAddr) -> io::Result<c_int> {
+    let fd = unsafe { socket(PF_CAN, SOCK_DGRAM, CAN_BCM) };
+
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let ret = unsafe { connect(fd, addr.as_sockaddr_ptr(), CanAddr::len() as u32) };
+
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        unsafe { close(fd) };
+        Err(err)
+    } else {
+        Ok(fd)
+    }
+}
+
+/// The socket option level of CAN ISO-TP, the options are not defined by libc yet.
+pub const SOL_CAN_ISOTP: c_int = SOL_CAN_BASE + CAN_ISOTP;
+pub const CAN_ISOTP_OPTS: c_int = 1;
+pub const CAN_ISOTP_RECV_FC: c_int = 2;
+pub const CAN_ISOTP_TX_STMIN: c_int = 3;
+pub const CAN_ISOTP_RX_STMIN: c_int = 4;
+pub const CAN_ISOTP_LL_OPTS: c_int = 5;
+
+/// The flags of `CanIsoTpOptions`.
+pub const CAN_ISOTP_LISTEN_MODE: u32 = 0x0001;
+pub const CAN_ISOTP_EXTEND_ADDR: u32 = 0x0002;
+pub const CAN_ISOTP_TX_PADDING: u32 = 0x0004;
+pub const CAN_ISOTP_RX_PADDING: u32 = 0x0008;
+pub const CAN_ISOTP_CHK_PAD_LEN: u32 = 0x0010;
+pub const CAN_ISOTP_CHK_PAD_DATA: u32 = 0x0020;
+pub const CAN_ISOTP_HALF_DUPLEX: u32 = 0x0040;
+pub const CAN_ISOTP_FORCE_TXSTMIN: u32 = 0x0080;
+pub const CAN_ISOTP_FORCE_RXSTMIN: u32 = 0x0100;
+pub const CAN_ISOTP_RX_EXT_ADDR: u32 = 0x0200;
+pub const CAN_ISOTP_WAIT_TX_DONE: u32 = 0x0400;
+pub const CAN_ISOTP_SF_BROADCAST: u32 = 0x0800;
+pub const CAN_ISOTP_CF_BROADCAST: u32 = 0x1000;
+
+/// The `can_isotp_options` of kernel.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CanIsoTpOptions {
+    pub flags: u32,
+    /// The frame transmission time in nanoseconds.
+    pub frame_txtime: u32,
+    pub ext_address: u8,
+    pub txpad_content: u8,
+    pub rxpad_content: u8,
+    pub rx_ext_address: u8,
+}
+
+/// The `can_isotp_fc_options` of kernel.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CanIsoTpFcOptions {
+    pub bs: u8,
+    pub stmin: u8,
+    pub wftmax: u8,
+}
+
+/// The `can_isotp_ll_options` of kernel.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CanIsoTpLlOptions {
+    /// `CAN_MTU` or `CANFD_MTU`.
+    pub mtu: u8,
+    /// The max data length of transmitted frames.
+    pub tx_dl: u8,
+    /// The flags of transmitted CAN-FD frames.
+    pub tx_flags: u8,
+}
+
+/// Tries to open the CAN ISO-TP socket, the options are set by `setup` before bound.
+pub fn isotp_open_socket<F>(addr: &CanAddr, setup: F) -> io::Result<c_int>
+where
+    F: FnOnce(c_int) -> io::Result<()>,
+{
+    let fd = unsafe { socket(PF_CAN, SOCK_DGRAM, CAN_ISOTP) };
+
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if let Err(e) = setup(fd) {
+        unsafe { close(fd) };
+        return Err(e);
+    }
+
+    let ret = unsafe { bind(fd, addr.as_sockaddr_ptr(), CanAddr::len() as u32) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        unsafe { close(fd) };
+        Err(err)
+    } else {
+        Ok(fd)
+    }
+}
+
+/// Tries to open the CAN J1939 socket bound to the NAME, address and PGN of `addr`.
+pub fn j1939_open_socket(addr: &CanAddr) -> io::Result<c_int> {
+    let fd = unsafe { socket(PF_CAN, SOCK_DGRAM, CAN_J1939) };
+
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let ret = unsafe { bind(fd, addr.as_sockaddr_ptr(), CanAddr::len() as u32) };
+
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        unsafe { close(fd) };
+        Err(err)
+    } else {
+        Ok(fd)
+    }
+}
+
 // Enable or disable FD mode on the socket, fd.
 pub fn set_fd_mode(fd: c_int, enable: bool) -> io::Result<c_int> {
     let enable = enable as c_int;
@@ -43,6 +176,27 @@ pub fn set_fd_mode(fd: c_int, enable: bool) -> io::Result<c_int> {
     }
 }
 
+/// The socket option of CAN XL VCID(since Linux 6.9), it's not defined by libc yet.
+pub const CAN_RAW_XL_VCID_OPTS: c_int = 8;
+/// Set the VCID of transmitted frames to `tx_vcid`.
+pub const CAN_RAW_XL_VCID_TX_SET: u8 = 0x01;
+/// Pass the VCID of transmitted frames as it is.
+pub const CAN_RAW_XL_VCID_TX_PASS: u8 = 0x02;
+/// Receive the frames whose VCID matches `rx_vcid` and `rx_vcid_mask`.
+pub const CAN_RAW_XL_VCID_RX_FILTER: u8 = 0x04;
+/// The offset of VCID in the priority field of `canxl_frame`.
+pub const CANXL_VCID_OFFSET: u32 = 16;
+
+/// The `can_raw_vcid_options` of kernel.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CanRawVcidOptions {
+    pub flags: u8,
+    pub tx_vcid: u8,
+    pub rx_vcid: u8,
+    pub rx_vcid_mask: u8,
+}
+
 // Write a single frame of any type to the socket, fd.
 pub fn raw_write_frame<T>(fd: c_int, frame_ptr: *const T, n: usize) -> io::Result<()> {
     let ret = unsafe { write(fd, frame_ptr.cast(), n) };
@@ -54,6 +208,126 @@ pub fn raw_write_frame<T>(fd: c_int, frame_ptr: *const T, n: usize) -> io::Resul
     }
 }
 
+/// Write a single frame of any type to the socket, fd, by `send` without blocking.
+pub fn raw_send_frame(fd: c_int, frame: &[u8]) -> io::Result<()> {
+    let ret = unsafe { send(fd, frame.as_ptr().cast(), frame.len(), MSG_DONTWAIT) };
+
+    if ret as usize == frame.len() {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Read a single frame of any type from the socket, fd, by `recvmsg`.
+///
+/// The length read and the kernel timestamp in nanoseconds are returned,
+/// the timestamp is `None` unless `SO_TIMESTAMPNS` or `SO_TIMESTAMPING` is enabled.
+pub fn raw_read_frame(fd: c_int, buffer: &mut [u8]) -> io::Result<(usize, Option<u64>)> {
+    let mut iov = iovec {
+        iov_base: buffer.as_mut_ptr().cast(),
+        iov_len: buffer.len(),
+    };
+    let mut control = [0u64; CONTROL_SIZE];
+    let mut msg = msghdr_new(&mut iov, &mut control);
+
+    let rd = unsafe { recvmsg(fd, &mut msg, 0) };
+    if rd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((rd as usize, cmsg_timestamp(&msg)))
+}
+
+/// Read frames from the socket, fd, by `recvmmsg` without blocking, a frame per buffer.
+///
+/// The length read and the kernel timestamp of frames are returned,
+/// and it's empty if no frame is available.
+pub fn raw_read_frames<const N: usize>(fd: c_int, buffers: &mut [[u8; N]]) -> io::Result<Vec<(usize, Option<u64>)>> {
+    let mut iovs = buffers.iter_mut()
+        .map(|b| iovec { iov_base: b.as_mut_ptr().cast(), iov_len: N })
+        .collect::<Vec<_>>();
+    let mut controls = vec![[0u64; CONTROL_SIZE]; buffers.len()];
+    let mut msgs = iovs.iter_mut()
+        .zip(controls.iter_mut())
+        .map(|(iov, control)| mmsghdr { msg_hdr: msghdr_new(iov, control), msg_len: 0 })
+        .collect::<Vec<_>>();
+
+    let count = unsafe { recvmmsg(fd, msgs.as_mut_ptr(), msgs.len() as c_uint, MSG_DONTWAIT, ptr::null_mut()) };
+    if count < 0 {
+        let err = io::Error::last_os_error();
+        return if err.kind() == io::ErrorKind::WouldBlock { Ok(Vec::new()) } else { Err(err) };
+    }
+
+    Ok(msgs.iter()
+        .take(count as usize)
+        .map(|m| (m.msg_len as usize, cmsg_timestamp(&m.msg_hdr)))
+        .collect())
+}
+
+/// Write frames to the socket, fd, by `sendmmsg`, the count of frames written is returned.
+pub fn raw_write_frames(fd: c_int, frames: &[&[u8]]) -> io::Result<usize> {
+    let mut iovs = frames.iter()
+        .map(|f| iovec { iov_base: f.as_ptr() as *mut c_void, iov_len: f.len() })
+        .collect::<Vec<_>>();
+    let mut msgs = iovs.iter_mut()
+        .map(|iov| {
+            let mut msg: msghdr = unsafe { mem::zeroed() };
+            msg.msg_iov = iov;
+            msg.msg_iovlen = 1;
+            mmsghdr { msg_hdr: msg, msg_len: 0 }
+        })
+        .collect::<Vec<_>>();
+
+    let count = unsafe { sendmmsg(fd, msgs.as_mut_ptr(), msgs.len() as c_uint, 0) };
+    if count < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(count as usize)
+    }
+}
+
+/// The size in `u64` of control buffer, it's enough for the timestamps and aligned for `cmsghdr`.
+const CONTROL_SIZE: usize = 16;
+
+fn msghdr_new(iov: &mut iovec, control: &mut [u64; CONTROL_SIZE]) -> msghdr {
+    let mut msg: msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = mem::size_of_val(control) as _;
+    msg
+}
+
+/// The kernel timestamp in nanoseconds of a received message.
+fn cmsg_timestamp(msg: &msghdr) -> Option<u64> {
+    let nanos = |t: timespec| t.tv_sec as u64 * 1_000_000_000 + t.tv_nsec as u64;
+    let mut timestamp = None;
+    unsafe {
+        let mut cmsg = CMSG_FIRSTHDR(msg);
+        while !cmsg.is_null() {
+            let data = CMSG_DATA(cmsg) as *const timespec;
+            match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+                (SOL_SOCKET, SCM_TIMESTAMPNS) => timestamp = Some(nanos(ptr::read_unaligned(data))),
+                (SOL_SOCKET, SCM_TIMESTAMPING) => {
+                    // software, deprecated and raw hardware timestamps.
+                    let hardware = ptr::read_unaligned(data.add(2));
+                    timestamp = if hardware.tv_sec != 0 || hardware.tv_nsec != 0 {
+                        Some(nanos(hardware))
+                    }
+                    else {
+                        Some(nanos(ptr::read_unaligned(data)))
+                    };
+                },
+                _ => {},
+            }
+            cmsg = CMSG_NXTHDR(msg, cmsg);
+        }
+    }
+
+    timestamp
+}
+
 /// `setsockopt` wrapper
 ///
 /// The libc `setsockopt` function is set to set various options on a socket.
@@ -136,6 +410,13 @@ pub fn canfd_frame_default() -> canfd_frame {
     unsafe { mem::zeroed() }
 }
 
+/// Creates a default C `canxl_frame`.
+/// This initializes the entire structure to zeros.
+#[inline(always)]
+pub fn canxl_frame_default() -> canxl_frame {
+    unsafe { mem::zeroed() }
+}
+
 /// Check an error return value for timeouts.
 ///
 /// Due to the fact that timeouts are reported as errors, calling `read_frame`
@@ -210,6 +491,27 @@ impl CanAddr {
         }
     }
 
+    /// Set the receive and transmit identifiers of transport protocol, like ISO-TP.
+    pub fn set_tp(&mut self, rx_id: canid_t, tx_id: canid_t) -> &mut Self {
+        self.0.can_addr.tp.rx_id = rx_id;
+        self.0.can_addr.tp.tx_id = tx_id;
+        self
+    }
+
+    /// Set the NAME, PGN and address of J1939.
+    pub fn set_j1939(&mut self, name: u64, pgn: u32, addr: u8) -> &mut Self {
+        self.0.can_addr.j1939.name = name;
+        self.0.can_addr.j1939.pgn = pgn;
+        self.0.can_addr.j1939.addr = addr;
+        self
+    }
+
+    /// Gets the NAME, PGN and address of J1939.
+    pub fn j1939(&self) -> (u64, u32, u8) {
+        let addr = unsafe { self.0.can_addr.j1939 };
+        (addr.name, addr.pgn, addr.addr)
+    }
+
     /// Gets the address of the structure as a `sockaddr_can` pointer.
     pub fn as_ptr(&self) -> *const sockaddr_can {
         &self.0
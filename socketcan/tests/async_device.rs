@@ -0,0 +1,46 @@
+#![cfg(feature = "async")]
+
+use futures::{SinkExt, StreamExt};
+use rs_can::{AsyncCanDevice, CanError, CanFrame, ChannelConfig, DeviceBuilder};
+use socketcan_rs::{CanMessage, SocketCan};
+
+#[tokio::test]
+#[ignore]   // vcan required
+async fn test_async_device() -> anyhow::Result<(), CanError> {
+    let iface = "vcan0";
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(iface, ChannelConfig::new(0));
+    let sender = builder.build::<SocketCan>()?;
+    let receiver = SocketCan::new();
+    receiver.init_channel(iface, false)?;
+
+    let mut message = CanMessage::new(0x123, &[0x01, 0x02]).unwrap();
+    message.set_channel(iface.into());
+    sender.transmit(message).await?;
+    let frames = receiver.receive(iface.into()).await?;
+    assert_eq!(frames[0].id().into_bits(), 0x123);
+    assert_eq!(frames[0].channel(), iface);
+
+    let mut sink = sender.sink();
+    for id in 0x200..0x205 {
+        let mut message = CanMessage::new(id, &[id as u8]).unwrap();
+        message.set_channel(iface.into());
+        sink.send(message).await?;
+    }
+    let ids = receiver.stream(iface.into())
+        .take(5)
+        .map(|r| r.map(|f| f.id().into_bits()))
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(ids, (0x200..0x205).collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_not_opened() {
+    let device = SocketCan::new();
+    assert!(matches!(device.receive("vcan-none".into()).await, Err(CanError::OperationError(_))));
+}
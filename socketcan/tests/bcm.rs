@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+use rs_can::{CanError, CanFrame};
+use socketcan_rs::{BcmEvent, BcmSocket, CanMessage};
+
+#[test]
+#[ignore]   // vcan required
+fn test_bcm() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let sender = BcmSocket::open(iface)?;
+    let receiver = BcmSocket::open(iface)?;
+
+    receiver.rx_setup(0x123, Some(&[0xFF, 0x00]), Some(Duration::from_millis(100)), None)?;
+
+    let mut message = CanMessage::new(0x123, &[0x01, 0x02]).unwrap();
+    message.set_channel(iface.into());
+    sender.tx_setup(&[message.clone()], 0, Duration::ZERO, Duration::from_millis(10))?;
+
+    match receiver.read_event(Some(Duration::from_millis(100)))? {
+        BcmEvent::Changed(frame) => assert_eq!(frame.data(), &[0x01, 0x02]),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    // the unmasked byte changed is not notified.
+    let mut message = CanMessage::new(0x123, &[0x01, 0x03]).unwrap();
+    message.set_channel(iface.into());
+    sender.tx_update(&[message])?;
+    assert!(matches!(receiver.read_event(Some(Duration::from_millis(50))), Err(CanError::TimeoutError(_))));
+
+    let mut message = CanMessage::new(0x123, &[0x04, 0x03]).unwrap();
+    message.set_channel(iface.into());
+    sender.tx_update(&[message])?;
+    match receiver.read_event(Some(Duration::from_millis(100)))? {
+        BcmEvent::Changed(frame) => assert_eq!(frame.data(), &[0x04, 0x03]),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    sender.tx_delete(0x123)?;
+    assert!(matches!(receiver.read_event(Some(Duration::from_millis(500)))?, BcmEvent::Timeout(_)));
+    receiver.rx_delete(0x123)?;
+
+    Ok(())
+}
+
+#[test]
+#[ignore]   // vcan required
+fn test_read_timeout() -> anyhow::Result<()> {
+    let socket = BcmSocket::open("vcan0")?;
+    let start = Instant::now();
+    assert!(matches!(socket.read_event(Some(Duration::from_millis(50))), Err(CanError::TimeoutError(_))));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    Ok(())
+}
+
+#[test]
+fn test_open() {
+    assert!(matches!(BcmSocket::open("not-exist"), Err(CanError::InitializeError(_))));
+}
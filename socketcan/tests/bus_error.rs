@@ -0,0 +1,48 @@
+use std::io::Cursor;
+use rs_can::CanError;
+use socketcan_rs::{CanBusError, CanErrorEvent, CandumpReader, ControllerProblems, ErrorCounters, ProtocolLocation, ProtocolViolations, TransceiverStatus};
+
+const LOG: &str = r#"(1700000000.100000) can0 20000004#0004000000000000
+(1700000000.200000) can0 20000088#0000881900000000
+(1700000000.300000) can0 20000242#0A0000000000507F
+(1700000000.400000) can0 20000120#0000000000000000
+(1700000000.500000) can0 20000010#0000000070000000
+(1700000000.600000) can0 123#11
+"#;
+
+#[test]
+fn test_error_event() -> anyhow::Result<()> {
+    let frames = CandumpReader::new(Cursor::new(LOG))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let event = CanErrorEvent::try_from(&frames[0])?;
+    assert_eq!(event.channel, "can0");
+    assert_eq!(event.timestamp, 1_700_000_000_100_000_000);
+    assert_eq!(event.errors, [CanBusError::Controller(ControllerProblems::RX_WARNING)]);
+    assert_eq!(event.counters, None);
+
+    let event = CanErrorEvent::try_from(&frames[1])?;
+    assert_eq!(event.errors, [
+        CanBusError::Protocol {
+            violations: ProtocolViolations::BIT0 | ProtocolViolations::TX,
+            location: ProtocolLocation::AckSlot,
+        },
+        CanBusError::BusError,
+    ]);
+
+    let event = CanErrorEvent::try_from(&frames[2])?;
+    assert_eq!(event.errors, [CanBusError::LostArbitration(Some(10)), CanBusError::BusOff]);
+    assert_eq!(event.counters, Some(ErrorCounters { tx: 0x50, rx: 0x7F }));
+    assert!(event.is_bus_off());
+
+    let event = CanErrorEvent::try_from(&frames[3])?;
+    assert_eq!(event.errors, [CanBusError::NoAck, CanBusError::Restarted]);
+    assert!(!event.is_bus_off());
+
+    let event = CanErrorEvent::try_from(frames[4].clone())?;
+    assert_eq!(event.errors, [CanBusError::Transceiver(TransceiverStatus::CanLShortToGnd)]);
+
+    assert!(matches!(CanErrorEvent::try_from(&frames[5]), Err(CanError::OperationError(_))));
+
+    Ok(())
+}
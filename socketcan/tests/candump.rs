@@ -0,0 +1,69 @@
+use std::io::Cursor;
+use rs_can::{CanDirect, CanFrame, CanId, CanType};
+use socketcan_rs::{CandumpReader, CandumpWriter, CanMessage};
+
+const LOG: &str = r#"(1700000000.123456) can0 123#DEADBEEF
+(1700000000.200000) can0 12345678#0102
+(1700000000.300000) can1 7DF#R
+(1700000000.400000) can1 7DF#R3
+(1700000000.500000) can0 7E8##3000102030405060708090A0B
+(1700000000.600000) can0 20000004#0004000000000000
+(1700000001.000000) vcan0 123#11.22.33 T
+"#;
+
+#[test]
+fn test_reader() -> anyhow::Result<()> {
+    let frames = CandumpReader::new(Cursor::new(LOG))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(frames.len(), 7);
+
+    assert_eq!(frames[0].timestamp(), 1_700_000_000_123);
+    assert_eq!(frames[0].timestamp_ns(), 1_700_000_000_123_456_000);
+    assert_eq!(frames[0].channel(), "can0");
+    assert_eq!(frames[0].id(), CanId::Standard(0x123));
+    assert_eq!(frames[0].data(), &[0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(frames[0].direct(), CanDirect::Receive);
+
+    assert_eq!(frames[1].id(), CanId::Extended(0x12345678));
+    assert!(frames[2].is_remote());
+    assert_eq!(frames[2].length(), 0);
+    assert_eq!(frames[3].length(), 3);
+
+    assert_eq!(frames[4].can_type(), CanType::CanFd);
+    assert!(frames[4].is_bitrate_switch());
+    assert!(frames[4].is_esi());
+    assert_eq!(frames[4].length(), 12);
+
+    assert!(frames[5].is_error_frame());
+    assert_eq!(frames[5].id().into_bits(), 0x04);
+
+    assert_eq!(frames[6].data(), &[0x11, 0x22, 0x33]);
+    assert_eq!(frames[6].direct(), CanDirect::Transmit);
+
+    Ok(())
+}
+
+#[test]
+fn test_round_trip() -> anyhow::Result<()> {
+    let frames = CandumpReader::new(Cursor::new(LOG))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut writer = CandumpWriter::new(Vec::new());
+    for frame in &frames {
+        writer.write(frame)?;
+    }
+    let content = String::from_utf8(writer.into_inner())?;
+    assert!(content.starts_with("(1700000000.123456) can0 123#DEADBEEF\n"));
+    assert!(content.contains("(1700000000.500000) can0 7E8##3000102030405060708090A0B\n"));
+    assert!(content.contains("(1700000000.600000) can0 20000004#0004000000000000\n"));
+
+    let results = CandumpReader::new(Cursor::new(content))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(results, frames);
+
+    let msg = CanMessage::from_candump("(1700000000.300000) can1 00000123#R2")?;
+    assert!(msg.is_extended());
+    assert_eq!(msg.to_candump(), "(1700000000.300000) can1 00000123#R2");
+
+    Ok(())
+}
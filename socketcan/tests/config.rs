@@ -0,0 +1,75 @@
+use rs_can::{CanError, CanFilter, DeviceConfig, registry};
+use socketcan_rs::{CanCtrlModes, SocketCanConfig, TimestampSource, SOCKETCAN, CANFD, CTRL_MODES, FILTERS, RECEIVE_LIMIT, SAMPLE_POINT, TIMESTAMP_SOURCE};
+
+const CONFIG: &str = r#"
+receive_limit: 16
+channels:
+  can0:
+    bitrate: 500000
+    dbitrate: 2000000
+    sample_point: 0.8
+    ctrl_modes: FD | BERR_REPORTING
+    canfd: true
+    timestamp_source: Hardware
+    filters:
+      - can_id: 0x7E8
+        can_mask: 0x7FF
+        extended: false
+  vcan0: {}
+"#;
+
+#[test]
+fn test_config() -> anyhow::Result<()> {
+    let cfg: SocketCanConfig = serde_yaml::from_str(CONFIG)?;
+    assert_eq!(cfg.receive_limit, Some(16));
+    let can0 = &cfg.channels["can0"];
+    assert_eq!(can0.bitrate, 500_000);
+    assert_eq!(can0.ctrl_modes, Some(CanCtrlModes::FD | CanCtrlModes::BERR_REPORTING));
+    assert_eq!(can0.timestamp_source, Some(TimestampSource::Hardware));
+    assert_eq!(cfg.channels["vcan0"], Default::default());
+
+    let loaded: SocketCanConfig = serde_yaml::from_str(&serde_yaml::to_string(&cfg)?)?;
+    assert_eq!(loaded, cfg);
+
+    Ok(())
+}
+
+#[test]
+fn test_builder() -> anyhow::Result<(), CanError> {
+    let cfg: SocketCanConfig = serde_yaml::from_str(CONFIG)
+        .map_err(|e| CanError::OtherError(e.to_string()))?;
+    let builder = cfg.builder();
+    assert_eq!(builder.get_other::<usize>(RECEIVE_LIMIT)?, Some(16));
+
+    let can0 = &builder.channel_configs()["can0"];
+    assert_eq!(can0.bitrate(), 500_000);
+    assert_eq!(can0.dbitrate(), Some(2_000_000));
+    assert_eq!(can0.get_other::<f32>(SAMPLE_POINT)?, Some(0.8));
+    assert_eq!(can0.get_other::<CanCtrlModes>(CTRL_MODES)?, Some(CanCtrlModes::FD | CanCtrlModes::BERR_REPORTING));
+    assert_eq!(can0.get_other::<bool>(CANFD)?, Some(true));
+    assert_eq!(can0.get_other::<TimestampSource>(TIMESTAMP_SOURCE)?, Some(TimestampSource::Hardware));
+    assert_eq!(can0.get_other::<Vec<CanFilter>>(FILTERS)?, Some(vec![CanFilter::from((0x7E8, 0x7FF))]));
+
+    let vcan0 = &builder.channel_configs()["vcan0"];
+    assert_eq!(vcan0.bitrate(), 0);
+    assert_eq!(vcan0.get_other::<bool>(CANFD)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_uri() -> anyhow::Result<(), CanError> {
+    let uri = "socketcan://vcan0,vcan1?bitrate=500000&canfd&receive_limit=8".parse()?;
+    let cfg = SocketCanConfig::from_uri(&uri)?;
+    assert_eq!(cfg.receive_limit, Some(8));
+    assert_eq!(cfg.channels.len(), 2);
+    assert_eq!(cfg.channels["vcan1"].bitrate, 500_000);
+    assert_eq!(cfg.channels["vcan1"].canfd, Some(true));
+    assert_eq!(cfg.channels["vcan1"].loopback, None);
+
+    socketcan_rs::register();
+    assert!(registry::backends().contains(&SOCKETCAN.to_string()));
+    assert!(registry::open("socketcan://can-none").is_err());
+
+    Ok(())
+}
@@ -1,5 +1,15 @@
-use rs_can::{CanDevice, CanError, CanFrame, DeviceBuilder};
-use socketcan_rs::{CanMessage, SocketCan};
+use rs_can::{can_utils, CanDevice, CanError, CanFrame, CanType, CanXlControl, ChannelConfig, DeviceBuilder};
+use socketcan_rs::{CanAnyFrame, CanMessage, SocketCan, TimestampSource, CANXL, RECEIVE_LIMIT, TIMESTAMP_SOURCE};
+
+fn messages_of(channel: &str, count: u8) -> Vec<CanMessage> {
+    (0..count)
+        .map(|i| {
+            let mut message = CanMessage::new(0x100 + i as u32, &[i]).unwrap();
+            message.set_channel(channel.to_string());
+            message
+        })
+        .collect()
+}
 
 #[test]
 fn test_driver() -> anyhow::Result<(), CanError> {
@@ -36,3 +46,149 @@ fn test_driver() -> anyhow::Result<(), CanError> {
 
     Ok(())
 }
+
+#[test]
+fn test_xl_frame() {
+    let data = (0..100).collect::<Vec<u8>>();
+    let mut message = CanMessage::new(0x24, &data).unwrap();
+    message.set_xl_control(CanXlControl { sdu_type: 0x03, vcid: 0x12, acceptance_field: 0xDEADBEEF, sec: true });
+    assert_eq!(message.can_type(), CanType::CanXl);
+
+    let frame: CanAnyFrame = message.clone().into();
+    assert_eq!(frame.size(), 12 + data.len());
+    if let CanAnyFrame::Xl(f) = &frame {
+        assert_eq!(f.prio, 0x12_0024);
+        assert_eq!(f.flags, 0x81);
+        assert_eq!(f.sdt, 0x03);
+        assert_eq!(f.af, 0xDEADBEEF);
+    }
+    else {
+        panic!("not a CAN XL frame");
+    }
+
+    let result = CanMessage::from(frame);
+    assert_eq!(result, message);
+    assert_eq!(result.can_type(), CanType::CanXl);
+    assert_eq!(result.xl_control(), message.xl_control());
+}
+
+#[test]
+#[ignore]   // vcan with CAN XL MTU required: `ip link set vcan0 mtu 2060`
+fn test_xl_driver() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let mut config = ChannelConfig::new(0);
+    config.add_other(CANXL, Box::new(true));
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(iface, config);
+    let device = builder.build::<SocketCan>()?;
+    device.set_recv_own_msgs(iface, true)?;
+    device.set_xl_vcid(iface, None, 0, 0)?;
+
+    let mut message = CanMessage::new(0x24, &[0x5A; 200]).unwrap();
+    message.set_channel(iface.to_string())
+        .set_xl_control(CanXlControl { sdu_type: 0x01, vcid: 0x05, acceptance_field: 0x1234, sec: false });
+    device.transmit(message.clone(), None)?;
+
+    let frames = device.receive(iface.to_string(), Some(100))?;
+    assert_eq!(frames[0], message);
+
+    Ok(())
+}
+
+#[test]
+#[ignore]   // vcan required
+fn test_timestamp_source() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let mut config = ChannelConfig::new(0);
+    config.add_other(TIMESTAMP_SOURCE, Box::new(TimestampSource::Kernel));
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(iface, config);
+    let device = builder.build::<SocketCan>()?;
+    device.set_recv_own_msgs(iface, true)?;
+
+    let mut message = CanMessage::new(0x123, &[0x01, 0x02]).unwrap();
+    message.set_channel(iface.to_string());
+    let before = can_utils::system_timestamp_ns();
+    device.transmit(message, None)?;
+
+    let frames = device.receive(iface.to_string(), Some(100))?;
+    let timestamp = frames[0].timestamp_ns();
+    assert!(timestamp >= before && timestamp <= can_utils::system_timestamp_ns());
+    assert_eq!(frames[0].timestamp(), timestamp / 1_000_000);
+
+    Ok(())
+}
+
+#[test]
+#[ignore]   // vcan required
+fn test_batch() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let mut builder = DeviceBuilder::new();
+    builder.add_config(iface, Default::default())
+        .add_other(RECEIVE_LIMIT, Box::new(16usize));
+    let device = builder.build::<SocketCan>()?;
+    device.set_recv_own_msgs(iface, true)?;
+
+    let messages = messages_of(iface, 20);
+    assert_eq!(device.write_batch(messages.clone())?, 20);
+
+    let frames = device.receive(iface.to_string(), Some(100))?;
+    assert_eq!(frames.len(), 16);
+    let rest = device.read_batch(iface, 16)?;
+    assert_eq!(rest.len(), 4);
+    assert!(device.read_batch(iface, 16)?.is_empty());
+    frames.iter()
+        .chain(rest.iter())
+        .zip(messages.iter())
+        .for_each(|(f, m)| {
+            assert_eq!(f, m);
+            assert_eq!(f.channel(), iface);
+        });
+
+    // the frames of channel not opened are skipped.
+    let mut message = CanMessage::new(0x200, &[0x01]).unwrap();
+    message.set_channel("not-opened".into());
+    let mut messages = vec![message];
+    messages.extend_from_slice(&messages_of(iface, 2));
+    assert_eq!(device.write_batch(messages)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_batch_not_opened() {
+    let device = SocketCan::new();
+    assert!(matches!(device.write_batch(messages_of("not-opened", 2)), Err(CanError::OperationError(_))));
+    assert_eq!(device.write_batch(Vec::new()).ok(), Some(0));
+}
+
+#[test]
+#[ignore]   // vcan required
+fn test_channels() -> anyhow::Result<()> {
+    let device = SocketCan::new();
+    let shared = device.clone();
+    shared.init_channel("vcan0", false)?;
+    assert_eq!(device.opened_channels(), ["vcan0"]);
+
+    let mut message = CanMessage::new(0x123, &[0x01, 0x02]).unwrap();
+    message.set_channel("vcan0".into());
+    device.transmit(message, None)?;
+
+    shared.close_channel("vcan0")?;
+    assert!(device.opened_channels().is_empty());
+    assert!(matches!(device.read("vcan0"), Err(CanError::OperationError(_))));
+    assert!(device.close_channel("vcan0").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_open_error() {
+    let device = SocketCan::new();
+    match device.init_channel("not-exist", false) {
+        Err(CanError::InitializeError(e)) => assert!(e.starts_with("not-exist: ")),
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert!(device.opened_channels().is_empty());
+    assert!(device.close_channel("not-exist").is_err());
+}
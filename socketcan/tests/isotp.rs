@@ -0,0 +1,44 @@
+use std::{thread, time::{Duration, Instant}};
+use rs_can::{CanError, isotp::{IsoTpConfig, StMin}, uds::Transport};
+use socketcan_rs::IsoTpSocket;
+
+#[test]
+#[ignore]   // vcan required
+fn test_isotp() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let mut config = IsoTpConfig::new(0x7E0, 0x7E8);
+    config.set_flow_control(4, StMin(0x01))
+        .set_functional_id(0x7DF);
+    let mut client = IsoTpSocket::open(iface, &config)?;
+    let server = IsoTpSocket::open(iface, &IsoTpConfig::new(0x7E8, 0x7E0))?;
+    let monitor = IsoTpSocket::listen(iface, &IsoTpConfig::new(0x7E8, 0x7E0))?;
+
+    let data = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+    let expected = data.clone();
+    let handle = thread::spawn(move || server.receive(Some(1000)));
+    Transport::send(&mut client, &data)?;
+    assert_eq!(handle.join().unwrap()?, expected);
+    assert_eq!(monitor.receive(Some(100))?, expected);
+
+    client.send_functional(&[0x3E, 0x80])?;
+    assert!(matches!(client.receive(Some(50)), Err(CanError::TimeoutError(_))));
+
+    Ok(())
+}
+
+#[test]
+#[ignore]   // vcan required
+fn test_receive_timeout() -> anyhow::Result<()> {
+    let socket = IsoTpSocket::open("vcan0", &IsoTpConfig::new(0x7E0, 0x7E8))?;
+    let start = Instant::now();
+    assert!(matches!(socket.receive(Some(50)), Err(CanError::TimeoutError(_))));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    Ok(())
+}
+
+#[test]
+fn test_open() {
+    let config = IsoTpConfig::new(0x7E0, 0x7E8);
+    assert!(matches!(IsoTpSocket::open("not-exist", &config), Err(CanError::InitializeError(_))));
+}
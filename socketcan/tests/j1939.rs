@@ -0,0 +1,63 @@
+use std::{thread, time::{Duration, Instant}};
+use rs_can::{CanError, j1939::{Name, DEFAULT_PRIORITY, GLOBAL_ADDRESS}};
+use socketcan_rs::{J1939Filter, J1939Socket};
+
+#[test]
+#[ignore]   // vcan and can-j1939 module required
+fn test_j1939() -> anyhow::Result<()> {
+    let iface = "vcan0";
+    let sender = J1939Socket::open(iface, None, Some(0x10), None)?;
+    let receiver = J1939Socket::open(iface, None, Some(0x20), None)?;
+    receiver.set_filters(&[J1939Filter::from_address(0x10)])?;
+
+    // broadcast by BAM.
+    let data = (0..100).collect::<Vec<u8>>();
+    sender.send(0xFEEC, DEFAULT_PRIORITY, GLOBAL_ADDRESS, &data)?;
+    let msg = receiver.receive(Some(2000))?;
+    assert_eq!(msg.pgn, 0xFEEC);
+    assert_eq!(msg.source, 0x10);
+    assert_eq!(msg.destination, GLOBAL_ADDRESS);
+    assert_eq!(msg.data, data);
+
+    // to destination by RTS/CTS.
+    let data = (0..=255).cycle().take(1785).collect::<Vec<u8>>();
+    let expected = data.clone();
+    let handle = thread::spawn(move || receiver.receive(Some(2000)));
+    sender.send(0xEF00, 3, 0x20, &data)?;
+    let msg = handle.join().unwrap()?;
+    assert_eq!(msg.pgn, 0xEF00);
+    assert_eq!(msg.priority, 3);
+    assert_eq!(msg.destination, 0x20);
+    assert_eq!(msg.data, expected);
+
+    // send to NAME resolved from address claim cache.
+    let mut name = Name::default();
+    name.set_identity_number(1);
+    let mut claimer = J1939Socket::open(iface, Some(name), None, None)?;
+    let observer = J1939Socket::open(iface, None, Some(0x30), None)?;
+    claimer.claim_address(0x80)?;
+    thread::sleep(Duration::from_millis(250));
+    assert_eq!(claimer.address(), Some(0x80));
+    observer.send_to_name(0xEF00, DEFAULT_PRIORITY, name, &[0x01, 0x02])?;
+    let msg = claimer.receive(Some(1000))?;
+    assert_eq!(msg.source, 0x30);
+    assert_eq!(msg.data, [0x01, 0x02]);
+
+    Ok(())
+}
+
+#[test]
+#[ignore]   // vcan and can-j1939 module required
+fn test_receive_timeout() -> anyhow::Result<()> {
+    let socket = J1939Socket::open("vcan0", None, Some(0x40), None)?;
+    let start = Instant::now();
+    assert!(matches!(socket.receive(Some(50)), Err(CanError::TimeoutError(_))));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    Ok(())
+}
+
+#[test]
+fn test_open() {
+    assert!(matches!(J1939Socket::open("not-exist", None, None, None), Err(CanError::InitializeError(_))));
+}
@@ -0,0 +1,47 @@
+use rs_can::{CanDirect, CanError, CanFrame, CanType, CanXlControl};
+use socketcan_rs::CanMessage;
+
+#[test]
+fn test_conversion() -> anyhow::Result<(), CanError> {
+    let mut msg = CanMessage::new(0x123, &[0x01; 20]).unwrap();
+    msg.set_channel("vcan0".into())
+        .set_timestamp_ns(Some(1_700_000_000_123_456_789))
+        .set_direct(CanDirect::Receive)
+        .set_bitrate_switch(true);
+
+    let common: rs_can::CanMessage = msg.clone().into();
+    assert_eq!(common.timestamp_ns(), 1_700_000_000_123_456_789);
+    assert_eq!(common.channel(), "vcan0");
+    assert_eq!(common.can_type(), CanType::CanFd);
+    assert!(common.is_bitrate_switch());
+
+    let other = CanMessage::try_from(common)?;
+    assert_eq!(other, msg);
+    assert_eq!(other.timestamp_ns(), msg.timestamp_ns());
+    assert_eq!(other.direct(), CanDirect::Receive);
+
+    Ok(())
+}
+
+#[test]
+fn test_conversion_xl() -> anyhow::Result<(), CanError> {
+    let ctrl = CanXlControl { sdu_type: 0x03, vcid: 0x05, acceptance_field: 0xAABB, sec: true };
+    let mut msg = CanMessage::new(0x10, &[0x02; 100]).unwrap();
+    msg.set_channel("vcan0".into())
+        .set_xl_control(ctrl);
+
+    let other = CanMessage::try_from(rs_can::CanMessage::from(msg.clone()))?;
+    assert_eq!(other, msg);
+    assert_eq!(other.can_type(), CanType::CanXl);
+    assert_eq!(other.xl_control(), ctrl);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_can_type() {
+    let mut msg = CanMessage::new(0x123, &[0x01; 20]).unwrap();
+    msg.set_can_type(CanType::Can);
+    assert_eq!(msg.length(), 8);
+    assert_eq!(msg.data(), &[0x01; 8]);
+}
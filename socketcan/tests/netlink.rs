@@ -0,0 +1,69 @@
+use rs_can::{CanError, ChannelConfig, DeviceBuilder};
+use socketcan_rs::{CanCtrlModes, CanInterface, SocketCan, CREATE_VCAN};
+
+#[test]
+#[ignore]   // CAP_NET_ADMIN and vcan, vxcan required
+fn test_interface() -> anyhow::Result<()> {
+    let iface = CanInterface::create_vcan("vcan-test")?;
+    assert_eq!(iface.kind()?.as_deref(), Some("vcan"));
+    assert_eq!(iface.bitrate()?, None);
+    iface.bring_up()?;
+    assert!(iface.is_up()?);
+    assert!(CanInterface::list()?.contains(&iface));
+    let details = iface.details()?;
+    assert_eq!(details.name, "vcan-test");
+    assert!(details.is_up);
+    assert_eq!(details.mtu, Some(16));
+    assert_eq!(details.state, None);
+    assert_eq!(details.termination, None);
+    assert_eq!(details.berr_counter, None);
+    assert!(details.stats.is_some());
+    iface.bring_down()?;
+    assert!(!iface.is_up()?);
+    // the virtual interface has no controller.
+    assert!(iface.set_bitrate(500_000, Some(0.875)).is_err());
+    assert!(iface.set_ctrl_modes(CanCtrlModes::LISTEN_ONLY, true).is_err());
+    assert!(iface.replace_ctrl_modes(CanCtrlModes::FD).is_err());
+    iface.delete()?;
+    assert!(CanInterface::open("vcan-test").is_err());
+
+    let (iface, peer) = CanInterface::create_vxcan("vxcan-test0", "vxcan-test1")?;
+    assert_eq!(peer.kind()?.as_deref(), Some("vxcan"));
+    iface.delete()?;
+    assert!(CanInterface::open("vxcan-test1").is_err());
+
+    let mut builder = DeviceBuilder::new();
+    let mut cfg = ChannelConfig::new(500_000);
+    cfg.add_other(CREATE_VCAN, Box::new(true));
+    builder.add_config("vcan-builder", cfg);
+    let device = builder.build::<SocketCan>()?;
+    let iface = CanInterface::open("vcan-builder")?;
+    assert!(iface.is_up()?);
+    drop(device);
+    iface.delete()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_open() -> anyhow::Result<()> {
+    assert!(matches!(CanInterface::open("not-exist"), Err(CanError::InitializeError(_))));
+
+    let lo = CanInterface::open("lo")?;
+    assert_eq!(lo.name(), "lo");
+    assert!(lo.index() > 0);
+    assert_eq!(lo.kind()?, None);
+    assert_eq!(lo.bitrate()?, None);
+    // the other interfaces are not listed.
+    assert!(!CanInterface::list()?.contains(&lo));
+
+    let details = lo.details()?;
+    assert_eq!(details.index, lo.index());
+    assert_eq!(details.kind, None);
+    assert!(details.is_up);
+    assert_eq!(details.mtu, Some(65536));
+    assert_eq!(details.bittiming, None);
+    assert!(details.stats.is_some());
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use std::{io::Write, os::{fd::AsRawFd, unix::net::UnixStream}, time::{Duration, Instant}};
+use socketcan_rs::raw_wait_readable;
+
+#[test]
+fn test_wait_readable() -> anyhow::Result<()> {
+    let (mut writer, reader) = UnixStream::pair()?;
+    let start = Instant::now();
+    assert!(!raw_wait_readable(reader.as_raw_fd(), Duration::from_millis(50))?);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    writer.write_all(&[0x01])?;
+    assert!(raw_wait_readable(reader.as_raw_fd(), Duration::from_millis(50))?);
+
+    Ok(())
+}
@@ -131,7 +131,8 @@ impl USBCANFD800UApi<'_> {
                 cmd_path.get_reference(), &state as *const c_uint as *const c_void)?;
         }
         // set channel protocol
-        let can_type = cfg.get_other::<u32>(CHANNEL_TYPE)?
+        let can_type = cfg.get_other::<u8>(CHANNEL_TYPE)?
+            .map(|v| v as u32)
             .unwrap_or(ZCanChlType::CANFD_ISO as u32);
         let cmd_path = CmdPath::new_reference(USBCANFD800UApi::REF_CONTROLLER_TYPE);
         self.self_set_reference(
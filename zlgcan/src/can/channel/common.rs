@@ -1,12 +1,12 @@
 use std::{collections::HashMap, fs::read_to_string, ffi::{c_uchar, c_uint, c_ushort}, path::PathBuf};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use rs_can::{CanError, ChannelConfig};
 use crate::can::{ZCanFilterType, constant::{BITRATE_CFG_FILENAME, TIMING0, TIMING1}};
 use crate::{ACC_CODE, ACC_MASK, CHANNEL_MODE, FILTER_TYPE};
 
 #[repr(C)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ZCanChlType {
     #[default]
     CAN = 0,
@@ -27,7 +27,7 @@ impl TryFrom<u8> for ZCanChlType {
 }
 
 #[repr(C)]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ZCanChlMode {
     #[default]
     Normal = 0,
@@ -1,10 +1,11 @@
 use std::fmt::{Display, Formatter};
-use rs_can::{CanDirect, CanFrame, CanId, CanType, MAX_FRAME_SIZE, MAX_FD_FRAME_SIZE, MAX_XL_FRAME_SIZE, can_utils};
+use rs_can::{CanDirect, CanError, CanFrame, CanId, CanType, MAX_FRAME_SIZE, MAX_FD_FRAME_SIZE, MAX_XL_FRAME_SIZE, can_utils};
 use crate::can::ZCanTxMode;
 
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct CanMessage {
+    /// The timestamp of device in microseconds.
     pub(crate) timestamp: u64,
     pub(crate) arbitration_id: u32,
     pub(crate) is_extended_id: bool,
@@ -116,6 +117,7 @@ impl CanFrame for CanMessage {
             },
         }
 
+        self.data.truncate(self.length);
         self.can_type = r#type;
         self
     }
@@ -232,3 +234,39 @@ impl Display for CanMessage {
         <dyn CanFrame<Channel=u8> as Display>::fmt(self, f)
     }
 }
+
+/// The transmit mode is not carried.
+impl From<CanMessage> for rs_can::CanMessage {
+    fn from(msg: CanMessage) -> Self {
+        let mut frame = Self::from_frame(&msg);
+        frame.set_timestamp_ns(Some(msg.timestamp.saturating_mul(1_000)));
+        frame
+    }
+}
+
+/// The channel must be a number, and the default transmit mode is used.
+impl TryFrom<rs_can::CanMessage> for CanMessage {
+    type Error = CanError;
+
+    fn try_from(msg: rs_can::CanMessage) -> Result<Self, Self::Error> {
+        let channel = msg.channel();
+        let channel = channel.parse::<u8>()
+            .map_err(|_| CanError::other_error(format!("`{}` not a channel number", channel)))?;
+
+        Ok(Self {
+            timestamp: msg.timestamp_ns() / 1_000,
+            arbitration_id: msg.id().into_bits(),
+            is_extended_id: msg.is_extended(),
+            is_remote_frame: msg.is_remote(),
+            is_error_frame: msg.is_error_frame(),
+            channel,
+            length: msg.length(),
+            data: msg.data().to_vec(),
+            can_type: msg.can_type(),
+            direct: msg.direct(),
+            bitrate_switch: msg.is_bitrate_switch(),
+            error_state_indicator: msg.is_esi(),
+            tx_mode: None,
+        })
+    }
+}
@@ -8,6 +8,7 @@ pub use channel::*;
 pub use frame::*;
 pub use message::*;
 
+use serde::{Deserialize, Serialize};
 use rs_can::CanError;
 
 #[allow(non_camel_case_types)]
@@ -28,7 +29,7 @@ impl TryFrom<u8> for ZCanFdStd {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ZCanFilterType {
     #[default]
     Double = 0,
@@ -6,9 +6,11 @@ pub use dev::*;
 pub use property::*;
 pub use typedef::*;
 
+use serde::{Deserialize, Serialize};
+
 /// The information about derive device.
 #[repr(C)]
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct DeriveInfo {
     pub(crate) canfd: bool,
     pub(crate) channels: u8,
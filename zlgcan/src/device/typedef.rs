@@ -1,9 +1,10 @@
 //! `typedef.rs` defined the zlgcan device type and some function supported feature.
+use serde::{Deserialize, Serialize};
 use rs_can::CanError;
 
 #[allow(non_camel_case_types, dead_code)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
 pub enum ZCanDeviceType {
     Undefined                          = 0,
     ZCAN_PCI5121                       = 1,
@@ -0,0 +1,143 @@
+//! The typed configuration of [`ZCanDriver`], it's converted to the options of [`DeviceBuilder`].
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use rs_can::{AnyCanDevice, CanError, ChannelConfig, DeviceBuilder, DeviceConfig, registry::{self, DeviceUri}};
+use crate::can::{ZCanChlMode, ZCanChlType, ZCanFilterType};
+use crate::constants::{ACC_CODE, ACC_MASK, BRP, CHANNEL_MODE, CHANNEL_TYPE, DERIVE_INFO, DEVICE_INDEX, DEVICE_TYPE, FILTER_TYPE, LIBPATH};
+use crate::device::{DeriveInfo, ZCanDeviceType};
+use super::ZCanDriver;
+
+/// The name of [`ZCanDriver`] backend in [`registry`].
+pub const ZLGCAN: &str = "zlgcan";
+
+/// The typed configuration of a ZLG CAN channel.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ZCanChannelConfig {
+    pub bitrate: u32,
+    pub dbitrate: Option<u32>,
+    pub resistance: Option<bool>,
+    pub channel_type: Option<ZCanChlType>,
+    pub channel_mode: Option<ZCanChlMode>,
+    pub filter_type: Option<ZCanFilterType>,
+    pub acc_code: Option<u32>,
+    pub acc_mask: Option<u32>,
+    pub brp: Option<u32>,
+}
+
+impl From<&ZCanChannelConfig> for ChannelConfig {
+    fn from(cfg: &ZCanChannelConfig) -> Self {
+        let mut config = ChannelConfig::new(cfg.bitrate);
+        if let Some(dbitrate) = cfg.dbitrate {
+            config.set_data_bitrate(dbitrate);
+        }
+        if let Some(resistance) = cfg.resistance {
+            config.set_resistance(resistance);
+        }
+        if let Some(v) = cfg.channel_type {
+            config.add_other(CHANNEL_TYPE, Box::new(v as u8));
+        }
+        if let Some(v) = cfg.channel_mode {
+            config.add_other(CHANNEL_MODE, Box::new(v as u8));
+        }
+        if let Some(v) = cfg.filter_type {
+            config.add_other(FILTER_TYPE, Box::new(v as u8));
+        }
+        if let Some(v) = cfg.acc_code {
+            config.add_other(ACC_CODE, Box::new(v));
+        }
+        if let Some(v) = cfg.acc_mask {
+            config.add_other(ACC_MASK, Box::new(v));
+        }
+        if let Some(v) = cfg.brp {
+            config.add_other(BRP, Box::new(v));
+        }
+
+        config
+    }
+}
+
+/// The typed configuration of [`ZCanDriver`], the channels are keyed by channel index.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ZCanConfig {
+    /// The directory of ZLG libraries.
+    pub libpath: String,
+    pub device_type: ZCanDeviceType,
+    #[serde(default)]
+    pub device_index: u32,
+    /// The information of derive device, it's required by the devices not defined by ZLG.
+    #[serde(default)]
+    pub derive: Option<DeriveInfo>,
+    #[serde(default)]
+    pub channels: HashMap<String, ZCanChannelConfig>,
+}
+
+impl DeviceConfig for ZCanConfig {
+    type Device = ZCanDriver;
+
+    fn builder(&self) -> DeviceBuilder {
+        let mut builder = DeviceBuilder::new();
+        builder.add_other(LIBPATH, Box::new(self.libpath.clone()))
+            .add_other(DEVICE_TYPE, Box::new(self.device_type as u32))
+            .add_other(DEVICE_INDEX, Box::new(self.device_index));
+        if let Some(derive) = self.derive {
+            builder.add_other(DERIVE_INFO, Box::new(derive));
+        }
+        for (chl, cfg) in &self.channels {
+            builder.add_config(chl.as_str(), cfg.into());
+        }
+
+        builder
+    }
+}
+
+impl ZCanConfig {
+    /// The configuration of device URI, like `zlgcan://0,1?libpath=/zlgcan/library&device_type=ZCAN_USBCANFD_200U&bitrate=500000`.
+    ///
+    /// The `device_type` is the name of [`ZCanDeviceType`], and the channels share the parameters
+    /// named as the fields of [`ZCanChannelConfig`], the enums of channel are in values.
+    /// The derive device is described by `derive_canfd` and `derive_channels`.
+    pub fn from_uri(uri: &DeviceUri) -> Result<Self, CanError> {
+        let libpath = uri.param("libpath")
+            .ok_or(CanError::other_error("`libpath` not found"))?;
+        let device_type = uri.param("device_type")
+            .ok_or(CanError::other_error("`device_type` not found"))?;
+        let device_type = serde_yaml::from_str::<ZCanDeviceType>(device_type)
+            .map_err(|_| CanError::OtherError(format!("invalid value of `device_type`: {}", device_type)))?;
+        let derive = match (uri.get("derive_canfd")?, uri.get("derive_channels")?) {
+            (None, None) => None,
+            (canfd, channels) => Some(DeriveInfo::new(canfd.unwrap_or_default(), channels.unwrap_or_default())),
+        };
+        let channel = ZCanChannelConfig {
+            bitrate: uri.get("bitrate")?.unwrap_or_default(),
+            dbitrate: uri.get("dbitrate")?,
+            resistance: uri.get("resistance")?,
+            channel_type: uri.get::<u8>("channel_type")?.map(ZCanChlType::try_from).transpose()?,
+            channel_mode: uri.get::<u8>("channel_mode")?.map(ZCanChlMode::try_from).transpose()?,
+            filter_type: uri.get::<u8>("filter_type")?.map(ZCanFilterType::try_from).transpose()?,
+            acc_code: uri.get("acc_code")?,
+            acc_mask: uri.get("acc_mask")?,
+            brp: uri.get("brp")?,
+        };
+
+        Ok(Self {
+            libpath: libpath.to_owned(),
+            device_type,
+            device_index: uri.get("device_index")?.unwrap_or_default(),
+            derive,
+            channels: uri.channels().iter()
+                .map(|c| (c.clone(), channel.clone()))
+                .collect(),
+        })
+    }
+}
+
+/// Register [`ZCanDriver`] to [`registry`] as [`ZLGCAN`].
+pub fn register() {
+    registry::register(ZLGCAN, open);
+}
+
+fn open(uri: &DeviceUri) -> Result<Box<dyn AnyCanDevice>, CanError> {
+    Ok(Box::new(ZCanConfig::from_uri(uri)?.build()?))
+}
@@ -5,6 +5,9 @@ use crate::constants;
 use crate::device::{DeriveInfo, Handler, ZCanDeviceType, ZChannelContext, ZDeviceInfo};
 use crate::lin::{ZLinChlCfg, ZLinFrame, ZLinPublish, ZLinPublishEx, ZLinSubscribe};
 
+mod config;
+pub use config::*;
+
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "windows")]
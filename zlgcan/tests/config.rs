@@ -0,0 +1,60 @@
+use rs_can::{CanError, DeviceConfig, registry};
+use zlgcan_rs::{can::{ZCanChlMode, ZCanChlType}, device::ZCanDeviceType, driver::{ZCanConfig, ZLGCAN}, CHANNEL_MODE, CHANNEL_TYPE, DEVICE_INDEX, DEVICE_TYPE, LIBPATH};
+
+const CONFIG: &str = r#"
+libpath: /projects/rust/rust-can/zlgcan/library
+device_type: ZCAN_USBCANFD_200U
+channels:
+  "0":
+    bitrate: 500000
+    dbitrate: 2000000
+    channel_type: CANFD_ISO
+    channel_mode: Normal
+  "1":
+    bitrate: 250000
+"#;
+
+#[test]
+fn test_config() -> anyhow::Result<(), CanError> {
+    let cfg: ZCanConfig = serde_yaml::from_str(CONFIG)
+        .map_err(|e| CanError::OtherError(e.to_string()))?;
+    assert_eq!(cfg.device_type, ZCanDeviceType::ZCAN_USBCANFD_200U);
+    assert_eq!(cfg.device_index, 0);
+    assert_eq!(cfg.derive, None);
+    assert_eq!(cfg.channels["0"].channel_type, Some(ZCanChlType::CANFD_ISO));
+
+    let builder = cfg.builder();
+    assert_eq!(builder.get_other::<String>(LIBPATH)?.as_deref(), Some("/projects/rust/rust-can/zlgcan/library"));
+    assert_eq!(builder.get_other::<u32>(DEVICE_TYPE)?, Some(ZCanDeviceType::ZCAN_USBCANFD_200U as u32));
+    assert_eq!(builder.get_other::<u32>(DEVICE_INDEX)?, Some(0));
+
+    let chl0 = &builder.channel_configs()["0"];
+    assert_eq!(chl0.bitrate(), 500_000);
+    assert_eq!(chl0.dbitrate(), Some(2_000_000));
+    assert_eq!(chl0.get_other::<u8>(CHANNEL_TYPE)?, Some(ZCanChlType::CANFD_ISO as u8));
+    assert_eq!(chl0.get_other::<u8>(CHANNEL_MODE)?, Some(ZCanChlMode::Normal as u8));
+    let chl1 = &builder.channel_configs()["1"];
+    assert_eq!(chl1.bitrate(), 250_000);
+    assert_eq!(chl1.get_other::<u8>(CHANNEL_TYPE)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_uri() -> anyhow::Result<(), CanError> {
+    let uri = "zlgcan://0,1?libpath=/zlgcan/library&device_type=ZCAN_USBCANFD_200U&bitrate=500000&channel_type=1".parse()?;
+    let cfg = ZCanConfig::from_uri(&uri)?;
+    assert_eq!(cfg.libpath, "/zlgcan/library");
+    assert_eq!(cfg.device_type, ZCanDeviceType::ZCAN_USBCANFD_200U);
+    assert_eq!(cfg.channels.len(), 2);
+    assert_eq!(cfg.channels["1"].bitrate, 500_000);
+    assert_eq!(cfg.channels["1"].channel_type, Some(ZCanChlType::CANFD_ISO));
+
+    assert!(ZCanConfig::from_uri(&"zlgcan://0?libpath=/zlgcan/library&device_type=USBCAN".parse()?).is_err());
+    assert!(ZCanConfig::from_uri(&"zlgcan://0?device_type=ZCAN_USBCANFD_200U".parse()?).is_err());
+
+    zlgcan_rs::driver::register();
+    assert!(registry::backends().contains(&ZLGCAN.to_string()));
+
+    Ok(())
+}
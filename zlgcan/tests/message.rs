@@ -0,0 +1,31 @@
+use rs_can::{CanDirect, CanError, CanFrame, CanType};
+use zlgcan_rs::can::CanMessage;
+
+#[test]
+fn test_conversion() -> anyhow::Result<(), CanError> {
+    let mut msg = CanMessage::new(0x18DA00F1, &[0x01; 12]).unwrap();
+    msg.set_channel(1)
+        .set_timestamp(Some(123_456))
+        .set_direct(CanDirect::Receive)
+        .set_esi(true);
+
+    // the timestamp of device is in microseconds.
+    let common: rs_can::CanMessage = msg.clone().into();
+    assert_eq!(common.timestamp_ns(), 123_456_000);
+    assert_eq!(common.timestamp(), 123);
+    assert_eq!(common.channel(), "1");
+    assert_eq!(common.can_type(), CanType::CanFd);
+
+    let other = CanMessage::try_from(common.clone())?;
+    assert_eq!(other, msg);
+    assert_eq!(other.timestamp(), 123_456);
+    assert_eq!(other.channel(), 1);
+    assert_eq!(other.direct(), CanDirect::Receive);
+    assert!(other.is_esi());
+
+    let mut common = common;
+    common.set_channel("can0".into());
+    assert!(CanMessage::try_from(common).is_err());
+
+    Ok(())
+}